@@ -0,0 +1,15 @@
+#!/bin/sh
+# Runs the Autobahn fuzzing client against the native echo server, the
+# report is written to reports/server/index.html
+set -e
+cd "$(dirname "$0")"
+
+cargo build --release -p autobahn
+../../target/release/autobahn 0.0.0.0:9001 &
+SERVER=$!
+trap 'kill $SERVER' EXIT
+
+docker run --rm --network host \
+    -v "$PWD:/config" -v "$PWD/reports:/reports" \
+    crossbario/autobahn-testsuite \
+    wstest -m fuzzingclient -s /config/fuzzingclient.json
@@ -0,0 +1,144 @@
+//! Native echo server for the Autobahn fuzzing client (`fuzzingclient.json`).
+//!
+//! The WebSocket modules are compiled from the wasm_oss sources, so the suite
+//! exercises the same framing, close handshake and permessage-deflate code
+//! that runs in U-Boot. Only the HTTP handshake is done here, as the hyper
+//! server of wasm_oss depends on the host sockets.
+use base64::prelude::*;
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+
+#[path = "../../wasm_oss/src/errors"]
+mod errors {
+    pub mod websocket_error;
+}
+
+#[path = "../../wasm_oss/src/asyncio/http"]
+mod http {
+    pub mod websocket;
+}
+
+/// Host functions used by the WebSocket modules
+mod ffi {
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    /// Milliseconds since the epoch, only used to seed the masking keys
+    pub unsafe fn env_now() -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_millis() as u64)
+    }
+}
+
+use crate::http::websocket::deflate::DeflateParams;
+use crate::http::websocket::handshake::accept_key;
+use crate::http::websocket::{Message, Role, WebSocket, WebSocketConfig};
+
+/// Largest request head accepted from the fuzzing client
+const MAX_REQUEST_HEAD: usize = 16 * 1024;
+
+/// Reads the upgrade request and answers it, returning the negotiated
+/// extension and the bytes read past the request head
+fn handshake(
+    stream: &mut TcpStream,
+    config: &WebSocketConfig,
+) -> Result<(Option<DeflateParams>, Vec<u8>), String> {
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 1024];
+    loop {
+        let n = stream.read(&mut chunk).map_err(|e| e.to_string())?;
+        if n == 0 {
+            return Err("connection closed during the handshake".to_string());
+        }
+        buf.extend_from_slice(&chunk[..n]);
+
+        let mut headers = [httparse::EMPTY_HEADER; 64];
+        let mut req = httparse::Request::new(&mut headers);
+        let head_len = match req.parse(&buf).map_err(|e| e.to_string())? {
+            httparse::Status::Complete(len) => len,
+            httparse::Status::Partial if buf.len() < MAX_REQUEST_HEAD => continue,
+            httparse::Status::Partial => return Err("request head too long".to_string()),
+        };
+
+        let header = |name: &str| {
+            let values: Vec<&str> = req
+                .headers
+                .iter()
+                .filter(|header| header.name.eq_ignore_ascii_case(name))
+                .filter_map(|header| std::str::from_utf8(header.value).ok())
+                .collect();
+            (!values.is_empty()).then(|| values.join(", "))
+        };
+        let key = header("Sec-WebSocket-Key")
+            .filter(|key| BASE64_STANDARD.decode(key).is_ok_and(|key| key.len() == 16))
+            .ok_or_else(|| "invalid Sec-WebSocket-Key".to_string())?;
+        let deflate = header("Sec-WebSocket-Extensions")
+            .filter(|_| config.compression)
+            .and_then(|offers| DeflateParams::accept_offer(&offers));
+
+        let mut response = format!(
+            "HTTP/1.1 101 Switching Protocols\r\n\
+             Upgrade: websocket\r\n\
+             Connection: Upgrade\r\n\
+             Sec-WebSocket-Accept: {}\r\n",
+            accept_key(&key)
+        );
+        if let Some(params) = &deflate {
+            response.push_str(&format!(
+                "Sec-WebSocket-Extensions: {}\r\n",
+                params.response()
+            ));
+        }
+        response.push_str("\r\n");
+        stream
+            .write_all(response.as_bytes())
+            .map_err(|e| e.to_string())?;
+
+        return Ok((deflate, buf.split_off(head_len)));
+    }
+}
+
+/// Echoes text and binary messages until the connection is closed
+fn serve(mut stream: TcpStream) {
+    let _ = stream.set_nodelay(true);
+    let config = WebSocketConfig::default();
+    let (deflate, read_buf) = match handshake(&mut stream, &config) {
+        Ok(accepted) => accepted,
+        Err(e) => {
+            eprintln!("Handshake failed: {}", e);
+            return;
+        }
+    };
+
+    let stream = futures::io::AllowStdIo::new(stream);
+    let mut ws = WebSocket::from_raw(stream, Role::Server, deflate, config, read_buf);
+    futures::executor::block_on(async {
+        while let Some(message) = ws.recv().await {
+            let result = match message {
+                Ok(message @ (Message::Text(_) | Message::Binary(_))) => ws.send(message).await,
+                Ok(_) => Ok(()),
+                Err(_) => break,
+            };
+            if result.is_err() {
+                break;
+            }
+        }
+    });
+}
+
+fn main() {
+    let addr = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| "127.0.0.1:9001".to_string());
+    let listener = TcpListener::bind(&addr).expect("failed to bind the listener");
+    println!("Echo server listening on ws://{}", addr);
+
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                std::thread::spawn(move || serve(stream));
+            }
+            Err(e) => eprintln!("Failed to accept a connection: {}", e),
+        }
+    }
+}
@@ -24,13 +24,15 @@ _sym_db = _symbol_database.Default()
 
 
 
-DESCRIPTOR = _descriptor_pool.Default().AddSerializedFile(b'\n\x0cschema.proto\x12\x06schema\"\x13\n\x11HelpClientRequest\"%\n\x12HelpClientResponse\x12\x0f\n\x07message\x18\x01 \x01(\t\"%\n\x12PrintClientRequest\x12\x0f\n\x07message\x18\x01 \x01(\t\"&\n\x13PrintClientResponse\x12\x0f\n\x07message\x18\x01 \x01(\t\"\x14\n\x12NonceClientRequest\"$\n\x13NonceClientResponse\x12\r\n\x05nonce\x18\x01 \x01(\t\"\x13\n\x11QuitClientRequest\"\x14\n\x12QuitClientResponse\"\x15\n\x13StatusClientRequest\"\x16\n\x14StatusClientResponse\"B\n\x12\x43hainClientRequest\x12\x14\n\x0cpayload_size\x18\x01 \x01(\x05\x12\x16\n\x0epayload_sha256\x18\x02 \x01(\t\"\x15\n\x13\x43hainClientResponse\"\x99\x01\n\x11\x42ootClientRequest\x12\x35\n\tboot_type\x18\x01 \x01(\x0e\x32\".schema.BootClientRequest.BootType\x12\x14\n\x0cpayload_size\x18\x02 \x01(\x05\x12\x16\n\x0epayload_sha256\x18\x03 \x01(\t\"\x1f\n\x08\x42ootType\x12\x13\n\x0f\x42OOT_TYPE_LINUX\x10\x00\"\x14\n\x12\x42ootClientResponse\"$\n\x13\x45rrorClientResponse\x12\r\n\x05\x65rror\x18\x01 \x01(\t\"\xa6\x04\n\rClientRequest\x12\x37\n\x05inner\x18\x01 \x01(\x0b\x32(.schema.ClientRequest.ClientRequestInner\x12*\n\tsignature\x18\x02 \x01(\x0b\x32\x15.schema.FullSignatureH\x00\x1a\x9d\x03\n\x12\x43lientRequestInner\x12\r\n\x05nonce\x18\x01 \x01(\t\x12\x31\n\x0chelp_request\x18\x02 \x01(\x0b\x32\x19.schema.HelpClientRequestH\x00\x12\x33\n\rprint_request\x18\x03 \x01(\x0b\x32\x1a.schema.PrintClientRequestH\x00\x12\x33\n\rnonce_request\x18\x04 \x01(\x0b\x32\x1a.schema.NonceClientRequestH\x00\x12\x31\n\x0cquit_request\x18\x05 \x01(\x0b\x32\x19.schema.QuitClientRequestH\x00\x12\x33\n\rchain_request\x18\x06 \x01(\x0b\x32\x1a.schema.ChainClientRequestH\x00\x12\x35\n\x0estatus_request\x18\x07 \x01(\x0b\x32\x1b.schema.StatusClientRequestH\x00\x12\x31\n\x0c\x62oot_request\x18\x08 \x01(\x0b\x32\x19.schema.BootClientRequestH\x00\x42\t\n\x07payloadB\x10\n\x0esignature_type\"\xf1\x04\n\x0e\x43lientResponse\x12\x39\n\x05inner\x18\x01 \x01(\x0b\x32*.schema.ClientResponse.ClientResponseInner\x12,\n\tsignature\x18\x02 \x01(\x0b\x32\x17.schema.ClientSignatureH\x00\x1a\xe3\x03\n\x13\x43lientResponseInner\x12\r\n\x05nonce\x18\x01 \x01(\t\x12\x35\n\x0e\x65rror_response\x18\x02 \x01(\x0b\x32\x1b.schema.ErrorClientResponseH\x00\x12\x33\n\rhelp_response\x18\x03 \x01(\x0b\x32\x1a.schema.HelpClientResponseH\x00\x12\x35\n\x0eprint_response\x18\x04 \x01(\x0b\x32\x1b.schema.PrintClientResponseH\x00\x12\x35\n\x0enonce_response\x18\x05 \x01(\x0b\x32\x1b.schema.NonceClientResponseH\x00\x12\x33\n\rquit_response\x18\x06 \x01(\x0b\x32\x1a.schema.QuitClientResponseH\x00\x12\x35\n\x0e\x63hain_response\x18\x07 \x01(\x0b\x32\x1b.schema.ChainClientResponseH\x00\x12\x37\n\x0fstatus_response\x18\x08 \x01(\x0b\x32\x1c.schema.StatusClientResponseH\x00\x12\x33\n\rboot_response\x18\t \x01(\x0b\x32\x1a.schema.BootClientResponseH\x00\x42\t\n\x07payloadB\x10\n\x0esignature_type\"\x15\n\x13WhoamiServerRequest\"&\n\x14WhoamiServerResponse\x12\x0e\n\x06whoami\x18\x01 \x01(\t\"\x14\n\x12NonceServerRequest\"$\n\x13NonceServerResponse\x12\r\n\x05nonce\x18\x01 \x01(\t\"\xa5\x02\n\rServerRequest\x12\x37\n\x05inner\x18\x01 \x01(\x0b\x32(.schema.ServerRequest.ServerRequestInner\x12,\n\tsignature\x18\x02 \x01(\x0b\x32\x17.schema.ClientSignatureH\x00\x1a\x9a\x01\n\x12ServerRequestInner\x12\x33\n\rnonce_request\x18\x01 \x01(\x0b\x32\x1a.schema.NonceServerRequestH\x00\x12\x35\n\x0ewhoami_request\x18\x02 \x01(\x0b\x32\x1b.schema.WhoamiServerRequestH\x00\x12\r\n\x05nonce\x18\x03 \x01(\tB\t\n\x07payloadB\x10\n\x0esignature_type\"\xad\x02\n\x0eServerResponse\x12\x39\n\x05inner\x18\x01 \x01(\x0b\x32*.schema.ServerResponse.ServerResponseInner\x12,\n\tsignature\x18\x02 \x01(\x0b\x32\x17.schema.ClientSignatureH\x00\x1a\x9f\x01\n\x13ServerResponseInner\x12\x35\n\x0enonce_response\x18\x01 \x01(\x0b\x32\x1b.schema.NonceServerResponseH\x00\x12\x37\n\x0fwhoami_response\x18\x02 \x01(\x0b\x32\x1c.schema.WhoamiServerResponseH\x00\x12\r\n\x05nonce\x18\x03 \x01(\tB\t\n\x07payloadB\x10\n\x0esignature_type\"5\n\tX509Chain\x12\r\n\x05\x63hain\x18\x01 \x03(\t\x12\x19\n\x11\x63\x65rtificate_roles\x18\x02 \x01(\t\"\x87\x01\n\rFullSignature\x12\x16\n\x0epayload_sha256\x18\x01 \x01(\t\x12,\n\x11\x63\x65rtificate_chain\x18\x02 \x01(\x0b\x32\x11.schema.X509Chain\x12\x16\n\x0euser_signature\x18\x03 \x01(\t\x12\x18\n\x10server_signature\x18\x04 \x01(\t\"\x9d\x01\n\x0f\x43lientSignature\x12\x16\n\x0epayload_sha256\x18\x01 \x01(\t\x12\x1a\n\x12\x63lient_certificate\x18\x02 \x01(\t\x12;\n\x1c\x63lient_certificate_signature\x18\x03 \x01(\x0b\x32\x15.schema.FullSignature\x12\x19\n\x11payload_signature\x18\x04 \x01(\t\"q\n\x0fServerSignature\x12\x16\n\x0epayload_sha256\x18\x01 \x01(\t\x12,\n\x11\x63\x65rtificate_chain\x18\x02 \x01(\x0b\x32\x11.schema.X509Chain\x12\x18\n\x10server_signature\x18\x03 \x01(\t\"m\n\rUserSignature\x12\x16\n\x0epayload_sha256\x18\x01 \x01(\t\x12,\n\x11\x63\x65rtificate_chain\x18\x02 \x01(\x0b\x32\x11.schema.X509Chain\x12\x16\n\x0euser_signature\x18\x03 \x01(\tb\x06proto3')
+DESCRIPTOR = _descriptor_pool.Default().AddSerializedFile(b'\n\x0cschema.proto\x12\x06schema\"\x13\n\x11HelpClientRequest\"%\n\x12HelpClientResponse\x12\x0f\n\x07message\x18\x01 \x01(\t\"%\n\x12PrintClientRequest\x12\x0f\n\x07message\x18\x01 \x01(\t\"&\n\x13PrintClientResponse\x12\x0f\n\x07message\x18\x01 \x01(\t\"\x14\n\x12NonceClientRequest\"$\n\x13NonceClientResponse\x12\r\n\x05nonce\x18\x01 \x01(\t\"\x13\n\x11QuitClientRequest\"\x14\n\x12QuitClientResponse\"\x15\n\x13StatusClientRequest\"l\n\x14StatusClientResponse\x12%\n\x07network\x18\x01 \x01(\x0b\x32\x14.schema.NetworkLease\x12-\n\nstatistics\x18\x02 \x01(\x0b\x32\x19.schema.NetworkStatistics\"B\n\x12\x43hainClientRequest\x12\x14\n\x0cpayload_size\x18\x01 \x01(\x05\x12\x16\n\x0epayload_sha256\x18\x02 \x01(\t\"\x15\n\x13\x43hainClientResponse\"\x99\x01\n\x11\x42ootClientRequest\x12\x35\n\tboot_type\x18\x01 \x01(\x0e\x32\".schema.BootClientRequest.BootType\x12\x14\n\x0cpayload_size\x18\x02 \x01(\x05\x12\x16\n\x0epayload_sha256\x18\x03 \x01(\t\"\x1f\n\x08\x42ootType\x12\x13\n\x0f\x42OOT_TYPE_LINUX\x10\x00\"\x14\n\x12\x42ootClientResponse\"\xcd\x01\n\x14NetworkConfiguration\x12\x36\n\x04mode\x18\x01 \x01(\x0e\x32(.schema.NetworkConfiguration.AddressMode\x12\x0f\n\x07\x61\x64\x64ress\x18\x02 \x01(\t\x12\x0f\n\x07netmask\x18\x03 \x01(\t\x12\x0f\n\x07gateway\x18\x04 \x01(\t\x12\x0b\n\x03\x64ns\x18\x05 \x03(\t\"=\n\x0b\x41\x64\x64ressMode\x12\x15\n\x11\x41\x44\x44RESS_MODE_DHCP\x10\x00\x12\x17\n\x13\x41\x44\x44RESS_MODE_STATIC\x10\x01\"p\n\x0cNetworkLease\x12\x0c\n\x04\x64hcp\x18\x01 \x01(\x08\x12\x0f\n\x07\x61\x64\x64ress\x18\x02 \x01(\t\x12\x0f\n\x07netmask\x18\x03 \x01(\t\x12\x0f\n\x07gateway\x18\x04 \x01(\t\x12\x0b\n\x03\x64ns\x18\x05 \x03(\t\x12\x12\n\nlease_time\x18\x06 \x01(\r\"D\n\x14NetworkClientRequest\x12,\n\x06\x63onfig\x18\x01 \x01(\x0b\x32\x1c.schema.NetworkConfiguration\"<\n\x15NetworkClientResponse\x12#\n\x05lease\x18\x01 \x01(\x0b\x32\x14.schema.NetworkLease\"\xd8\x02\n\x11NetworkStatistics\x12\x16\n\x0e\x62ytes_received\x18\x01 \x01(\x04\x12\x12\n\nbytes_sent\x18\x02 \x01(\x04\x12\x14\n\x0copen_sockets\x18\x03 \x01(\r\x12\x1c\n\x14\x63onnections_accepted\x18\x04 \x01(\x04\x12\x17\n\x0f\x61\x63\x63\x65pt_failures\x18\x05 \x01(\x04\x12\x1f\n\x17\x63onnections_established\x18\x06 \x01(\x04\x12\x18\n\x10\x63onnect_failures\x18\x07 \x01(\x04\x12\x35\n\x06\x65rrors\x18\x08 \x03(\x0b\x32%.schema.NetworkStatistics.ErrorsEntry\x12\x13\n\x0bmax_sockets\x18\t \x01(\x05\x12\x14\n\x0cused_sockets\x18\n \x01(\x05\x1a-\n\x0b\x45rrorsEntry\x12\x0b\n\x03key\x18\x01 \x01(\t\x12\r\n\x05value\x18\x02 \x01(\x04:\x02\x38\x01\"\x16\n\x14NetstatClientRequest\"F\n\x15NetstatClientResponse\x12-\n\nstatistics\x18\x01 \x01(\x0b\x32\x19.schema.NetworkStatistics\"s\n\x11TftpClientRequest\x12\x0e\n\x06server\x18\x01 \x01(\t\x12\x10\n\x08\x66ilename\x18\x02 \x01(\t\x12\x0e\n\x06target\x18\x03 \x01(\t\x12\x14\n\x0cpayload_size\x18\x04 \x01(\x05\x12\x16\n\x0epayload_sha256\x18\x05 \x01(\t\"2\n\x12TftpClientResponse\x12\x0c\n\x04size\x18\x01 \x01(\x04\x12\x0e\n\x06sha256\x18\x02 \x01(\t\"\xd9\x02\n\x13\x45rrorClientResponse\x12\r\n\x05\x65rror\x18\x01 \x01(\t\x12\x33\n\x04\x63ode\x18\x02 \x01(\x0e\x32%.schema.ErrorClientResponse.ErrorCode\"\xfd\x01\n\tErrorCode\x12\x1a\n\x16\x45RROR_CODE_UNSPECIFIED\x10\x00\x12\x1e\n\x1a\x45RROR_CODE_INVALID_REQUEST\x10\x01\x12\x16\n\x12\x45RROR_CODE_NETWORK\x10\x02\x12\x12\n\x0e\x45RROR_CODE_TLS\x10\x03\x12\x13\n\x0f\x45RROR_CODE_HTTP\x10\x04\x12\x13\n\x0f\x45RROR_CODE_TFTP\x10\x05\x12\x17\n\x13\x45RROR_CODE_PROTOCOL\x10\x06\x12\x1b\n\x17\x45RROR_CODE_VERIFICATION\x10\x07\x12\x13\n\x0f\x45RROR_CODE_BOOT\x10\x08\x12\x13\n\x0f\x45RROR_CODE_HOST\x10\t\"\xcb\x05\n\rClientRequest\x12\x37\n\x05inner\x18\x01 \x01(\x0b\x32(.schema.ClientRequest.ClientRequestInner\x12*\n\tsignature\x18\x02 \x01(\x0b\x32\x15.schema.FullSignatureH\x00\x1a\xc2\x04\n\x12\x43lientRequestInner\x12\r\n\x05nonce\x18\x01 \x01(\t\x12\x31\n\x0chelp_request\x18\x02 \x01(\x0b\x32\x19.schema.HelpClientRequestH\x00\x12\x33\n\rprint_request\x18\x03 \x01(\x0b\x32\x1a.schema.PrintClientRequestH\x00\x12\x33\n\rnonce_request\x18\x04 \x01(\x0b\x32\x1a.schema.NonceClientRequestH\x00\x12\x31\n\x0cquit_request\x18\x05 \x01(\x0b\x32\x19.schema.QuitClientRequestH\x00\x12\x33\n\rchain_request\x18\x06 \x01(\x0b\x32\x1a.schema.ChainClientRequestH\x00\x12\x35\n\x0estatus_request\x18\x07 \x01(\x0b\x32\x1b.schema.StatusClientRequestH\x00\x12\x31\n\x0c\x62oot_request\x18\x08 \x01(\x0b\x32\x19.schema.BootClientRequestH\x00\x12\x37\n\x0fnetwork_request\x18\t \x01(\x0b\x32\x1c.schema.NetworkClientRequestH\x00\x12\x31\n\x0ctftp_request\x18\n \x01(\x0b\x32\x19.schema.TftpClientRequestH\x00\x12\x37\n\x0fnetstat_request\x18\x0b \x01(\x0b\x32\x1c.schema.NetstatClientRequestH\x00\x42\t\n\x07payloadB\x10\n\x0esignature_type\"\x9c\x06\n\x0e\x43lientResponse\x12\x39\n\x05inner\x18\x01 \x01(\x0b\x32*.schema.ClientResponse.ClientResponseInner\x12,\n\tsignature\x18\x02 \x01(\x0b\x32\x17.schema.ClientSignatureH\x00\x1a\x8e\x05\n\x13\x43lientResponseInner\x12\r\n\x05nonce\x18\x01 \x01(\t\x12\x35\n\x0e\x65rror_response\x18\x02 \x01(\x0b\x32\x1b.schema.ErrorClientResponseH\x00\x12\x33\n\rhelp_response\x18\x03 \x01(\x0b\x32\x1a.schema.HelpClientResponseH\x00\x12\x35\n\x0eprint_response\x18\x04 \x01(\x0b\x32\x1b.schema.PrintClientResponseH\x00\x12\x35\n\x0enonce_response\x18\x05 \x01(\x0b\x32\x1b.schema.NonceClientResponseH\x00\x12\x33\n\rquit_response\x18\x06 \x01(\x0b\x32\x1a.schema.QuitClientResponseH\x00\x12\x35\n\x0e\x63hain_response\x18\x07 \x01(\x0b\x32\x1b.schema.ChainClientResponseH\x00\x12\x37\n\x0fstatus_response\x18\x08 \x01(\x0b\x32\x1c.schema.StatusClientResponseH\x00\x12\x33\n\rboot_response\x18\t \x01(\x0b\x32\x1a.schema.BootClientResponseH\x00\x12\x39\n\x10network_response\x18\n \x01(\x0b\x32\x1d.schema.NetworkClientResponseH\x00\x12\x33\n\rtftp_response\x18\x0b \x01(\x0b\x32\x1a.schema.TftpClientResponseH\x00\x12\x39\n\x10netstat_response\x18\x0c \x01(\x0b\x32\x1d.schema.NetstatClientResponseH\x00\x42\t\n\x07payloadB\x10\n\x0esignature_type\"\x15\n\x13WhoamiServerRequest\"&\n\x14WhoamiServerResponse\x12\x0e\n\x06whoami\x18\x01 \x01(\t\"\x14\n\x12NonceServerRequest\"$\n\x13NonceServerResponse\x12\r\n\x05nonce\x18\x01 \x01(\t\"\xa5\x02\n\rServerRequest\x12\x37\n\x05inner\x18\x01 \x01(\x0b\x32(.schema.ServerRequest.ServerRequestInner\x12,\n\tsignature\x18\x02 \x01(\x0b\x32\x17.schema.ClientSignatureH\x00\x1a\x9a\x01\n\x12ServerRequestInner\x12\x33\n\rnonce_request\x18\x01 \x01(\x0b\x32\x1a.schema.NonceServerRequestH\x00\x12\x35\n\x0ewhoami_request\x18\x02 \x01(\x0b\x32\x1b.schema.WhoamiServerRequestH\x00\x12\r\n\x05nonce\x18\x03 \x01(\tB\t\n\x07payloadB\x10\n\x0esignature_type\"\xad\x02\n\x0eServerResponse\x12\x39\n\x05inner\x18\x01 \x01(\x0b\x32*.schema.ServerResponse.ServerResponseInner\x12,\n\tsignature\x18\x02 \x01(\x0b\x32\x17.schema.ClientSignatureH\x00\x1a\x9f\x01\n\x13ServerResponseInner\x12\x35\n\x0enonce_response\x18\x01 \x01(\x0b\x32\x1b.schema.NonceServerResponseH\x00\x12\x37\n\x0fwhoami_response\x18\x02 \x01(\x0b\x32\x1c.schema.WhoamiServerResponseH\x00\x12\r\n\x05nonce\x18\x03 \x01(\tB\t\n\x07payloadB\x10\n\x0esignature_type\"5\n\tX509Chain\x12\r\n\x05\x63hain\x18\x01 \x03(\t\x12\x19\n\x11\x63\x65rtificate_roles\x18\x02 \x01(\t\"\x87\x01\n\rFullSignature\x12\x16\n\x0epayload_sha256\x18\x01 \x01(\t\x12,\n\x11\x63\x65rtificate_chain\x18\x02 \x01(\x0b\x32\x11.schema.X509Chain\x12\x16\n\x0euser_signature\x18\x03 \x01(\t\x12\x18\n\x10server_signature\x18\x04 \x01(\t\"\x9d\x01\n\x0f\x43lientSignature\x12\x16\n\x0epayload_sha256\x18\x01 \x01(\t\x12\x1a\n\x12\x63lient_certificate\x18\x02 \x01(\t\x12;\n\x1c\x63lient_certificate_signature\x18\x03 \x01(\x0b\x32\x15.schema.FullSignature\x12\x19\n\x11payload_signature\x18\x04 \x01(\t\"q\n\x0fServerSignature\x12\x16\n\x0epayload_sha256\x18\x01 \x01(\t\x12,\n\x11\x63\x65rtificate_chain\x18\x02 \x01(\x0b\x32\x11.schema.X509Chain\x12\x18\n\x10server_signature\x18\x03 \x01(\t\"m\n\rUserSignature\x12\x16\n\x0epayload_sha256\x18\x01 \x01(\t\x12,\n\x11\x63\x65rtificate_chain\x18\x02 \x01(\x0b\x32\x11.schema.X509Chain\x12\x16\n\x0euser_signature\x18\x03 \x01(\tb\x06proto3')
 
 _globals = globals()
 _builder.BuildMessageAndEnumDescriptors(DESCRIPTOR, _globals)
 _builder.BuildTopDescriptorsAndMessages(DESCRIPTOR, 'schema_pb2', _globals)
 if not _descriptor._USE_C_DESCRIPTORS:
   DESCRIPTOR._loaded_options = None
+  _globals['_NETWORKSTATISTICS_ERRORSENTRY']._loaded_options = None
+  _globals['_NETWORKSTATISTICS_ERRORSENTRY']._serialized_options = b'8\001'
   _globals['_HELPCLIENTREQUEST']._serialized_start=24
   _globals['_HELPCLIENTREQUEST']._serialized_end=43
   _globals['_HELPCLIENTRESPONSE']._serialized_start=45
@@ -50,51 +52,75 @@ if not _descriptor._USE_C_DESCRIPTORS:
   _globals['_STATUSCLIENTREQUEST']._serialized_start=266
   _globals['_STATUSCLIENTREQUEST']._serialized_end=287
   _globals['_STATUSCLIENTRESPONSE']._serialized_start=289
-  _globals['_STATUSCLIENTRESPONSE']._serialized_end=311
-  _globals['_CHAINCLIENTREQUEST']._serialized_start=313
-  _globals['_CHAINCLIENTREQUEST']._serialized_end=379
-  _globals['_CHAINCLIENTRESPONSE']._serialized_start=381
-  _globals['_CHAINCLIENTRESPONSE']._serialized_end=402
-  _globals['_BOOTCLIENTREQUEST']._serialized_start=405
-  _globals['_BOOTCLIENTREQUEST']._serialized_end=558
-  _globals['_BOOTCLIENTREQUEST_BOOTTYPE']._serialized_start=527
-  _globals['_BOOTCLIENTREQUEST_BOOTTYPE']._serialized_end=558
-  _globals['_BOOTCLIENTRESPONSE']._serialized_start=560
-  _globals['_BOOTCLIENTRESPONSE']._serialized_end=580
-  _globals['_ERRORCLIENTRESPONSE']._serialized_start=582
-  _globals['_ERRORCLIENTRESPONSE']._serialized_end=618
-  _globals['_CLIENTREQUEST']._serialized_start=621
-  _globals['_CLIENTREQUEST']._serialized_end=1171
-  _globals['_CLIENTREQUEST_CLIENTREQUESTINNER']._serialized_start=740
-  _globals['_CLIENTREQUEST_CLIENTREQUESTINNER']._serialized_end=1153
-  _globals['_CLIENTRESPONSE']._serialized_start=1174
-  _globals['_CLIENTRESPONSE']._serialized_end=1799
-  _globals['_CLIENTRESPONSE_CLIENTRESPONSEINNER']._serialized_start=1298
-  _globals['_CLIENTRESPONSE_CLIENTRESPONSEINNER']._serialized_end=1781
-  _globals['_WHOAMISERVERREQUEST']._serialized_start=1801
-  _globals['_WHOAMISERVERREQUEST']._serialized_end=1822
-  _globals['_WHOAMISERVERRESPONSE']._serialized_start=1824
-  _globals['_WHOAMISERVERRESPONSE']._serialized_end=1862
-  _globals['_NONCESERVERREQUEST']._serialized_start=1864
-  _globals['_NONCESERVERREQUEST']._serialized_end=1884
-  _globals['_NONCESERVERRESPONSE']._serialized_start=1886
-  _globals['_NONCESERVERRESPONSE']._serialized_end=1922
-  _globals['_SERVERREQUEST']._serialized_start=1925
-  _globals['_SERVERREQUEST']._serialized_end=2218
-  _globals['_SERVERREQUEST_SERVERREQUESTINNER']._serialized_start=2046
-  _globals['_SERVERREQUEST_SERVERREQUESTINNER']._serialized_end=2200
-  _globals['_SERVERRESPONSE']._serialized_start=2221
-  _globals['_SERVERRESPONSE']._serialized_end=2522
-  _globals['_SERVERRESPONSE_SERVERRESPONSEINNER']._serialized_start=2345
-  _globals['_SERVERRESPONSE_SERVERRESPONSEINNER']._serialized_end=2504
-  _globals['_X509CHAIN']._serialized_start=2524
-  _globals['_X509CHAIN']._serialized_end=2577
-  _globals['_FULLSIGNATURE']._serialized_start=2580
-  _globals['_FULLSIGNATURE']._serialized_end=2715
-  _globals['_CLIENTSIGNATURE']._serialized_start=2718
-  _globals['_CLIENTSIGNATURE']._serialized_end=2875
-  _globals['_SERVERSIGNATURE']._serialized_start=2877
-  _globals['_SERVERSIGNATURE']._serialized_end=2990
-  _globals['_USERSIGNATURE']._serialized_start=2992
-  _globals['_USERSIGNATURE']._serialized_end=3101
+  _globals['_STATUSCLIENTRESPONSE']._serialized_end=397
+  _globals['_CHAINCLIENTREQUEST']._serialized_start=399
+  _globals['_CHAINCLIENTREQUEST']._serialized_end=465
+  _globals['_CHAINCLIENTRESPONSE']._serialized_start=467
+  _globals['_CHAINCLIENTRESPONSE']._serialized_end=488
+  _globals['_BOOTCLIENTREQUEST']._serialized_start=491
+  _globals['_BOOTCLIENTREQUEST']._serialized_end=644
+  _globals['_BOOTCLIENTREQUEST_BOOTTYPE']._serialized_start=613
+  _globals['_BOOTCLIENTREQUEST_BOOTTYPE']._serialized_end=644
+  _globals['_BOOTCLIENTRESPONSE']._serialized_start=646
+  _globals['_BOOTCLIENTRESPONSE']._serialized_end=666
+  _globals['_NETWORKCONFIGURATION']._serialized_start=669
+  _globals['_NETWORKCONFIGURATION']._serialized_end=874
+  _globals['_NETWORKCONFIGURATION_ADDRESSMODE']._serialized_start=813
+  _globals['_NETWORKCONFIGURATION_ADDRESSMODE']._serialized_end=874
+  _globals['_NETWORKLEASE']._serialized_start=876
+  _globals['_NETWORKLEASE']._serialized_end=988
+  _globals['_NETWORKCLIENTREQUEST']._serialized_start=990
+  _globals['_NETWORKCLIENTREQUEST']._serialized_end=1058
+  _globals['_NETWORKCLIENTRESPONSE']._serialized_start=1060
+  _globals['_NETWORKCLIENTRESPONSE']._serialized_end=1120
+  _globals['_NETWORKSTATISTICS']._serialized_start=1123
+  _globals['_NETWORKSTATISTICS']._serialized_end=1467
+  _globals['_NETWORKSTATISTICS_ERRORSENTRY']._serialized_start=1422
+  _globals['_NETWORKSTATISTICS_ERRORSENTRY']._serialized_end=1467
+  _globals['_NETSTATCLIENTREQUEST']._serialized_start=1469
+  _globals['_NETSTATCLIENTREQUEST']._serialized_end=1491
+  _globals['_NETSTATCLIENTRESPONSE']._serialized_start=1493
+  _globals['_NETSTATCLIENTRESPONSE']._serialized_end=1563
+  _globals['_TFTPCLIENTREQUEST']._serialized_start=1565
+  _globals['_TFTPCLIENTREQUEST']._serialized_end=1680
+  _globals['_TFTPCLIENTRESPONSE']._serialized_start=1682
+  _globals['_TFTPCLIENTRESPONSE']._serialized_end=1732
+  _globals['_ERRORCLIENTRESPONSE']._serialized_start=1735
+  _globals['_ERRORCLIENTRESPONSE']._serialized_end=2080
+  _globals['_ERRORCLIENTRESPONSE_ERRORCODE']._serialized_start=1827
+  _globals['_ERRORCLIENTRESPONSE_ERRORCODE']._serialized_end=2080
+  _globals['_CLIENTREQUEST']._serialized_start=2083
+  _globals['_CLIENTREQUEST']._serialized_end=2798
+  _globals['_CLIENTREQUEST_CLIENTREQUESTINNER']._serialized_start=2202
+  _globals['_CLIENTREQUEST_CLIENTREQUESTINNER']._serialized_end=2780
+  _globals['_CLIENTRESPONSE']._serialized_start=2801
+  _globals['_CLIENTRESPONSE']._serialized_end=3597
+  _globals['_CLIENTRESPONSE_CLIENTRESPONSEINNER']._serialized_start=2925
+  _globals['_CLIENTRESPONSE_CLIENTRESPONSEINNER']._serialized_end=3579
+  _globals['_WHOAMISERVERREQUEST']._serialized_start=3599
+  _globals['_WHOAMISERVERREQUEST']._serialized_end=3620
+  _globals['_WHOAMISERVERRESPONSE']._serialized_start=3622
+  _globals['_WHOAMISERVERRESPONSE']._serialized_end=3660
+  _globals['_NONCESERVERREQUEST']._serialized_start=3662
+  _globals['_NONCESERVERREQUEST']._serialized_end=3682
+  _globals['_NONCESERVERRESPONSE']._serialized_start=3684
+  _globals['_NONCESERVERRESPONSE']._serialized_end=3720
+  _globals['_SERVERREQUEST']._serialized_start=3723
+  _globals['_SERVERREQUEST']._serialized_end=4016
+  _globals['_SERVERREQUEST_SERVERREQUESTINNER']._serialized_start=3844
+  _globals['_SERVERREQUEST_SERVERREQUESTINNER']._serialized_end=3998
+  _globals['_SERVERRESPONSE']._serialized_start=4019
+  _globals['_SERVERRESPONSE']._serialized_end=4320
+  _globals['_SERVERRESPONSE_SERVERRESPONSEINNER']._serialized_start=4143
+  _globals['_SERVERRESPONSE_SERVERRESPONSEINNER']._serialized_end=4302
+  _globals['_X509CHAIN']._serialized_start=4322
+  _globals['_X509CHAIN']._serialized_end=4375
+  _globals['_FULLSIGNATURE']._serialized_start=4378
+  _globals['_FULLSIGNATURE']._serialized_end=4513
+  _globals['_CLIENTSIGNATURE']._serialized_start=4516
+  _globals['_CLIENTSIGNATURE']._serialized_end=4673
+  _globals['_SERVERSIGNATURE']._serialized_start=4675
+  _globals['_SERVERSIGNATURE']._serialized_end=4788
+  _globals['_USERSIGNATURE']._serialized_start=4790
+  _globals['_USERSIGNATURE']._serialized_end=4899
 # @@protoc_insertion_point(module_scope)
@@ -2,7 +2,8 @@ from google.protobuf.internal import containers as _containers
 from google.protobuf.internal import enum_type_wrapper as _enum_type_wrapper
 from google.protobuf import descriptor as _descriptor
 from google.protobuf import message as _message
-from typing import ClassVar as _ClassVar, Iterable as _Iterable, Mapping as _Mapping, Optional as _Optional, Union as _Union
+from collections.abc import Iterable as _Iterable, Mapping as _Mapping
+from typing import ClassVar as _ClassVar, Optional as _Optional, Union as _Union
 
 DESCRIPTOR: _descriptor.FileDescriptor
 
@@ -51,8 +52,12 @@ class StatusClientRequest(_message.Message):
     def __init__(self) -> None: ...
 
 class StatusClientResponse(_message.Message):
-    __slots__ = ()
-    def __init__(self) -> None: ...
+    __slots__ = ("network", "statistics")
+    NETWORK_FIELD_NUMBER: _ClassVar[int]
+    STATISTICS_FIELD_NUMBER: _ClassVar[int]
+    network: NetworkLease
+    statistics: NetworkStatistics
+    def __init__(self, network: _Optional[_Union[NetworkLease, _Mapping]] = ..., statistics: _Optional[_Union[NetworkStatistics, _Mapping]] = ...) -> None: ...
 
 class ChainClientRequest(_message.Message):
     __slots__ = ("payload_size", "payload_sha256")
@@ -84,16 +89,151 @@ class BootClientResponse(_message.Message):
     __slots__ = ()
     def __init__(self) -> None: ...
 
+class NetworkConfiguration(_message.Message):
+    __slots__ = ("mode", "address", "netmask", "gateway", "dns")
+    class AddressMode(int, metaclass=_enum_type_wrapper.EnumTypeWrapper):
+        __slots__ = ()
+        ADDRESS_MODE_DHCP: _ClassVar[NetworkConfiguration.AddressMode]
+        ADDRESS_MODE_STATIC: _ClassVar[NetworkConfiguration.AddressMode]
+    ADDRESS_MODE_DHCP: NetworkConfiguration.AddressMode
+    ADDRESS_MODE_STATIC: NetworkConfiguration.AddressMode
+    MODE_FIELD_NUMBER: _ClassVar[int]
+    ADDRESS_FIELD_NUMBER: _ClassVar[int]
+    NETMASK_FIELD_NUMBER: _ClassVar[int]
+    GATEWAY_FIELD_NUMBER: _ClassVar[int]
+    DNS_FIELD_NUMBER: _ClassVar[int]
+    mode: NetworkConfiguration.AddressMode
+    address: str
+    netmask: str
+    gateway: str
+    dns: _containers.RepeatedScalarFieldContainer[str]
+    def __init__(self, mode: _Optional[_Union[NetworkConfiguration.AddressMode, str]] = ..., address: _Optional[str] = ..., netmask: _Optional[str] = ..., gateway: _Optional[str] = ..., dns: _Optional[_Iterable[str]] = ...) -> None: ...
+
+class NetworkLease(_message.Message):
+    __slots__ = ("dhcp", "address", "netmask", "gateway", "dns", "lease_time")
+    DHCP_FIELD_NUMBER: _ClassVar[int]
+    ADDRESS_FIELD_NUMBER: _ClassVar[int]
+    NETMASK_FIELD_NUMBER: _ClassVar[int]
+    GATEWAY_FIELD_NUMBER: _ClassVar[int]
+    DNS_FIELD_NUMBER: _ClassVar[int]
+    LEASE_TIME_FIELD_NUMBER: _ClassVar[int]
+    dhcp: bool
+    address: str
+    netmask: str
+    gateway: str
+    dns: _containers.RepeatedScalarFieldContainer[str]
+    lease_time: int
+    def __init__(self, dhcp: _Optional[bool] = ..., address: _Optional[str] = ..., netmask: _Optional[str] = ..., gateway: _Optional[str] = ..., dns: _Optional[_Iterable[str]] = ..., lease_time: _Optional[int] = ...) -> None: ...
+
+class NetworkClientRequest(_message.Message):
+    __slots__ = ("config",)
+    CONFIG_FIELD_NUMBER: _ClassVar[int]
+    config: NetworkConfiguration
+    def __init__(self, config: _Optional[_Union[NetworkConfiguration, _Mapping]] = ...) -> None: ...
+
+class NetworkClientResponse(_message.Message):
+    __slots__ = ("lease",)
+    LEASE_FIELD_NUMBER: _ClassVar[int]
+    lease: NetworkLease
+    def __init__(self, lease: _Optional[_Union[NetworkLease, _Mapping]] = ...) -> None: ...
+
+class NetworkStatistics(_message.Message):
+    __slots__ = ("bytes_received", "bytes_sent", "open_sockets", "connections_accepted", "accept_failures", "connections_established", "connect_failures", "errors", "max_sockets", "used_sockets")
+    class ErrorsEntry(_message.Message):
+        __slots__ = ("key", "value")
+        KEY_FIELD_NUMBER: _ClassVar[int]
+        VALUE_FIELD_NUMBER: _ClassVar[int]
+        key: str
+        value: int
+        def __init__(self, key: _Optional[str] = ..., value: _Optional[int] = ...) -> None: ...
+    BYTES_RECEIVED_FIELD_NUMBER: _ClassVar[int]
+    BYTES_SENT_FIELD_NUMBER: _ClassVar[int]
+    OPEN_SOCKETS_FIELD_NUMBER: _ClassVar[int]
+    CONNECTIONS_ACCEPTED_FIELD_NUMBER: _ClassVar[int]
+    ACCEPT_FAILURES_FIELD_NUMBER: _ClassVar[int]
+    CONNECTIONS_ESTABLISHED_FIELD_NUMBER: _ClassVar[int]
+    CONNECT_FAILURES_FIELD_NUMBER: _ClassVar[int]
+    ERRORS_FIELD_NUMBER: _ClassVar[int]
+    MAX_SOCKETS_FIELD_NUMBER: _ClassVar[int]
+    USED_SOCKETS_FIELD_NUMBER: _ClassVar[int]
+    bytes_received: int
+    bytes_sent: int
+    open_sockets: int
+    connections_accepted: int
+    accept_failures: int
+    connections_established: int
+    connect_failures: int
+    errors: _containers.ScalarMap[str, int]
+    max_sockets: int
+    used_sockets: int
+    def __init__(self, bytes_received: _Optional[int] = ..., bytes_sent: _Optional[int] = ..., open_sockets: _Optional[int] = ..., connections_accepted: _Optional[int] = ..., accept_failures: _Optional[int] = ..., connections_established: _Optional[int] = ..., connect_failures: _Optional[int] = ..., errors: _Optional[_Mapping[str, int]] = ..., max_sockets: _Optional[int] = ..., used_sockets: _Optional[int] = ...) -> None: ...
+
+class NetstatClientRequest(_message.Message):
+    __slots__ = ()
+    def __init__(self) -> None: ...
+
+class NetstatClientResponse(_message.Message):
+    __slots__ = ("statistics",)
+    STATISTICS_FIELD_NUMBER: _ClassVar[int]
+    statistics: NetworkStatistics
+    def __init__(self, statistics: _Optional[_Union[NetworkStatistics, _Mapping]] = ...) -> None: ...
+
+class TftpClientRequest(_message.Message):
+    __slots__ = ("server", "filename", "target", "payload_size", "payload_sha256")
+    SERVER_FIELD_NUMBER: _ClassVar[int]
+    FILENAME_FIELD_NUMBER: _ClassVar[int]
+    TARGET_FIELD_NUMBER: _ClassVar[int]
+    PAYLOAD_SIZE_FIELD_NUMBER: _ClassVar[int]
+    PAYLOAD_SHA256_FIELD_NUMBER: _ClassVar[int]
+    server: str
+    filename: str
+    target: str
+    payload_size: int
+    payload_sha256: str
+    def __init__(self, server: _Optional[str] = ..., filename: _Optional[str] = ..., target: _Optional[str] = ..., payload_size: _Optional[int] = ..., payload_sha256: _Optional[str] = ...) -> None: ...
+
+class TftpClientResponse(_message.Message):
+    __slots__ = ("size", "sha256")
+    SIZE_FIELD_NUMBER: _ClassVar[int]
+    SHA256_FIELD_NUMBER: _ClassVar[int]
+    size: int
+    sha256: str
+    def __init__(self, size: _Optional[int] = ..., sha256: _Optional[str] = ...) -> None: ...
+
 class ErrorClientResponse(_message.Message):
-    __slots__ = ("error",)
+    __slots__ = ("error", "code")
+    class ErrorCode(int, metaclass=_enum_type_wrapper.EnumTypeWrapper):
+        __slots__ = ()
+        ERROR_CODE_UNSPECIFIED: _ClassVar[ErrorClientResponse.ErrorCode]
+        ERROR_CODE_INVALID_REQUEST: _ClassVar[ErrorClientResponse.ErrorCode]
+        ERROR_CODE_NETWORK: _ClassVar[ErrorClientResponse.ErrorCode]
+        ERROR_CODE_TLS: _ClassVar[ErrorClientResponse.ErrorCode]
+        ERROR_CODE_HTTP: _ClassVar[ErrorClientResponse.ErrorCode]
+        ERROR_CODE_TFTP: _ClassVar[ErrorClientResponse.ErrorCode]
+        ERROR_CODE_PROTOCOL: _ClassVar[ErrorClientResponse.ErrorCode]
+        ERROR_CODE_VERIFICATION: _ClassVar[ErrorClientResponse.ErrorCode]
+        ERROR_CODE_BOOT: _ClassVar[ErrorClientResponse.ErrorCode]
+        ERROR_CODE_HOST: _ClassVar[ErrorClientResponse.ErrorCode]
+    ERROR_CODE_UNSPECIFIED: ErrorClientResponse.ErrorCode
+    ERROR_CODE_INVALID_REQUEST: ErrorClientResponse.ErrorCode
+    ERROR_CODE_NETWORK: ErrorClientResponse.ErrorCode
+    ERROR_CODE_TLS: ErrorClientResponse.ErrorCode
+    ERROR_CODE_HTTP: ErrorClientResponse.ErrorCode
+    ERROR_CODE_TFTP: ErrorClientResponse.ErrorCode
+    ERROR_CODE_PROTOCOL: ErrorClientResponse.ErrorCode
+    ERROR_CODE_VERIFICATION: ErrorClientResponse.ErrorCode
+    ERROR_CODE_BOOT: ErrorClientResponse.ErrorCode
+    ERROR_CODE_HOST: ErrorClientResponse.ErrorCode
     ERROR_FIELD_NUMBER: _ClassVar[int]
+    CODE_FIELD_NUMBER: _ClassVar[int]
     error: str
-    def __init__(self, error: _Optional[str] = ...) -> None: ...
+    code: ErrorClientResponse.ErrorCode
+    def __init__(self, error: _Optional[str] = ..., code: _Optional[_Union[ErrorClientResponse.ErrorCode, str]] = ...) -> None: ...
 
 class ClientRequest(_message.Message):
     __slots__ = ("inner", "signature")
     class ClientRequestInner(_message.Message):
-        __slots__ = ("nonce", "help_request", "print_request", "nonce_request", "quit_request", "chain_request", "status_request", "boot_request")
+        __slots__ = ("nonce", "help_request", "print_request", "nonce_request", "quit_request", "chain_request", "status_request", "boot_request", "network_request", "tftp_request", "netstat_request")
         NONCE_FIELD_NUMBER: _ClassVar[int]
         HELP_REQUEST_FIELD_NUMBER: _ClassVar[int]
         PRINT_REQUEST_FIELD_NUMBER: _ClassVar[int]
@@ -102,6 +242,9 @@ class ClientRequest(_message.Message):
         CHAIN_REQUEST_FIELD_NUMBER: _ClassVar[int]
         STATUS_REQUEST_FIELD_NUMBER: _ClassVar[int]
         BOOT_REQUEST_FIELD_NUMBER: _ClassVar[int]
+        NETWORK_REQUEST_FIELD_NUMBER: _ClassVar[int]
+        TFTP_REQUEST_FIELD_NUMBER: _ClassVar[int]
+        NETSTAT_REQUEST_FIELD_NUMBER: _ClassVar[int]
         nonce: str
         help_request: HelpClientRequest
         print_request: PrintClientRequest
@@ -110,7 +253,10 @@ class ClientRequest(_message.Message):
         chain_request: ChainClientRequest
         status_request: StatusClientRequest
         boot_request: BootClientRequest
-        def __init__(self, nonce: _Optional[str] = ..., help_request: _Optional[_Union[HelpClientRequest, _Mapping]] = ..., print_request: _Optional[_Union[PrintClientRequest, _Mapping]] = ..., nonce_request: _Optional[_Union[NonceClientRequest, _Mapping]] = ..., quit_request: _Optional[_Union[QuitClientRequest, _Mapping]] = ..., chain_request: _Optional[_Union[ChainClientRequest, _Mapping]] = ..., status_request: _Optional[_Union[StatusClientRequest, _Mapping]] = ..., boot_request: _Optional[_Union[BootClientRequest, _Mapping]] = ...) -> None: ...
+        network_request: NetworkClientRequest
+        tftp_request: TftpClientRequest
+        netstat_request: NetstatClientRequest
+        def __init__(self, nonce: _Optional[str] = ..., help_request: _Optional[_Union[HelpClientRequest, _Mapping]] = ..., print_request: _Optional[_Union[PrintClientRequest, _Mapping]] = ..., nonce_request: _Optional[_Union[NonceClientRequest, _Mapping]] = ..., quit_request: _Optional[_Union[QuitClientRequest, _Mapping]] = ..., chain_request: _Optional[_Union[ChainClientRequest, _Mapping]] = ..., status_request: _Optional[_Union[StatusClientRequest, _Mapping]] = ..., boot_request: _Optional[_Union[BootClientRequest, _Mapping]] = ..., network_request: _Optional[_Union[NetworkClientRequest, _Mapping]] = ..., tftp_request: _Optional[_Union[TftpClientRequest, _Mapping]] = ..., netstat_request: _Optional[_Union[NetstatClientRequest, _Mapping]] = ...) -> None: ...
     INNER_FIELD_NUMBER: _ClassVar[int]
     SIGNATURE_FIELD_NUMBER: _ClassVar[int]
     inner: ClientRequest.ClientRequestInner
@@ -120,7 +266,7 @@ class ClientRequest(_message.Message):
 class ClientResponse(_message.Message):
     __slots__ = ("inner", "signature")
     class ClientResponseInner(_message.Message):
-        __slots__ = ("nonce", "error_response", "help_response", "print_response", "nonce_response", "quit_response", "chain_response", "status_response", "boot_response")
+        __slots__ = ("nonce", "error_response", "help_response", "print_response", "nonce_response", "quit_response", "chain_response", "status_response", "boot_response", "network_response", "tftp_response", "netstat_response")
         NONCE_FIELD_NUMBER: _ClassVar[int]
         ERROR_RESPONSE_FIELD_NUMBER: _ClassVar[int]
         HELP_RESPONSE_FIELD_NUMBER: _ClassVar[int]
@@ -130,6 +276,9 @@ class ClientResponse(_message.Message):
         CHAIN_RESPONSE_FIELD_NUMBER: _ClassVar[int]
         STATUS_RESPONSE_FIELD_NUMBER: _ClassVar[int]
         BOOT_RESPONSE_FIELD_NUMBER: _ClassVar[int]
+        NETWORK_RESPONSE_FIELD_NUMBER: _ClassVar[int]
+        TFTP_RESPONSE_FIELD_NUMBER: _ClassVar[int]
+        NETSTAT_RESPONSE_FIELD_NUMBER: _ClassVar[int]
         nonce: str
         error_response: ErrorClientResponse
         help_response: HelpClientResponse
@@ -139,7 +288,10 @@ class ClientResponse(_message.Message):
         chain_response: ChainClientResponse
         status_response: StatusClientResponse
         boot_response: BootClientResponse
-        def __init__(self, nonce: _Optional[str] = ..., error_response: _Optional[_Union[ErrorClientResponse, _Mapping]] = ..., help_response: _Optional[_Union[HelpClientResponse, _Mapping]] = ..., print_response: _Optional[_Union[PrintClientResponse, _Mapping]] = ..., nonce_response: _Optional[_Union[NonceClientResponse, _Mapping]] = ..., quit_response: _Optional[_Union[QuitClientResponse, _Mapping]] = ..., chain_response: _Optional[_Union[ChainClientResponse, _Mapping]] = ..., status_response: _Optional[_Union[StatusClientResponse, _Mapping]] = ..., boot_response: _Optional[_Union[BootClientResponse, _Mapping]] = ...) -> None: ...
+        network_response: NetworkClientResponse
+        tftp_response: TftpClientResponse
+        netstat_response: NetstatClientResponse
+        def __init__(self, nonce: _Optional[str] = ..., error_response: _Optional[_Union[ErrorClientResponse, _Mapping]] = ..., help_response: _Optional[_Union[HelpClientResponse, _Mapping]] = ..., print_response: _Optional[_Union[PrintClientResponse, _Mapping]] = ..., nonce_response: _Optional[_Union[NonceClientResponse, _Mapping]] = ..., quit_response: _Optional[_Union[QuitClientResponse, _Mapping]] = ..., chain_response: _Optional[_Union[ChainClientResponse, _Mapping]] = ..., status_response: _Optional[_Union[StatusClientResponse, _Mapping]] = ..., boot_response: _Optional[_Union[BootClientResponse, _Mapping]] = ..., network_response: _Optional[_Union[NetworkClientResponse, _Mapping]] = ..., tftp_response: _Optional[_Union[TftpClientResponse, _Mapping]] = ..., netstat_response: _Optional[_Union[NetstatClientResponse, _Mapping]] = ...) -> None: ...
     INNER_FIELD_NUMBER: _ClassVar[int]
     SIGNATURE_FIELD_NUMBER: _ClassVar[int]
     inner: ClientResponse.ClientResponseInner
@@ -34,8 +34,14 @@ pub struct QuitClientResponse {}
 /// Status command (cmdPattern: "status")
 #[derive(Clone, Copy, PartialEq, ::prost::Message)]
 pub struct StatusClientRequest {}
-#[derive(Clone, Copy, PartialEq, ::prost::Message)]
-pub struct StatusClientResponse {}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct StatusClientResponse {
+    /// The current network lease
+    #[prost(message, optional, tag = "1")]
+    pub network: ::core::option::Option<NetworkLease>,
+    #[prost(message, optional, tag = "2")]
+    pub statistics: ::core::option::Option<NetworkStatistics>,
+}
 /// Chain command (cmdPattern: "chain")
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct ChainClientRequest {
@@ -95,11 +101,218 @@ pub mod boot_client_request {
 }
 #[derive(Clone, Copy, PartialEq, ::prost::Message)]
 pub struct BootClientResponse {}
+/// Network command (cmdPattern: "ifconfig")
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct NetworkConfiguration {
+    #[prost(enumeration = "network_configuration::AddressMode", tag = "1")]
+    pub mode: i32,
+    /// Only used in static mode
+    #[prost(string, tag = "2")]
+    pub address: ::prost::alloc::string::String,
+    #[prost(string, tag = "3")]
+    pub netmask: ::prost::alloc::string::String,
+    #[prost(string, tag = "4")]
+    pub gateway: ::prost::alloc::string::String,
+    /// Overrides the DNS servers offered by DHCP
+    #[prost(string, repeated, tag = "5")]
+    pub dns: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+}
+/// Nested message and enum types in `NetworkConfiguration`.
+pub mod network_configuration {
+    #[derive(
+        Clone,
+        Copy,
+        Debug,
+        PartialEq,
+        Eq,
+        Hash,
+        PartialOrd,
+        Ord,
+        ::prost::Enumeration
+    )]
+    #[repr(i32)]
+    pub enum AddressMode {
+        Dhcp = 0,
+        Static = 1,
+    }
+    impl AddressMode {
+        /// String value of the enum field names used in the ProtoBuf definition.
+        ///
+        /// The values are not transformed in any way and thus are considered stable
+        /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+        pub fn as_str_name(&self) -> &'static str {
+            match self {
+                Self::Dhcp => "ADDRESS_MODE_DHCP",
+                Self::Static => "ADDRESS_MODE_STATIC",
+            }
+        }
+        /// Creates an enum from field names used in the ProtoBuf definition.
+        pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+            match value {
+                "ADDRESS_MODE_DHCP" => Some(Self::Dhcp),
+                "ADDRESS_MODE_STATIC" => Some(Self::Static),
+                _ => None,
+            }
+        }
+    }
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct NetworkLease {
+    #[prost(bool, tag = "1")]
+    pub dhcp: bool,
+    #[prost(string, tag = "2")]
+    pub address: ::prost::alloc::string::String,
+    #[prost(string, tag = "3")]
+    pub netmask: ::prost::alloc::string::String,
+    #[prost(string, tag = "4")]
+    pub gateway: ::prost::alloc::string::String,
+    #[prost(string, repeated, tag = "5")]
+    pub dns: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// Lease time in seconds, 0 for a static configuration
+    #[prost(uint32, tag = "6")]
+    pub lease_time: u32,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct NetworkClientRequest {
+    /// The configuration to apply, if unset the current lease is returned
+    #[prost(message, optional, tag = "1")]
+    pub config: ::core::option::Option<NetworkConfiguration>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct NetworkClientResponse {
+    #[prost(message, optional, tag = "1")]
+    pub lease: ::core::option::Option<NetworkLease>,
+}
+/// Netstat command (cmdPattern: "netstat")
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct NetworkStatistics {
+    #[prost(uint64, tag = "1")]
+    pub bytes_received: u64,
+    #[prost(uint64, tag = "2")]
+    pub bytes_sent: u64,
+    /// Sockets currently open by the bootloader
+    #[prost(uint32, tag = "3")]
+    pub open_sockets: u32,
+    #[prost(uint64, tag = "4")]
+    pub connections_accepted: u64,
+    #[prost(uint64, tag = "5")]
+    pub accept_failures: u64,
+    #[prost(uint64, tag = "6")]
+    pub connections_established: u64,
+    #[prost(uint64, tag = "7")]
+    pub connect_failures: u64,
+    /// Occurrences of each lwIP error, keyed by its name
+    #[prost(map = "string, uint64", tag = "8")]
+    pub errors: ::std::collections::HashMap<::prost::alloc::string::String, u64>,
+    /// Size and usage of the host's socket pool, -1 if not reported
+    #[prost(int32, tag = "9")]
+    pub max_sockets: i32,
+    #[prost(int32, tag = "10")]
+    pub used_sockets: i32,
+}
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct NetstatClientRequest {}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct NetstatClientResponse {
+    #[prost(message, optional, tag = "1")]
+    pub statistics: ::core::option::Option<NetworkStatistics>,
+}
+/// TFTP command (cmdPattern: "tftp <server> <filename> <target>")
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct TftpClientRequest {
+    /// Host name or address of the server, defaults to the serverip variable
+    #[prost(string, tag = "1")]
+    pub server: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub filename: ::prost::alloc::string::String,
+    /// A payload type such as "kernel_addr_r", or "chain" to chain-load the file
+    #[prost(string, tag = "3")]
+    pub target: ::prost::alloc::string::String,
+    /// Optional, checked against the size announced by the server
+    #[prost(int32, tag = "4")]
+    pub payload_size: i32,
+    /// Optional, the transfer is rejected if the digest does not match
+    #[prost(string, tag = "5")]
+    pub payload_sha256: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct TftpClientResponse {
+    #[prost(uint64, tag = "1")]
+    pub size: u64,
+    #[prost(string, tag = "2")]
+    pub sha256: ::prost::alloc::string::String,
+}
 /// Error response
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct ErrorClientResponse {
     #[prost(string, tag = "1")]
     pub error: ::prost::alloc::string::String,
+    #[prost(enumeration = "error_client_response::ErrorCode", tag = "2")]
+    pub code: i32,
+}
+/// Nested message and enum types in `ErrorClientResponse`.
+pub mod error_client_response {
+    /// Category of the error, for clients to react to without parsing the message
+    #[derive(
+        Clone,
+        Copy,
+        Debug,
+        PartialEq,
+        Eq,
+        Hash,
+        PartialOrd,
+        Ord,
+        ::prost::Enumeration
+    )]
+    #[repr(i32)]
+    pub enum ErrorCode {
+        Unspecified = 0,
+        InvalidRequest = 1,
+        Network = 2,
+        Tls = 3,
+        Http = 4,
+        Tftp = 5,
+        Protocol = 6,
+        Verification = 7,
+        Boot = 8,
+        Host = 9,
+    }
+    impl ErrorCode {
+        /// String value of the enum field names used in the ProtoBuf definition.
+        ///
+        /// The values are not transformed in any way and thus are considered stable
+        /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+        pub fn as_str_name(&self) -> &'static str {
+            match self {
+                Self::Unspecified => "ERROR_CODE_UNSPECIFIED",
+                Self::InvalidRequest => "ERROR_CODE_INVALID_REQUEST",
+                Self::Network => "ERROR_CODE_NETWORK",
+                Self::Tls => "ERROR_CODE_TLS",
+                Self::Http => "ERROR_CODE_HTTP",
+                Self::Tftp => "ERROR_CODE_TFTP",
+                Self::Protocol => "ERROR_CODE_PROTOCOL",
+                Self::Verification => "ERROR_CODE_VERIFICATION",
+                Self::Boot => "ERROR_CODE_BOOT",
+                Self::Host => "ERROR_CODE_HOST",
+            }
+        }
+        /// Creates an enum from field names used in the ProtoBuf definition.
+        pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+            match value {
+                "ERROR_CODE_UNSPECIFIED" => Some(Self::Unspecified),
+                "ERROR_CODE_INVALID_REQUEST" => Some(Self::InvalidRequest),
+                "ERROR_CODE_NETWORK" => Some(Self::Network),
+                "ERROR_CODE_TLS" => Some(Self::Tls),
+                "ERROR_CODE_HTTP" => Some(Self::Http),
+                "ERROR_CODE_TFTP" => Some(Self::Tftp),
+                "ERROR_CODE_PROTOCOL" => Some(Self::Protocol),
+                "ERROR_CODE_VERIFICATION" => Some(Self::Verification),
+                "ERROR_CODE_BOOT" => Some(Self::Boot),
+                "ERROR_CODE_HOST" => Some(Self::Host),
+                _ => None,
+            }
+        }
+    }
 }
 /// Client request
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -117,7 +330,10 @@ pub mod client_request {
         /// A unique identifier for the request, used to prevent replay attacks
         #[prost(string, tag = "1")]
         pub nonce: ::prost::alloc::string::String,
-        #[prost(oneof = "client_request_inner::Payload", tags = "2, 3, 4, 5, 6, 7, 8")]
+        #[prost(
+            oneof = "client_request_inner::Payload",
+            tags = "2, 3, 4, 5, 6, 7, 8, 9, 10, 11"
+        )]
         pub payload: ::core::option::Option<client_request_inner::Payload>,
     }
     /// Nested message and enum types in `ClientRequestInner`.
@@ -138,6 +354,12 @@ pub mod client_request {
             StatusRequest(super::super::StatusClientRequest),
             #[prost(message, tag = "8")]
             BootRequest(super::super::BootClientRequest),
+            #[prost(message, tag = "9")]
+            NetworkRequest(super::super::NetworkClientRequest),
+            #[prost(message, tag = "10")]
+            TftpRequest(super::super::TftpClientRequest),
+            #[prost(message, tag = "11")]
+            NetstatRequest(super::super::NetstatClientRequest),
         }
     }
     #[derive(Clone, PartialEq, ::prost::Oneof)]
@@ -165,7 +387,7 @@ pub mod client_response {
         /// command or an error
         #[prost(
             oneof = "client_response_inner::Payload",
-            tags = "2, 3, 4, 5, 6, 7, 8, 9"
+            tags = "2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12"
         )]
         pub payload: ::core::option::Option<client_response_inner::Payload>,
     }
@@ -191,6 +413,12 @@ pub mod client_response {
             StatusResponse(super::super::StatusClientResponse),
             #[prost(message, tag = "9")]
             BootResponse(super::super::BootClientResponse),
+            #[prost(message, tag = "10")]
+            NetworkResponse(super::super::NetworkClientResponse),
+            #[prost(message, tag = "11")]
+            TftpResponse(super::super::TftpClientResponse),
+            #[prost(message, tag = "12")]
+            NetstatResponse(super::super::NetstatClientResponse),
         }
     }
     #[derive(Clone, PartialEq, ::prost::Oneof)]
@@ -0,0 +1,58 @@
+import argparse
+import logging
+import time
+
+import requests
+
+from proxyclient import config
+from proxyclient.commands.base_command import BaseCommand
+
+logger = logging.getLogger(__name__)
+
+# Only available when the bootloader is built with the `upload_bench` feature
+BENCH_ENDPOINT = '/api/v1/bench/upload'
+
+
+class BenchCommand(BaseCommand):
+    COMMAND_NAME = 'bench'
+    COMMAND_HELP = 'Measure upload throughput to the bootloader (requires the upload_bench feature).'
+
+    def add_arguments(self, parser: argparse.ArgumentParser):
+        parser.add_argument(
+            '--size-mb',
+            type=int,
+            default=16,
+            help='Size of each upload in MiB (default: 16)',
+        )
+        parser.add_argument(
+            '--runs',
+            type=int,
+            default=3,
+            help='Number of uploads to average over (default: 3)',
+        )
+
+    def run(self, args: argparse.Namespace):
+        endpoint = f'{args.server_url.rstrip("/")}{BENCH_ENDPOINT}'
+        payload = bytes(args.size_mb * 1024 * 1024)
+        rates = []
+
+        with requests.Session() as session:
+            for run in range(1, args.runs + 1):
+                start = time.monotonic()
+                try:
+                    resp = session.post(
+                        endpoint, data=payload, headers=config.auth_headers(), timeout=config.REQUEST_TIMEOUT
+                    )
+                    resp.raise_for_status()
+                except requests.exceptions.RequestException as e:
+                    logger.error(f'Upload to {endpoint} failed: {e}')
+                    print('\nError: Upload failed. Is the bootloader built with the upload_bench feature?')
+                    return 1
+                elapsed = time.monotonic() - start
+
+                rate = len(payload) / elapsed / (1024 * 1024)
+                rates.append(rate)
+                print(f'Run {run}: {rate:.2f} MiB/s ({elapsed:.2f} s), device: {resp.text.strip()}')
+
+        print(f'Average: {sum(rates) / len(rates):.2f} MiB/s over {len(rates)} runs of {args.size_mb} MiB')
+        return 0
@@ -0,0 +1,136 @@
+import argparse
+import logging
+import socket
+import struct
+import time
+
+from proxyclient.commands.base_command import BaseCommand
+
+logger = logging.getLogger(__name__)
+
+MDNS_GROUP = '224.0.0.251'
+MDNS_PORT = 5353
+SERVICE_TYPE = '_neoboot._tcp.local'
+
+TYPE_A = 1
+TYPE_PTR = 12
+TYPE_TXT = 16
+TYPE_SRV = 33
+
+
+def _read_name(packet: bytes, offset: int) -> tuple[str, int]:
+    """Reads a possibly compressed name, returning it and the offset after it."""
+    labels = []
+    resume_at = None
+    for _ in range(64):
+        length = packet[offset]
+        if length & 0xC0 == 0xC0:
+            if resume_at is None:
+                resume_at = offset + 2
+            offset = ((length & 0x3F) << 8) | packet[offset + 1]
+        elif length == 0:
+            offset += 1
+            break
+        else:
+            labels.append(packet[offset + 1 : offset + 1 + length].decode(errors='replace'))
+            offset += 1 + length
+    return '.'.join(labels), resume_at if resume_at is not None else offset
+
+
+def _build_query() -> bytes:
+    header = struct.pack('!HHHHHH', 0, 0, 1, 0, 0, 0)
+    name = b''.join(bytes([len(label)]) + label.encode() for label in SERVICE_TYPE.split('.')) + b'\x00'
+    return header + name + struct.pack('!HH', TYPE_PTR, 1)
+
+
+def _parse_records(packet: bytes) -> list[tuple[str, int, object]]:
+    """Returns the (name, type, data) of all records in an mDNS response."""
+    _, flags, qdcount, ancount, nscount, arcount = struct.unpack_from('!HHHHHH', packet)
+    if not flags & 0x8000:
+        return []
+
+    offset = 12
+    for _ in range(qdcount):
+        _, offset = _read_name(packet, offset)
+        offset += 4
+
+    records = []
+    for _ in range(ancount + nscount + arcount):
+        name, offset = _read_name(packet, offset)
+        rtype, _, _, rdlength = struct.unpack_from('!HHIH', packet, offset)
+        offset += 10
+        rdata = packet[offset : offset + rdlength]
+        if rtype == TYPE_A and rdlength == 4:
+            data = socket.inet_ntoa(rdata)
+        elif rtype == TYPE_PTR:
+            data = _read_name(packet, offset)[0].lower()
+        elif rtype == TYPE_SRV:
+            port = struct.unpack_from('!H', packet, offset + 4)[0]
+            target, _ = _read_name(packet, offset + 6)
+            data = (target.lower(), port)
+        elif rtype == TYPE_TXT:
+            data, pos = {}, 0
+            while pos < len(rdata):
+                entry = rdata[pos + 1 : pos + 1 + rdata[pos]].decode(errors='replace')
+                pos += 1 + rdata[pos]
+                key, _, value = entry.partition('=')
+                if key:
+                    data[key] = value
+        else:
+            data = rdata
+        records.append((name.lower(), rtype, data))
+        offset += rdlength
+    return records
+
+
+class DiscoverCommand(BaseCommand):
+    COMMAND_NAME = 'discover'
+    COMMAND_HELP = 'Discover bootloaders on the local network using mDNS.'
+
+    def add_arguments(self, parser: argparse.ArgumentParser):
+        parser.add_argument(
+            '--timeout',
+            type=float,
+            default=3.0,
+            help='Seconds to wait for responses (default: 3.0)',
+        )
+
+    def run(self, args: argparse.Namespace):
+        sock = socket.socket(socket.AF_INET, socket.SOCK_DGRAM, socket.IPPROTO_UDP)
+        sock.setsockopt(socket.IPPROTO_IP, socket.IP_MULTICAST_TTL, 255)
+        # Queries from an ephemeral port are answered directly by unicast
+        sock.bind(('', 0))
+        sock.sendto(_build_query(), (MDNS_GROUP, MDNS_PORT))
+
+        records = []
+        deadline = time.monotonic() + args.timeout
+        while (remaining := deadline - time.monotonic()) > 0:
+            sock.settimeout(remaining)
+            try:
+                packet, source = sock.recvfrom(9000)
+            except socket.timeout:
+                break
+            try:
+                records.extend(_parse_records(packet))
+            except (struct.error, IndexError) as e:
+                logger.debug(f'Ignoring malformed response from {source[0]}: {e}')
+        sock.close()
+
+        addresses = {name: data for name, rtype, data in records if rtype == TYPE_A}
+        services = {name: data for name, rtype, data in records if rtype == TYPE_SRV}
+        txts = {name: data for name, rtype, data in records if rtype == TYPE_TXT}
+        instances = sorted({data for name, rtype, data in records if rtype == TYPE_PTR and name == SERVICE_TYPE})
+
+        if not instances:
+            print('No devices found.')
+            return 1
+
+        for instance in instances:
+            target, port = services.get(instance, ('', 0))
+            txt = txts.get(instance, {})
+            address = addresses.get(target, target)
+            print(f'{txt.get("id", instance)}: http://{address}:{port}{txt.get("path", "")}')
+            for key in ('arch', 'firmware', 'security'):
+                print(f'  {key}: {txt.get(key, "unknown")}')
+
+        return 0
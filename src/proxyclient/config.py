@@ -3,7 +3,11 @@ from pathlib import Path
 
 # Default server URL
 DEFAULT_SERVER_URL = os.environ.get('PROXYCLIENT_SERVER_URL', 'http://localhost:8080')
-API_ENDPOINT = '/api/v1/rpc'
+# The v2 endpoint takes the request framed in the body, so it is not bound by header size limits
+API_ENDPOINT = '/api/v2/rpc'
+
+# Bearer token, for bootloaders configured with a server auth_token
+AUTH_TOKEN = os.environ.get('PROXYCLIENT_AUTH_TOKEN')
 
 # Base directory for payload files (relative to project root or absolute)
 # Assumes you run the client from proxyclient_project/
@@ -34,3 +38,8 @@ SUPPORTED_ARCHS = list(LINUX_DEFAULTS.keys())
 
 # Request timeout
 REQUEST_TIMEOUT = 60  # seconds
+
+
+def auth_headers() -> dict[str, str]:
+    """Returns the Authorization header, if a token is configured."""
+    return {'Authorization': f'Bearer {AUTH_TOKEN}'} if AUTH_TOKEN else {}
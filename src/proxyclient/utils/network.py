@@ -1,5 +1,4 @@
 import logging
-import base64
 import hashlib
 from typing import Optional, Dict, Any
 import requests
@@ -93,6 +92,16 @@ class UploadStreamWithProgress(BytesIO):
             super().close()
 
 
+def encode_varint(value: int) -> bytes:
+    """Encodes a non-negative integer as a protobuf varint."""
+    encoded = bytearray()
+    while value > 0x7F:
+        encoded.append((value & 0x7F) | 0x80)
+        value >>= 7
+    encoded.append(value)
+    return bytes(encoded)
+
+
 def calculate_sha256(data: bytes) -> str:
     """Calculates the SHA256 hash of the given data."""
     return hashlib.sha256(data).hexdigest()
@@ -123,15 +132,14 @@ def send_request(
     endpoint = f'{server_url.rstrip("/")}{config.API_ENDPOINT}'
     logger.info(f'Sending request to {endpoint}')
 
-    # Prepare headers
+    # Frame the request: a varint length, the ClientRequest, then the raw payload
     try:
         serialized_req = request_proto.SerializeToString()
-        req_base64 = base64.b64encode(serialized_req)
-        headers = {'X-Client-Request': req_base64}
-        # Let requests handle Content-Type for data uploads
+        envelope = encode_varint(len(serialized_req)) + serialized_req
+        headers = {'Content-Type': 'application/x-protobuf', **config.auth_headers()}
         # Let requests calculate Content-Length when using file-like objects
     except Exception as e:
-        logger.error(f'Failed to serialize or encode request protobuf: {e}')
+        logger.error(f'Failed to serialize request protobuf: {e}')
         raise  # Re-raise as a critical setup error
 
     data_stream = None
@@ -141,16 +149,17 @@ def send_request(
         # Prepare data stream with progress if payload exists
         if payload:
             logger.info(f'Preparing payload ({len(payload)} bytes)')
-            data_stream = UploadStreamWithProgress(payload, desc=payload_desc)
+            data_stream = UploadStreamWithProgress(envelope + payload, desc=payload_desc)
             data_to_send = data_stream
         else:
             logger.debug('No payload to send.')
-            headers['Content-Length'] = '0'  # Explicitly set for no-body requests
-            data_to_send = None
+            data_to_send = envelope
 
         logger.debug(f'POST {endpoint}')
-        # Avoid logging full base64 header for security/readability
-        log_headers = {k: (v if k != 'X-Client-Request' else f'{v[:10]}...') for k, v in headers.items()}
+        logger.debug(f'Client request size: {len(serialized_req)}')
+        log_headers = dict(headers)
+        if 'Authorization' in log_headers:
+            log_headers['Authorization'] = '***'
         logger.debug(f'Headers: {log_headers}')
         if data_to_send:
             logger.debug(f'Payload size: {len(data_to_send)}')  # Log the length __len__ reports
@@ -1,79 +1,300 @@
-use crate::{errors::lwip_error::LwipError, ffi, utils::ip_addr_to_u32};
-use futures::lock::Mutex;
-use log::info;
+use crate::asyncio::net::UdpSocket;
+use crate::asyncio::sleep_ms;
+use crate::errors::resolve_error::ResolveError;
+use crate::{ffi, utils::sys_get_env};
+use futures::future::{select, Either};
+use log::{info, warn};
+use message::{Message, RecordData, CLASS_IN, RCODE_NXDOMAIN, RCODE_OK, TYPE_A};
 use once_cell::sync::Lazy;
 use std::{
-    future::Future,
+    collections::HashMap,
     net::{IpAddr, Ipv4Addr},
-    pin::Pin,
-    task::{Context, Poll},
+    sync::Mutex,
 };
 
-// default dns server
-const DEFAULT_DNS_SERVER: &str = "8.8.8.8";
+pub mod message;
 
-pub static GLOBAL_DNS_RESOLVER: Lazy<Dns> = Lazy::new(|| {
-    unsafe { ffi::env_net_dns_set_server(ip_addr_to_u32(DEFAULT_DNS_SERVER).unwrap()) };
-    Dns::new()
-});
+// fallback dns server, used when neither configuration nor DHCP provided one
+const FALLBACK_DNS_SERVER: Ipv4Addr = Ipv4Addr::new(8, 8, 8, 8);
 
+// U-Boot environment variables populated by `dhcp` or set by the user
+const DNS_ENV_VARS: [&str; 2] = ["dnsip", "dnsip2"];
+
+const DNS_PORT: u16 = 53;
+const QUERY_TIMEOUT_MS: u64 = 2000;
+const QUERY_ATTEMPTS: usize = 2;
+const MAX_RESPONSE_SIZE: usize = 512;
+/// Source ports are picked at random from the dynamic range (RFC 6335)
+const MIN_SOURCE_PORT: u16 = 49152;
+const SOURCE_PORT_ATTEMPTS: usize = 4;
+
+// Bounds applied to record TTLs before they are cached
+const MIN_CACHE_TTL_SECS: u32 = 5;
+const MAX_CACHE_TTL_SECS: u32 = 3600;
+const NEGATIVE_CACHE_TTL_SECS: u32 = 30;
+const MAX_CACHE_ENTRIES: usize = 64;
+
+pub static GLOBAL_DNS_RESOLVER: Lazy<Dns> = Lazy::new(Dns::from_env);
+
+struct CacheEntry {
+    // Empty for a cached NXDOMAIN
+    addrs: Vec<IpAddr>,
+    expires_at: u64,
+}
+
+/// Caching stub resolver that queries the configured servers directly over UDP.
+///
+/// Lookups do not share any state apart from the cache, so independent lookups
+/// run concurrently. Servers are tried in order until one of them answers.
 pub struct Dns {
-    lock: Mutex<()>,
+    servers: Mutex<Vec<Ipv4Addr>>,
+    cache: Mutex<HashMap<String, CacheEntry>>,
 }
 
 impl Dns {
-    fn new() -> Self {
+    fn new(servers: Vec<Ipv4Addr>) -> Self {
         Self {
-            lock: Mutex::new(()),
+            servers: Mutex::new(servers),
+            cache: Mutex::new(HashMap::new()),
         }
     }
-}
 
-impl Dns {
-    pub async fn get_host_by_name(&self, host: &str) -> Result<IpAddr, LwipError> {
-        let _guard = self.lock.lock().await;
-
-        let result = unsafe { ffi::env_net_dns_lookup(host.as_ptr(), host.len() as u32) };
+    /// Creates a resolver using the DNS servers found in the U-Boot environment
+    fn from_env() -> Self {
+        let servers: Vec<Ipv4Addr> = DNS_ENV_VARS
+            .iter()
+            .filter_map(|key| sys_get_env(key).ok())
+            .filter_map(|value| value.trim().parse().ok())
+            .collect();
 
-        if result != LwipError::Ok.to_code() {
-            return Err(LwipError::from_code(result));
+        if servers.is_empty() {
+            warn!(
+                "No DNS servers configured, falling back to {}",
+                FALLBACK_DNS_SERVER
+            );
+            return Self::new(vec![FALLBACK_DNS_SERVER]);
         }
 
-        poll_dns().await
+        info!("Using DNS servers: {:?}", servers);
+        Self::new(servers)
+    }
+
+    /// Replaces the list of servers, in order of preference, and flushes the cache
+    pub fn set_servers(&self, servers: Vec<Ipv4Addr>) {
+        *self.servers.lock().unwrap() = servers;
+        self.flush_cache();
+    }
+
+    pub fn servers(&self) -> Vec<Ipv4Addr> {
+        self.servers.lock().unwrap().clone()
     }
-}
 
-/// Future that polls DNS resolution
-async fn poll_dns() -> Result<IpAddr, LwipError> {
-    struct DnsPollingFuture;
+    pub fn flush_cache(&self) {
+        self.cache.lock().unwrap().clear();
+    }
 
-    impl Future for DnsPollingFuture {
-        type Output = Result<IpAddr, LwipError>;
+    /// Resolves a hostname to the first address returned
+    pub async fn get_host_by_name(&self, host: &str) -> Result<IpAddr, ResolveError> {
+        let addrs = self.lookup(host).await?;
+        addrs
+            .first()
+            .copied()
+            .ok_or_else(|| ResolveError::NoAddress(host.to_string()))
+    }
 
-        fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
-            unsafe { ffi::env_net_rx() };
+    /// Resolves a hostname to all of its IPv4 addresses
+    pub async fn lookup(&self, host: &str) -> Result<Vec<IpAddr>, ResolveError> {
+        if let Ok(ip) = host.parse::<IpAddr>() {
+            return Ok(vec![ip]);
+        }
 
-            let status = unsafe { ffi::env_net_dns_lookup_poll() };
+        let host = host.trim_end_matches('.').to_ascii_lowercase();
+        if let Some(cached) = self.cached(&host) {
+            return cached;
+        }
 
-            match LwipError::from_code(status) {
-                LwipError::Ok => {
-                    let result = unsafe { ffi::env_net_dns_lookup_result() };
-                    let chunks = result.to_be_bytes();
-                    let ip = Ipv4Addr::new(chunks[3], chunks[2], chunks[1], chunks[0]);
-                    info!("DNS lookup result: {}", ip);
-                    Poll::Ready(Ok(IpAddr::V4(ip)))
+        let servers = self.servers();
+        if servers.is_empty() {
+            return Err(ResolveError::NoServers);
+        }
+
+        let mut last_err = ResolveError::Timeout;
+        for server in servers {
+            for _ in 0..QUERY_ATTEMPTS {
+                match self.query(server, &host).await {
+                    Ok((addrs, ttl)) => {
+                        info!("DNS lookup result for {}: {:?}", host, addrs);
+                        self.insert(&host, addrs.clone(), ttl);
+                        return Ok(addrs);
+                    }
+                    Err(err @ ResolveError::NotFound(_)) => {
+                        // The server answered authoritatively that the name does not exist
+                        self.insert(&host, vec![], NEGATIVE_CACHE_TTL_SECS);
+                        return Err(err);
+                    }
+                    // Asking another server gives the same answer
+                    Err(err @ (ResolveError::NoAddress(_) | ResolveError::InvalidName(_))) => {
+                        return Err(err)
+                    }
+                    Err(err) => {
+                        warn!("DNS query for {} via {} failed: {}", host, server, err);
+                        // Do not retry servers that cannot be reached at all
+                        let retry = matches!(err, ResolveError::Timeout);
+                        last_err = err;
+                        if !retry {
+                            break;
+                        }
+                    }
                 }
-                LwipError::InProgress => {
-                    cx.waker().wake_by_ref();
-                    Poll::Pending
+            }
+        }
+
+        Err(last_err)
+    }
+
+    fn cached(&self, host: &str) -> Option<Result<Vec<IpAddr>, ResolveError>> {
+        let now = unsafe { ffi::env_now() };
+        let mut cache = self.cache.lock().unwrap();
+        match cache.get(host) {
+            Some(entry) if entry.expires_at > now => {
+                if entry.addrs.is_empty() {
+                    Some(Err(ResolveError::NotFound(host.to_string())))
+                } else {
+                    Some(Ok(entry.addrs.clone()))
                 }
-                err => {
-                    info!("DNS lookup failed result: {}", status);
-                    Poll::Ready(Err(err))
+            }
+            Some(_) => {
+                cache.remove(host);
+                None
+            }
+            None => None,
+        }
+    }
+
+    fn insert(&self, host: &str, addrs: Vec<IpAddr>, ttl_secs: u32) {
+        let now = unsafe { ffi::env_now() };
+        let ttl_ms = ttl_secs.clamp(MIN_CACHE_TTL_SECS, MAX_CACHE_TTL_SECS) as u64 * 1000;
+        let mut cache = self.cache.lock().unwrap();
+
+        if cache.len() >= MAX_CACHE_ENTRIES {
+            cache.retain(|_, entry| entry.expires_at > now);
+        }
+        if cache.len() >= MAX_CACHE_ENTRIES {
+            // Evict the entry closest to expiring
+            let oldest = cache
+                .iter()
+                .min_by_key(|(_, entry)| entry.expires_at)
+                .map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                cache.remove(&oldest);
+            }
+        }
+
+        cache.insert(
+            host.to_string(),
+            CacheEntry {
+                addrs,
+                expires_at: now + ttl_ms,
+            },
+        );
+    }
+
+    /// Sends a single query to `server` and returns the addresses and the smallest TTL
+    async fn query(
+        &self,
+        server: Ipv4Addr,
+        host: &str,
+    ) -> Result<(Vec<IpAddr>, u32), ResolveError> {
+        // Random ids and ports keep off-path hosts from forging replies
+        let id = random_u16()?;
+        let request = Message::query(id, host, TYPE_A)
+            .encode()
+            .map_err(|_| ResolveError::InvalidName(host.to_string()))?;
+
+        let socket = bind_random_port()?;
+        socket.connect(server, DNS_PORT)?;
+        socket.send(&request).await?;
+
+        let receive = Box::pin(async {
+            let mut buf = vec![0u8; MAX_RESPONSE_SIZE];
+            loop {
+                let len = socket.recv(&mut buf).await?;
+                match Message::decode(&buf[..len]) {
+                    Ok(response)
+                        if response.id == id
+                            && response.is_response()
+                            && answers_question(&response, host) =>
+                    {
+                        return Ok::<_, ResolveError>(response)
+                    }
+                    // Ignore stray or malformed datagrams
+                    _ => continue,
                 }
             }
+        });
+
+        let response = match select(receive, Box::pin(sleep_ms(QUERY_TIMEOUT_MS))).await {
+            Either::Left((response, _)) => response?,
+            Either::Right((_, _)) => return Err(ResolveError::Timeout),
+        };
+
+        match response.rcode() {
+            RCODE_OK => (),
+            RCODE_NXDOMAIN => return Err(ResolveError::NotFound(host.to_string())),
+            rcode => return Err(ResolveError::ServerFailure(rcode)),
         }
+
+        let mut ttl = u32::MAX;
+        let addrs: Vec<IpAddr> = response
+            .answers
+            .iter()
+            .filter_map(|record| match record.data {
+                RecordData::A(addr) => {
+                    ttl = ttl.min(record.ttl);
+                    Some(IpAddr::V4(addr))
+                }
+                _ => None,
+            })
+            .collect();
+
+        if addrs.is_empty() {
+            return Err(ResolveError::NoAddress(host.to_string()));
+        }
+
+        Ok((addrs, ttl))
     }
+}
 
-    DnsPollingFuture.await
+/// Whether `response` carries the single A question that was asked for `host`,
+/// so a spoofed or misrouted reply for another name is not accepted
+fn answers_question(response: &Message, host: &str) -> bool {
+    match response.questions.as_slice() {
+        [question] => {
+            question.qtype == TYPE_A
+                && question.qclass == CLASS_IN
+                && question
+                    .name
+                    .trim_end_matches('.')
+                    .eq_ignore_ascii_case(host)
+        }
+        _ => false,
+    }
+}
+
+fn random_u16() -> Result<u16, ResolveError> {
+    let mut bytes = [0u8; 2];
+    getrandom::getrandom(&mut bytes).map_err(ResolveError::Entropy)?;
+    Ok(u16::from_ne_bytes(bytes))
+}
+
+/// Binds a socket to a random port, falling back to the next ephemeral port
+/// of the stack when the random ones are taken
+fn bind_random_port() -> Result<UdpSocket, ResolveError> {
+    for _ in 0..SOURCE_PORT_ATTEMPTS {
+        let port = MIN_SOURCE_PORT + random_u16()? % (u16::MAX - MIN_SOURCE_PORT + 1);
+        if let Ok(socket) = UdpSocket::bind(Ipv4Addr::UNSPECIFIED, port) {
+            return Ok(socket);
+        }
+    }
+    Ok(UdpSocket::bind(Ipv4Addr::UNSPECIFIED, 0)?)
 }
@@ -0,0 +1,343 @@
+use crate::errors::protocol_error::ProtocolError;
+use std::net::Ipv4Addr;
+
+/// Record types used by the resolver and the mDNS responder
+pub const TYPE_A: u16 = 1;
+pub const TYPE_CNAME: u16 = 5;
+pub const TYPE_PTR: u16 = 12;
+pub const TYPE_TXT: u16 = 16;
+pub const TYPE_SRV: u16 = 33;
+pub const TYPE_ANY: u16 = 255;
+
+/// Internet class
+pub const CLASS_IN: u16 = 1;
+
+/// Top bit of the class, the unicast-response bit in mDNS questions and the
+/// cache-flush bit in mDNS records (RFC 6762, section 10.2)
+pub const CLASS_MDNS_FLAG: u16 = 0x8000;
+
+/// Header flags
+pub const FLAG_RESPONSE: u16 = 0x8000;
+pub const FLAG_AUTHORITATIVE: u16 = 0x0400;
+
+/// Response codes
+pub const RCODE_OK: u8 = 0;
+pub const RCODE_NXDOMAIN: u8 = 3;
+
+/// Maximum number of compression pointers followed when reading a name
+const MAX_POINTER_JUMPS: usize = 16;
+
+/// Smallest encoded question, a root name followed by its type and class
+const MIN_QUESTION_LEN: usize = 5;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Question {
+    pub name: String,
+    pub qtype: u16,
+    pub qclass: u16,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RecordData {
+    A(Ipv4Addr),
+    Cname(String),
+    Ptr(String),
+    Srv {
+        priority: u16,
+        weight: u16,
+        port: u16,
+        target: String,
+    },
+    Txt(Vec<String>),
+    Other(Vec<u8>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Record {
+    pub name: String,
+    pub rtype: u16,
+    pub rclass: u16,
+    pub ttl: u32,
+    pub data: RecordData,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Message {
+    pub id: u16,
+    pub flags: u16,
+    pub questions: Vec<Question>,
+    pub answers: Vec<Record>,
+    pub authorities: Vec<Record>,
+    pub additionals: Vec<Record>,
+}
+
+impl Message {
+    /// Creates a recursive query for a single name
+    pub fn query(id: u16, name: &str, qtype: u16) -> Self {
+        Self {
+            id,
+            // Recursion desired
+            flags: 0x0100,
+            questions: vec![Question {
+                name: name.to_string(),
+                qtype,
+                qclass: CLASS_IN,
+            }],
+            ..Default::default()
+        }
+    }
+
+    pub fn is_response(&self) -> bool {
+        self.flags & FLAG_RESPONSE != 0
+    }
+
+    pub fn opcode(&self) -> u8 {
+        ((self.flags >> 11) & 0x000F) as u8
+    }
+
+    pub fn rcode(&self) -> u8 {
+        (self.flags & 0x000F) as u8
+    }
+
+    pub fn encode(&self) -> Result<Vec<u8>, ProtocolError> {
+        let mut buf = Vec::with_capacity(512);
+        buf.extend_from_slice(&self.id.to_be_bytes());
+        buf.extend_from_slice(&self.flags.to_be_bytes());
+        for count in [
+            self.questions.len(),
+            self.answers.len(),
+            self.authorities.len(),
+            self.additionals.len(),
+        ] {
+            buf.extend_from_slice(&(count as u16).to_be_bytes());
+        }
+
+        for question in &self.questions {
+            write_name(&mut buf, &question.name)?;
+            buf.extend_from_slice(&question.qtype.to_be_bytes());
+            buf.extend_from_slice(&question.qclass.to_be_bytes());
+        }
+
+        for record in self
+            .answers
+            .iter()
+            .chain(&self.authorities)
+            .chain(&self.additionals)
+        {
+            write_record(&mut buf, record)?;
+        }
+
+        Ok(buf)
+    }
+
+    pub fn decode(buf: &[u8]) -> Result<Self, ProtocolError> {
+        let mut reader = Reader { buf, pos: 0 };
+        let id = reader.read_u16()?;
+        let flags = reader.read_u16()?;
+        let qdcount = reader.read_u16()?;
+        let ancount = reader.read_u16()?;
+        let nscount = reader.read_u16()?;
+        let arcount = reader.read_u16()?;
+
+        // The counts are untrusted, a question takes at least MIN_QUESTION_LEN bytes
+        let remaining = buf.len() - reader.pos;
+        let mut questions =
+            Vec::with_capacity((qdcount as usize).min(remaining / MIN_QUESTION_LEN));
+        for _ in 0..qdcount {
+            questions.push(Question {
+                name: reader.read_name()?,
+                qtype: reader.read_u16()?,
+                qclass: reader.read_u16()?,
+            });
+        }
+
+        let mut read_records = |count: u16| -> Result<Vec<Record>, ProtocolError> {
+            (0..count).map(|_| reader.read_record()).collect()
+        };
+        let answers = read_records(ancount)?;
+        let authorities = read_records(nscount)?;
+        let additionals = read_records(arcount)?;
+
+        Ok(Self {
+            id,
+            flags,
+            questions,
+            answers,
+            authorities,
+            additionals,
+        })
+    }
+}
+
+fn write_name(buf: &mut Vec<u8>, name: &str) -> Result<(), ProtocolError> {
+    for label in name.trim_end_matches('.').split('.') {
+        if label.is_empty() {
+            continue;
+        }
+        if label.len() > 63 {
+            return Err(ProtocolError::TooLong("DNS label"));
+        }
+        buf.push(label.len() as u8);
+        buf.extend_from_slice(label.as_bytes());
+    }
+    buf.push(0);
+    Ok(())
+}
+
+fn write_record(buf: &mut Vec<u8>, record: &Record) -> Result<(), ProtocolError> {
+    write_name(buf, &record.name)?;
+    buf.extend_from_slice(&record.rtype.to_be_bytes());
+    buf.extend_from_slice(&record.rclass.to_be_bytes());
+    buf.extend_from_slice(&record.ttl.to_be_bytes());
+
+    let mut rdata = Vec::new();
+    match &record.data {
+        RecordData::A(addr) => rdata.extend_from_slice(&addr.octets()),
+        RecordData::Cname(name) | RecordData::Ptr(name) => write_name(&mut rdata, name)?,
+        RecordData::Srv {
+            priority,
+            weight,
+            port,
+            target,
+        } => {
+            rdata.extend_from_slice(&priority.to_be_bytes());
+            rdata.extend_from_slice(&weight.to_be_bytes());
+            rdata.extend_from_slice(&port.to_be_bytes());
+            write_name(&mut rdata, target)?;
+        }
+        RecordData::Txt(entries) => {
+            for entry in entries {
+                if entry.len() > 255 {
+                    return Err(ProtocolError::TooLong("TXT entry"));
+                }
+                rdata.push(entry.len() as u8);
+                rdata.extend_from_slice(entry.as_bytes());
+            }
+            // A TXT record must contain at least one string
+            if entries.is_empty() {
+                rdata.push(0);
+            }
+        }
+        RecordData::Other(data) => rdata.extend_from_slice(data),
+    }
+    buf.extend_from_slice(&(rdata.len() as u16).to_be_bytes());
+    buf.extend_from_slice(&rdata);
+    Ok(())
+}
+
+struct Reader<'b> {
+    buf: &'b [u8],
+    pos: usize,
+}
+
+impl Reader<'_> {
+    fn read_bytes(&mut self, len: usize) -> Result<&[u8], ProtocolError> {
+        let end = self.pos.checked_add(len).ok_or(ProtocolError::Truncated)?;
+        let bytes = self
+            .buf
+            .get(self.pos..end)
+            .ok_or(ProtocolError::Truncated)?;
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn read_u16(&mut self) -> Result<u16, ProtocolError> {
+        let bytes = self.read_bytes(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn read_u32(&mut self) -> Result<u32, ProtocolError> {
+        let bytes = self.read_bytes(4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    /// Reads a possibly compressed name, leaving the cursor after the name
+    fn read_name(&mut self) -> Result<String, ProtocolError> {
+        let mut labels: Vec<String> = Vec::new();
+        let mut pos = self.pos;
+        let mut resume_at = None;
+        let mut jumps = 0;
+
+        loop {
+            let len = *self.buf.get(pos).ok_or(ProtocolError::Truncated)? as usize;
+            match len & 0xC0 {
+                0x00 => {
+                    pos += 1;
+                    if len == 0 {
+                        break;
+                    }
+                    let label = self
+                        .buf
+                        .get(pos..pos + len)
+                        .ok_or(ProtocolError::Truncated)?;
+                    labels.push(String::from_utf8_lossy(label).to_string());
+                    pos += len;
+                }
+                0xC0 => {
+                    let low = *self.buf.get(pos + 1).ok_or(ProtocolError::Truncated)? as usize;
+                    if resume_at.is_none() {
+                        resume_at = Some(pos + 2);
+                    }
+                    jumps += 1;
+                    if jumps > MAX_POINTER_JUMPS {
+                        return Err(ProtocolError::Malformed("too many compression pointers"));
+                    }
+                    pos = ((len & 0x3F) << 8) | low;
+                }
+                _ => return Err(ProtocolError::Malformed("reserved label type")),
+            }
+        }
+
+        self.pos = resume_at.unwrap_or(pos);
+        Ok(labels.join("."))
+    }
+
+    fn read_record(&mut self) -> Result<Record, ProtocolError> {
+        let name = self.read_name()?;
+        let rtype = self.read_u16()?;
+        let rclass = self.read_u16()?;
+        let ttl = self.read_u32()?;
+        let rdlength = self.read_u16()? as usize;
+        let rdata_end = self.pos + rdlength;
+
+        let data = match rtype {
+            TYPE_A if rdlength == 4 => {
+                let octets = self.read_bytes(4)?;
+                RecordData::A(Ipv4Addr::new(octets[0], octets[1], octets[2], octets[3]))
+            }
+            TYPE_CNAME => RecordData::Cname(self.read_name()?),
+            TYPE_PTR => RecordData::Ptr(self.read_name()?),
+            TYPE_SRV => RecordData::Srv {
+                priority: self.read_u16()?,
+                weight: self.read_u16()?,
+                port: self.read_u16()?,
+                target: self.read_name()?,
+            },
+            TYPE_TXT => {
+                let mut entries = Vec::new();
+                let mut rdata = self.read_bytes(rdlength)?;
+                while let Some((&len, rest)) = rdata.split_first() {
+                    let entry = rest.get(..len as usize).ok_or(ProtocolError::Truncated)?;
+                    if !entry.is_empty() {
+                        entries.push(String::from_utf8_lossy(entry).to_string());
+                    }
+                    rdata = &rest[len as usize..];
+                }
+                RecordData::Txt(entries)
+            }
+            _ => RecordData::Other(self.read_bytes(rdlength)?.to_vec()),
+        };
+
+        if self.pos != rdata_end {
+            return Err(ProtocolError::Malformed("record data length mismatch"));
+        }
+
+        Ok(Record {
+            name,
+            rtype,
+            rclass,
+            ttl,
+            data,
+        })
+    }
+}
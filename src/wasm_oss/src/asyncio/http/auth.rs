@@ -0,0 +1,123 @@
+use crate::configuration::TlsConfig;
+use crate::errors::http_error::HttpError;
+use base64::prelude::*;
+use futures::future::LocalBoxFuture;
+use rustls_pki_types::pem::PemObject;
+use rustls_pki_types::{CertificateDer, PrivateKeyDer};
+use std::error::Error;
+use std::rc::Rc;
+
+/// Credentials sent in the `Authorization` header
+#[derive(Clone, PartialEq, Eq)]
+pub enum Credentials {
+    Bearer(String),
+    Basic { username: String, password: String },
+}
+
+impl Credentials {
+    pub fn header_value(&self) -> String {
+        match self {
+            Self::Bearer(token) => format!("Bearer {}", token),
+            Self::Basic { username, password } => format!(
+                "Basic {}",
+                BASE64_STANDARD.encode(format!("{}:{}", username, password))
+            ),
+        }
+    }
+}
+
+// Requests are logged, so the secrets are left out
+impl std::fmt::Debug for Credentials {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Bearer(_) => f.write_str("Bearer(***)"),
+            Self::Basic { username, .. } => f
+                .debug_struct("Basic")
+                .field("username", username)
+                .field("password", &"***")
+                .finish(),
+        }
+    }
+}
+
+/// Source of bearer tokens that expire, such as OAuth access tokens
+pub trait TokenProvider {
+    /// Returns a valid token, fetching a new one if the current one expired
+    fn token(&self) -> LocalBoxFuture<'_, Result<String, Box<dyn Error + Send + Sync>>>;
+
+    /// Drops the current token after the server rejected it, so that the
+    /// next call to `token` fetches a new one
+    fn invalidate(&self);
+}
+
+/// How the client authenticates its requests
+#[derive(Clone)]
+pub enum Auth<'a> {
+    Static(Credentials),
+    Provider(Rc<dyn TokenProvider + 'a>),
+}
+
+impl Auth<'_> {
+    /// Value of the `Authorization` header, asking the provider for a token if needed
+    pub async fn header_value(&self) -> Result<String, HttpError> {
+        match self {
+            Self::Static(credentials) => Ok(credentials.header_value()),
+            Self::Provider(provider) => {
+                let token = provider.token().await.map_err(HttpError::Auth)?;
+                Ok(Credentials::Bearer(token).header_value())
+            }
+        }
+    }
+}
+
+/// Certificate chain and private key presented to servers that require TLS
+/// client authentication
+pub struct ClientIdentity {
+    pub cert_chain: Vec<CertificateDer<'static>>,
+    pub key: PrivateKeyDer<'static>,
+}
+
+impl Clone for ClientIdentity {
+    fn clone(&self) -> Self {
+        Self {
+            cert_chain: self.cert_chain.clone(),
+            key: self.key.clone_key(),
+        }
+    }
+}
+
+impl std::fmt::Debug for ClientIdentity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ClientIdentity")
+            .field("certificates", &self.cert_chain.len())
+            .finish()
+    }
+}
+
+impl ClientIdentity {
+    /// Parses a PEM certificate chain, leaf first, and a PEM private key in
+    /// PKCS#8, SEC1 or PKCS#1 format
+    pub fn from_pem(cert_chain: &str, key: &str) -> Result<Self, HttpError> {
+        let cert_chain = CertificateDer::pem_slice_iter(cert_chain.as_bytes())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| HttpError::Pem("certificate", e))?;
+        if cert_chain.is_empty() {
+            return Err(HttpError::Identity("no certificate found"));
+        }
+        let key = PrivateKeyDer::from_pem_slice(key.as_bytes())
+            .map_err(|e| HttpError::Pem("private key", e))?;
+
+        Ok(Self { cert_chain, key })
+    }
+
+    /// Loads the certificate and key the device was provisioned with, if any
+    pub fn from_config(config: &TlsConfig) -> Result<Option<Self>, HttpError> {
+        match (&config.client_cert, &config.client_key) {
+            (Some(cert_chain), Some(key)) => Self::from_pem(cert_chain, key).map(Some),
+            (None, None) => Ok(None),
+            _ => Err(HttpError::Identity(
+                "certificate and key must be set together",
+            )),
+        }
+    }
+}
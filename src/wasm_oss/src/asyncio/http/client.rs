@@ -1,3 +1,9 @@
+use super::auth::{Auth, ClientIdentity, Credentials, TokenProvider};
+use super::decode::{ContentEncoding, Decoder, ACCEPT_ENCODING};
+use super::policy::{RedirectPolicy, RetryPolicy, SENSITIVE_HEADERS};
+use super::pool::{Pool, PoolConfig, PoolKey, Sender};
+use super::proxy::Proxy;
+use super::request::Body;
 use super::request::RequestBody;
 use super::request::RequestConfig;
 use super::response::Response;
@@ -9,23 +15,60 @@ use super::timeout::setup_timeout_task;
 use super::timeout::timeout_with_controller;
 use super::timeout::TimeoutController;
 use super::tls::create_tls_connector;
+use super::websocket::{handshake, WebSocket, WebSocketConfig};
 use crate::asyncio::dns::GLOBAL_DNS_RESOLVER;
 use crate::asyncio::net::TcpStream;
+use crate::asyncio::sleep_ms;
+use crate::configuration::{Configuration, ProxyConfig};
+use crate::errors::http_error::HttpError;
+use crate::errors::resolve_error::ResolveError;
 use crate::executor::Executor;
+use crate::ffi;
 use async_fn_stream::try_fn_stream;
 use bytes::Bytes;
+use futures::future::{select, Either};
+use futures::StreamExt;
 use http::Method;
 use http::Request;
-use http_body_util::BodyExt;
-use log::warn;
+use http_body_util::{BodyExt, Full, StreamBody};
+use hyper::body::Frame;
+use log::{debug, warn};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
 use rustls_pki_types::DnsName;
+use std::cell::RefCell;
 use std::collections::HashMap;
+use std::net::IpAddr;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
 use url::Url;
 
+/// Whether the client uses HTTP/2
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Http2Mode {
+    Disabled,
+    /// Offered via ALPN over TLS, cleartext requests use HTTP/1.1
+    Negotiate,
+    /// Also used over cleartext, for servers known to support it (RFC 9113, section 3.3)
+    PriorKnowledge,
+}
+
 pub struct Client<'a> {
     executor: Executor<'a>,
     base_url: String,
     default_headers: HashMap<String, String>,
+    proxy: ProxyConfig,
+    pool: Rc<RefCell<Pool>>,
+    redirect_policy: RedirectPolicy,
+    retry_policy: RetryPolicy,
+    /// Source of the backoff jitter
+    rng: StdRng,
+    decompress: bool,
+    http2: Http2Mode,
+    auth: Option<Auth<'a>>,
+    identity: Option<ClientIdentity>,
+    /// Whether server certificates are checked for requests without credentials
+    verify_tls: bool,
 }
 
 impl<'a> Client<'a> {
@@ -34,9 +77,29 @@ impl<'a> Client<'a> {
             executor,
             base_url: String::new(),
             default_headers: HashMap::new(),
+            proxy: ProxyConfig::default(),
+            pool: Rc::new(RefCell::new(Pool::new(PoolConfig::default()))),
+            redirect_policy: RedirectPolicy::default(),
+            retry_policy: RetryPolicy::default(),
+            rng: StdRng::seed_from_u64(unsafe { ffi::env_now() }),
+            decompress: true,
+            http2: Http2Mode::Negotiate,
+            auth: None,
+            identity: None,
+            verify_tls: true,
         }
     }
 
+    /// Creates a client with the proxies and the TLS settings from `config`,
+    /// as loaded from the embedded config and the U-Boot environment
+    pub fn from_config(executor: Executor<'a>, config: &Configuration) -> Result<Self, HttpError> {
+        let mut client = Self::new(executor)
+            .with_proxy(config.proxy.clone())
+            .with_tls_verification(!config.tls.insecure);
+        client.identity = ClientIdentity::from_config(&config.tls)?;
+        Ok(client)
+    }
+
     pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
         self.base_url = base_url.into();
         self
@@ -47,6 +110,79 @@ impl<'a> Client<'a> {
         self
     }
 
+    /// Routes requests through the proxies in `proxy`, unless the host is excluded
+    pub fn with_proxy(mut self, proxy: ProxyConfig) -> Self {
+        self.proxy = proxy;
+        self
+    }
+
+    /// Limits how many connections are kept open for reuse, and for how long
+    pub fn with_pool_config(mut self, config: PoolConfig) -> Self {
+        self.pool = Rc::new(RefCell::new(Pool::new(config)));
+        self
+    }
+
+    pub fn with_redirect_policy(mut self, policy: RedirectPolicy) -> Self {
+        self.redirect_policy = policy;
+        self
+    }
+
+    pub fn with_retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry_policy = policy;
+        self
+    }
+
+    /// Whether to ask for compressed responses and decompress them, which
+    /// is skipped for requests that set `Accept-Encoding` themselves
+    pub fn with_decompression(mut self, decompress: bool) -> Self {
+        self.decompress = decompress;
+        self
+    }
+
+    pub fn with_http2(mut self, mode: Http2Mode) -> Self {
+        self.http2 = mode;
+        self
+    }
+
+    /// Authenticates requests with a fixed token, unless they set `Authorization` themselves
+    pub fn with_bearer_token(mut self, token: impl Into<String>) -> Self {
+        self.auth = Some(Auth::Static(Credentials::Bearer(token.into())));
+        self
+    }
+
+    pub fn with_basic_auth(
+        mut self,
+        username: impl Into<String>,
+        password: impl Into<String>,
+    ) -> Self {
+        self.auth = Some(Auth::Static(Credentials::Basic {
+            username: username.into(),
+            password: password.into(),
+        }));
+        self
+    }
+
+    /// Authenticates requests with tokens from `provider`. A request rejected
+    /// with 401 is sent once more with a fresh token.
+    pub fn with_token_provider(mut self, provider: impl TokenProvider + 'a) -> Self {
+        self.auth = Some(Auth::Provider(Rc::new(provider)));
+        self
+    }
+
+    /// Presents `identity` to servers that ask for a TLS client certificate
+    pub fn with_client_identity(mut self, identity: ClientIdentity) -> Self {
+        self.identity = Some(identity);
+        self
+    }
+
+    /// Whether to check server certificates, which is the default. Turning
+    /// it off only affects requests without credentials, which are never
+    /// sent to a server that was not verified.
+    pub fn with_tls_verification(mut self, verify: bool) -> Self {
+        self.verify_tls = verify;
+        self
+    }
+
     fn build_full_url(&self, url: &str) -> String {
         if url.starts_with("http://") || url.starts_with("https://") {
             url.to_string()
@@ -60,15 +196,27 @@ impl<'a> Client<'a> {
     }
 
     async fn perform_request(
-        &mut self,
+        &self,
         method: Method,
         url: String,
         config: RequestConfig,
-    ) -> Result<Response, Box<dyn std::error::Error>> {
+        strip_credentials: bool,
+    ) -> Result<Response<'a>, HttpError> {
+        let executor_clone = self.executor.clone();
+        let proxy_config = self.proxy.clone();
+        let default_headers = self.default_headers.clone();
+        let pool = self.pool.clone();
+        let http2 = self.http2;
+        let identity = self.identity.clone();
+        let verify_tls = self.verify_tls;
+        let negotiate_encoding = self.decompress
+            && !default_headers
+                .keys()
+                .chain(config.headers.keys())
+                .any(|key| key.eq_ignore_ascii_case("accept-encoding"));
         let body_stream = try_fn_stream(|emitter| {
             let method_clone = method.clone();
             let config_clone = config.clone();
-            let executor_clone = self.executor.clone();
             let timeout_ms = config_clone.timeout.as_millis() as u64;
 
             async move {
@@ -79,13 +227,7 @@ impl<'a> Client<'a> {
                 let conn_timeout_controller = TimeoutController::new(timeout_ms);
                 setup_timeout_task(&executor_clone, conn_timeout_controller.clone()).await;
 
-                // DNS resolution
-                let host = url.host().ok_or("Missing host in URL")?;
-
-                let ip = match GLOBAL_DNS_RESOLVER.get_host_by_name(host).await {
-                    Ok(ip) => ip,
-                    Err(e) => return Err(format!("DNS resolution failed: {}", e).into()),
-                };
+                let host = url.host().ok_or(HttpError::MissingHost)?;
 
                 // Determine port
                 let port =
@@ -93,118 +235,157 @@ impl<'a> Client<'a> {
                         .map(|p| p.as_u16())
                         .unwrap_or(if is_https { 443 } else { 80 });
 
-                // Connect to server
-                let tcp_stream = match timeout_with_controller(
-                    conn_timeout_controller.clone(),
-                    TcpStream::connect(ip.to_string().as_str(), port),
-                )
-                .await
-                {
-                    Ok(stream) => stream?,
-                    Err(e) => return Err(format!("Connection failed: {}", e).into()),
-                };
+                // Connect to the proxy instead, if one applies to the host
+                let proxy = Proxy::for_host(&proxy_config, host, is_https)?;
 
-                // Set up HTTP or HTTPS stream
-                let mut stream = AnyHttpStream::Http(tcp_stream.clone());
-                if is_https {
-                    let host_str = String::from(host);
-                    let dnsname = DnsName::try_from_str(&host_str)?;
-                    let server_name = rustls_pki_types::ServerName::DnsName(dnsname.to_owned());
+                // Certificates are checked unless turned off, and always when
+                // credentials are sent
+                let verify = is_https
+                    && (verify_tls
+                        || identity.is_some()
+                        || (!strip_credentials
+                            && default_headers
+                                .keys()
+                                .chain(config_clone.headers.keys())
+                                .any(|key| is_sensitive_header(key))));
 
-                    let connector = create_tls_connector();
-                    match timeout_with_controller(
-                        conn_timeout_controller.clone(),
-                        connector.connect(server_name, tcp_stream.clone()),
-                    )
-                    .await
+                let key = PoolKey {
+                    https: is_https,
+                    host: host.to_string(),
+                    port,
+                    verified: verify,
+                };
+                let forward_proxy = proxy.as_ref().filter(|_| !is_https);
+
+                // Reuse an idle connection to the same server, if one is still usable.
+                // A body stream can only be sent once, so it always gets a fresh
+                // connection instead of one that may turn out to be stale.
+                let replayable = config_clone
+                    .body
+                    .as_ref()
+                    .is_none_or(RequestBody::is_replayable);
+                let mut sender = None;
+                let pooled = if replayable {
+                    pool.borrow_mut().checkout(&key)
+                } else {
+                    None
+                };
+                if let Some(mut pooled) = pooled {
+                    if let Ok(Ok(())) =
+                        timeout_with_controller(conn_timeout_controller.clone(), pooled.ready())
+                            .await
                     {
-                        Ok(Ok(tls_stream)) => stream = AnyHttpStream::Https(Box::new(tls_stream)),
-                        Ok(Err(e)) => return Err(format!("TLS handshake failed: {}", e).into()),
-                        Err(_) => return Err("TLS handshake timed out".into()),
+                        sender = Some(pooled);
                     }
                 }
-
-                // HTTP handshake
-                let (mut sender, conn) = match timeout_with_controller(
-                    conn_timeout_controller.clone(),
-                    hyper::client::conn::http1::handshake(stream),
-                )
-                .await
-                {
-                    Ok(result) => result?,
-                    Err(_) => return Err("HTTP handshake timed out".into()),
+                let mut reused = sender.is_some();
+                let mut sender = match sender {
+                    Some(sender) => sender,
+                    None => {
+                        let sender = Self::connect(
+                            &executor_clone,
+                            &url,
+                            proxy.as_ref(),
+                            http2,
+                            identity.as_ref(),
+                            verify,
+                            conn_timeout_controller.clone(),
+                        )
+                        .await?;
+                        // Further requests share an HTTP/2 connection while this one is in flight
+                        if let Some(shared) = sender.share() {
+                            pool.borrow_mut().checkin(key.clone(), shared);
+                        }
+                        sender
+                    }
                 };
 
-                // Spawn connection handler
-                self.executor.spawn(async move {
-                    if let Err(err) = conn.await {
-                        warn!("Connection failed: {:?}", err);
+                // Send request
+                let mut res = loop {
+                    let mut req = Self::build_request(
+                        &default_headers,
+                        &config_clone,
+                        &method_clone,
+                        &url,
+                        forward_proxy,
+                        strip_credentials,
+                        negotiate_encoding,
+                    )?;
+                    if sender.is_http2() {
+                        // The target goes in the :scheme, :authority and :path pseudo-headers
+                        *req.uri_mut() = url.clone();
+                        req.headers_mut().remove(hyper::header::HOST);
                     }
-                });
-
-                // Build request
-                let authority = url.authority().ok_or("Missing authority in URL")?.clone();
-                let mut req_builder = Request::builder()
-                    .method(method_clone.clone())
-                    .uri(url.clone())
-                    .header(hyper::header::HOST, authority.as_str());
-
-                // Add headers
-                for (key, value) in &self.default_headers {
-                    req_builder = req_builder.header(key, value);
-                }
-
-                for (key, value) in &config_clone.headers {
-                    req_builder = req_builder.header(key, value);
-                }
+                    let result = match timeout_with_controller(
+                        conn_timeout_controller.clone(),
+                        sender.send_request(req),
+                    )
+                    .await
+                    {
+                        Ok(result) => result,
+                        Err(_) => return Err(HttpError::Timeout("Request")),
+                    };
 
-                // Add body if present
-                let req = if let Some(body) = &config_clone.body {
-                    match body {
-                        RequestBody::Json(json) => {
-                            req_builder = req_builder
-                                .header(hyper::header::CONTENT_TYPE, "application/json")
-                                .header(
-                                    hyper::header::CONTENT_LENGTH,
-                                    json.to_string().len().to_string(),
-                                );
-                            req_builder.body(http_body_util::Full::new(json.to_string().into()))?
-                        }
-                        RequestBody::Data(data) => {
-                            req_builder = req_builder
-                                .header(hyper::header::CONTENT_TYPE, "application/octet-stream")
-                                .header(hyper::header::CONTENT_LENGTH, data.len().to_string());
-                            req_builder.body(http_body_util::Full::new(data.clone()))?
+                    match result {
+                        Ok(res) => break res,
+                        // The server may have closed the idle connection in the meantime,
+                        // which is only safe to retry if the request was not processed
+                        Err(e)
+                            if reused
+                                && (e.is_canceled()
+                                    || (e.is_incomplete_message()
+                                        && method_clone.is_idempotent())) =>
+                        {
+                            reused = false;
+                            sender = Self::connect(
+                                &executor_clone,
+                                &url,
+                                proxy.as_ref(),
+                                http2,
+                                identity.as_ref(),
+                                verify,
+                                conn_timeout_controller.clone(),
+                            )
+                            .await?;
                         }
+                        Err(e) => return Err(e.into()),
                     }
-                } else {
-                    req_builder.body(http_body_util::Full::new(Bytes::new()))?
                 };
 
-                // Send request
-                let mut res = match timeout_with_controller(
-                    conn_timeout_controller.clone(),
-                    sender.send_request(req),
-                )
-                .await
-                {
-                    Ok(res) => res?,
-                    Err(_) => return Err("Request timed out".into()),
-                };
+                let mut headers: Vec<(String, String)> = res
+                    .headers()
+                    .iter()
+                    .map(|(k, v)| (k.to_string(), v.to_str().unwrap_or("").to_string()))
+                    .collect();
+
+                // Decompress the body if the server used an encoding that was offered,
+                // the headers then describe the decompressed body
+                let mut decoder = None;
+                if negotiate_encoding {
+                    let encoding = res
+                        .headers()
+                        .get(hyper::header::CONTENT_ENCODING)
+                        .map(|value| value.to_str().unwrap_or(""))
+                        .map(ContentEncoding::from_header)
+                        .transpose()?
+                        .flatten();
+                    if let Some(encoding) = encoding {
+                        decoder = Some(Decoder::new(encoding, config_clone.max_decompressed_size));
+                        headers.retain(|(key, _)| {
+                            key != "content-encoding" && key != "content-length"
+                        });
+                    }
+                }
 
                 // Emit metadata
                 emitter
-                    .emit(ResponseData::Metadata(ResponseMetadata {
+                    .emit(ResponseData::Metadata(Box::new(ResponseMetadata {
                         status_code: res.status().into(),
-                        headers: res
-                            .headers()
-                            .iter()
-                            .map(|(k, v)| (k.to_string(), v.to_str().unwrap_or("").to_string()))
-                            .collect(),
+                        headers,
                         url: url.to_string(),
                         method: method_clone,
                         request_config: config_clone,
-                    }))
+                    })))
                     .await;
 
                 // Process response body
@@ -219,24 +400,39 @@ impl<'a> Client<'a> {
                             .await
                         {
                             Ok(result) => result,
-                            Err(_) => return Err("Response body read timed out".into()),
+                            Err(_) => return Err(HttpError::Timeout("Response body read")),
                         };
 
                     match frame_result {
                         Some(Ok(frame)) => {
                             if let Some(chunk) = frame.data_ref() {
-                                emitter
-                                    .emit(ResponseData::Stream(ResponseChunk {
-                                        data: chunk.clone(),
-                                    }))
-                                    .await;
+                                let data = match &mut decoder {
+                                    Some(decoder) => decoder.decode(chunk)?,
+                                    None => chunk.clone(),
+                                };
+                                if !data.is_empty() {
+                                    emitter
+                                        .emit(ResponseData::Stream(ResponseChunk { data }))
+                                        .await;
+                                }
                             }
                         }
-                        Some(Err(e)) => {
-                            warn!("Error reading frame: {:?}", e);
+                        // A body cut short must not look complete to the reader
+                        Some(Err(e)) => return Err(e.into()),
+                        None => {
+                            if let Some(decoder) = &mut decoder {
+                                let data = decoder.finish()?;
+                                if !data.is_empty() {
+                                    emitter
+                                        .emit(ResponseData::Stream(ResponseChunk { data }))
+                                        .await;
+                                }
+                            }
+
+                            // The connection is ready for the next request
+                            pool.borrow_mut().checkin(key, sender);
                             break;
                         }
-                        None => break, // End of stream
                     }
                 }
 
@@ -250,22 +446,506 @@ impl<'a> Client<'a> {
         }
     }
 
+    /// Opens a new connection to the server of `url`, or through `proxy`
+    async fn connect(
+        executor: &Executor<'a>,
+        url: &hyper::Uri,
+        proxy: Option<&Proxy>,
+        http2: Http2Mode,
+        identity: Option<&ClientIdentity>,
+        verify: bool,
+        conn_timeout_controller: Arc<Mutex<TimeoutController>>,
+    ) -> Result<Sender, HttpError> {
+        let (stream, use_http2) = Self::open_stream(
+            url,
+            proxy,
+            false,
+            http2,
+            identity,
+            verify,
+            conn_timeout_controller.clone(),
+        )
+        .await?;
+
+        // HTTP handshake, then spawn the connection handler, which runs as long
+        // as the connection is in use or idle in the pool
+        let exit_executor = executor.clone();
+        let sender = if use_http2 {
+            let handshake =
+                hyper::client::conn::http2::Builder::new(executor.clone()).handshake(stream);
+            let (sender, conn) =
+                match timeout_with_controller(conn_timeout_controller.clone(), handshake).await {
+                    Ok(result) => result?,
+                    Err(_) => return Err(HttpError::Timeout("HTTP/2 handshake")),
+                };
+            executor.spawn(async move {
+                match select(Box::pin(conn), Box::pin(exit_executor.wait_for_exit())).await {
+                    Either::Left((Err(err), _)) => warn!("HTTP/2 connection failed: {:?}", err),
+                    Either::Left((Ok(()), _)) | Either::Right(_) => (),
+                }
+            });
+            Sender::Http2(sender)
+        } else {
+            let (sender, conn) = match timeout_with_controller(
+                conn_timeout_controller.clone(),
+                hyper::client::conn::http1::handshake(stream),
+            )
+            .await
+            {
+                Ok(result) => result?,
+                Err(_) => return Err(HttpError::Timeout("HTTP handshake")),
+            };
+            executor.spawn(async move {
+                match select(Box::pin(conn), Box::pin(exit_executor.wait_for_exit())).await {
+                    Either::Left((Err(err), _)) => warn!("Connection failed: {:?}", err),
+                    Either::Left((Ok(()), _)) | Either::Right(_) => (),
+                }
+            });
+            Sender::Http1(sender)
+        };
+
+        Ok(sender)
+    }
+
+    /// Connects to the server of `url`, or through `proxy`, and sets up TLS,
+    /// verifying the server certificate with `verify`. Returns the stream and
+    /// whether ALPN selected HTTP/2. With `tunnel`, cleartext connections are
+    /// tunneled through the proxy as well.
+    async fn open_stream(
+        url: &hyper::Uri,
+        proxy: Option<&Proxy>,
+        tunnel: bool,
+        http2: Http2Mode,
+        identity: Option<&ClientIdentity>,
+        verify: bool,
+        conn_timeout_controller: Arc<Mutex<TimeoutController>>,
+    ) -> Result<(AnyHttpStream<TcpStream>, bool), HttpError> {
+        let is_https = url.scheme_str() == Some("https");
+        let host = url.host().ok_or(HttpError::MissingHost)?;
+        let port = url
+            .port()
+            .map(|p| p.as_u16())
+            .unwrap_or(if is_https { 443 } else { 80 });
+        let (connect_host, connect_port) = match proxy {
+            Some(proxy) => (proxy.host.as_str(), proxy.port),
+            None => (host, port),
+        };
+
+        // DNS resolution
+        let addrs = GLOBAL_DNS_RESOLVER.lookup(connect_host).await?;
+
+        // Connect to server, trying each resolved address in turn
+        let mut tcp_stream = None;
+        let mut last_err = HttpError::Dns(ResolveError::NoAddress(connect_host.to_string()));
+        let addrs = addrs.into_iter().filter_map(|addr| match addr {
+            IpAddr::V4(addr) => Some(addr),
+            IpAddr::V6(_) => None,
+        });
+        for ip in addrs {
+            match timeout_with_controller(
+                conn_timeout_controller.clone(),
+                TcpStream::connect(ip, connect_port),
+            )
+            .await
+            {
+                Ok(Ok(stream)) => {
+                    tcp_stream = Some(stream);
+                    break;
+                }
+                Ok(Err(e)) => {
+                    debug!("Failed to connect to {}: {}", ip, e);
+                    last_err = HttpError::Connect(e);
+                }
+                Err(_) => {
+                    debug!("Connecting to {} timed out", ip);
+                    last_err = HttpError::Timeout("Connect");
+                }
+            }
+        }
+        let tcp_stream = match tcp_stream {
+            Some(stream) => stream,
+            None => return Err(last_err),
+        };
+
+        // HTTPS is tunneled through the proxy, so TLS runs end to end
+        if let (Some(proxy), true) = (proxy, is_https || tunnel) {
+            let mut tunnel_stream = tcp_stream.clone();
+            match timeout_with_controller(
+                conn_timeout_controller.clone(),
+                proxy.connect_tunnel(&mut tunnel_stream, host, port),
+            )
+            .await
+            {
+                Ok(Ok(())) => (),
+                Ok(Err(e)) => return Err(e.into()),
+                Err(_) => return Err(HttpError::Timeout("Proxy tunnel")),
+            }
+        }
+
+        // Set up HTTP or HTTPS stream, where ALPN selects the HTTP version.
+        // Cleartext HTTP/2 is only used with prior knowledge of the server.
+        let mut stream = AnyHttpStream::Http(tcp_stream.clone());
+        let mut use_http2 = !is_https && http2 == Http2Mode::PriorKnowledge && proxy.is_none();
+        if is_https {
+            let host_str = String::from(host);
+            let dnsname = DnsName::try_from_str(&host_str)?;
+            let server_name = rustls_pki_types::ServerName::DnsName(dnsname.to_owned());
+
+            let connector = create_tls_connector(http2 != Http2Mode::Disabled, identity, verify)?;
+            match timeout_with_controller(
+                conn_timeout_controller.clone(),
+                connector.connect(server_name, tcp_stream.clone()),
+            )
+            .await
+            {
+                Ok(Ok(tls_stream)) => {
+                    use_http2 = tls_stream.get_ref().1.alpn_protocol() == Some(b"h2");
+                    stream = AnyHttpStream::from(tls_stream);
+                }
+                Ok(Err(e)) => return Err(e.into()),
+                Err(_) => return Err(HttpError::Timeout("TLS handshake")),
+            }
+        }
+
+        Ok((stream, use_http2))
+    }
+
+    fn build_request(
+        default_headers: &HashMap<String, String>,
+        config: &RequestConfig,
+        method: &Method,
+        url: &hyper::Uri,
+        forward_proxy: Option<&Proxy>,
+        strip_credentials: bool,
+        negotiate_encoding: bool,
+    ) -> Result<Request<Body>, HttpError> {
+        // Proxies expect the absolute URI and origin servers the path
+        let authority = url.authority().ok_or(HttpError::MissingHost)?.clone();
+        let request_uri = match forward_proxy {
+            Some(_) => url.clone(),
+            None => url
+                .path_and_query()
+                .map_or("/", |path| path.as_str())
+                .parse::<hyper::Uri>()?,
+        };
+        let mut req_builder = Request::builder()
+            .method(method.clone())
+            .uri(request_uri)
+            .header(hyper::header::HOST, authority.as_str());
+
+        if let Some(authorization) = forward_proxy.and_then(|p| p.authorization.as_ref()) {
+            req_builder = req_builder.header(hyper::header::PROXY_AUTHORIZATION, authorization);
+        }
+
+        if negotiate_encoding {
+            req_builder = req_builder.header(hyper::header::ACCEPT_ENCODING, ACCEPT_ENCODING);
+        }
+
+        // Add headers, without credentials once redirected to another origin
+        for (key, value) in default_headers.iter().chain(&config.headers) {
+            if !(strip_credentials && is_sensitive_header(key)) {
+                req_builder = req_builder.header(key, value);
+            }
+        }
+
+        // Add body if present
+        let req = if let Some(body) = &config.body {
+            match body {
+                RequestBody::Json(json) => {
+                    req_builder = req_builder
+                        .header(hyper::header::CONTENT_TYPE, "application/json")
+                        .header(
+                            hyper::header::CONTENT_LENGTH,
+                            json.to_string().len().to_string(),
+                        );
+                    req_builder.body::<Body>(Box::pin(Full::new(json.to_string().into())))?
+                }
+                RequestBody::Data(data) => {
+                    req_builder = req_builder
+                        .header(hyper::header::CONTENT_TYPE, "application/octet-stream")
+                        .header(hyper::header::CONTENT_LENGTH, data.len().to_string());
+                    req_builder.body::<Body>(Box::pin(Full::new(data.clone())))?
+                }
+                RequestBody::Stream(body_stream) => {
+                    let stream = body_stream.take().ok_or(HttpError::BodyAlreadySent)?;
+                    req_builder =
+                        req_builder.header(hyper::header::CONTENT_TYPE, "application/octet-stream");
+                    // Without a length, hyper sends the body chunked
+                    if let Some(length) = body_stream.length {
+                        req_builder =
+                            req_builder.header(hyper::header::CONTENT_LENGTH, length.to_string());
+                    }
+                    let frames = stream.map(|chunk| Ok(Frame::data(chunk)));
+                    req_builder.body::<Body>(Box::pin(StreamBody::new(frames)))?
+                }
+            }
+        } else {
+            req_builder.body::<Body>(Box::pin(Full::new(Bytes::new())))?
+        };
+
+        Ok(req)
+    }
+
+    /// Sends a request, retrying and following redirects according to the policies
     pub async fn request(
         &mut self,
         method: Method,
         url: impl AsRef<str>,
         config: RequestConfig,
-    ) -> Result<Response, Box<dyn std::error::Error>> {
+    ) -> Result<Response<'a>, HttpError> {
         let full_url = self.build_full_url(url.as_ref());
-        let full_url = self.add_params_to_url(&full_url, &config.params);
-        self.perform_request(method, full_url, config).await
+        let mut url = self.add_params_to_url(&full_url, &config.params)?;
+        let origin = url.origin();
+        let mut method = method;
+        let mut config = config;
+        let mut redirects = 0;
+        let mut token_refreshed = false;
+
+        // Credentials set on the request take precedence over the client's
+        let has_authorization = self
+            .default_headers
+            .keys()
+            .chain(config.headers.keys())
+            .any(|key| key.eq_ignore_ascii_case("authorization"));
+        let auth = self.auth.clone().filter(|_| !has_authorization);
+        if let Some(auth) = &auth {
+            config
+                .headers
+                .insert("Authorization".to_string(), auth.header_value().await?);
+        }
+
+        loop {
+            let strip_credentials = url.origin() != origin;
+            let response = self
+                .perform_with_retries(&method, &url, &config, strip_credentials)
+                .await?;
+
+            let status_code = response.metadata.status_code;
+
+            // A token that expired early is replaced once, if the request can be sent again
+            if let Some(Auth::Provider(provider)) = &auth {
+                if status_code == 401
+                    && !token_refreshed
+                    && !strip_credentials
+                    && config.body.as_ref().is_none_or(RequestBody::is_replayable)
+                {
+                    debug!("{} {} returned 401, refreshing the token", method, url);
+                    provider.invalidate();
+                    let token = provider.token().await.map_err(HttpError::Auth)?;
+                    config.headers.insert(
+                        "Authorization".to_string(),
+                        Credentials::Bearer(token).header_value(),
+                    );
+                    token_refreshed = true;
+                    continue;
+                }
+            }
+
+            let location = response
+                .metadata
+                .headers
+                .iter()
+                .find(|(key, _)| key.eq_ignore_ascii_case("location"))
+                .map(|(_, value)| value.clone());
+            let location = match location {
+                Some(location)
+                    if matches!(status_code, 301 | 302 | 303 | 307 | 308)
+                        && self.redirect_policy.max_redirects > 0 =>
+                {
+                    location
+                }
+                _ => return Ok(response),
+            };
+            if redirects >= self.redirect_policy.max_redirects {
+                return Err(HttpError::TooManyRedirects(redirects));
+            }
+            redirects += 1;
+
+            let next = url.join(&location)?;
+            if next.scheme() != "http" && next.scheme() != "https" {
+                return Err(HttpError::UnsupportedRedirect(next.to_string()));
+            }
+
+            // Like browsers, only 307 and 308 resend the body, except that
+            // 301 and 302 keep methods other than POST
+            let to_get = match status_code {
+                303 => method != Method::HEAD,
+                301 | 302 => method == Method::POST,
+                _ => false,
+            };
+            if to_get {
+                method = Method::GET;
+                config.body = None;
+                config.headers.retain(|key, _| {
+                    !key.eq_ignore_ascii_case("content-type")
+                        && !key.eq_ignore_ascii_case("content-length")
+                });
+            } else if !config.body.as_ref().is_none_or(RequestBody::is_replayable) {
+                // A streamed body is gone, leave the redirect to the caller
+                return Ok(response);
+            }
+
+            debug!("Following {} redirect to {}", status_code, next);
+            url = next;
+        }
+    }
+
+    /// Opens a WebSocket to a `ws://` or `wss://` URL, or one relative to the
+    /// base URL. Default headers, the headers of `config` and the client's
+    /// credentials are sent with the handshake, which must finish within the
+    /// timeout of `config`. Redirects are not followed.
+    pub async fn websocket(
+        &mut self,
+        url: impl AsRef<str>,
+        config: RequestConfig,
+        ws_config: WebSocketConfig,
+    ) -> Result<WebSocket<AnyHttpStream<TcpStream>>, HttpError> {
+        let url = url.as_ref();
+        let url = match url.split_once("://") {
+            Some(("ws", rest)) => format!("http://{}", rest),
+            Some(("wss", rest)) => format!("https://{}", rest),
+            _ => url.to_string(),
+        };
+        let full_url = self.build_full_url(&url);
+        let url = self
+            .add_params_to_url(&full_url, &config.params)?
+            .as_str()
+            .parse::<hyper::Uri>()?;
+        let is_https = url.scheme_str() == Some("https");
+        let host = url.host().ok_or(HttpError::MissingHost)?;
+        let authority = url.authority().ok_or(HttpError::MissingHost)?.as_str();
+        let path_and_query = url.path_and_query().map_or("/", |path| path.as_str());
+
+        let mut headers: Vec<(String, String)> = self
+            .default_headers
+            .iter()
+            .chain(config.headers.iter())
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+        let has_authorization = headers
+            .iter()
+            .any(|(key, _)| key.eq_ignore_ascii_case("authorization"));
+        if let (Some(auth), false) = (&self.auth, has_authorization) {
+            headers.push(("Authorization".to_string(), auth.header_value().await?));
+        }
+
+        let timeout_controller = TimeoutController::new(config.timeout.as_millis() as u64);
+        setup_timeout_task(&self.executor, timeout_controller.clone()).await;
+
+        // The upgrade needs HTTP/1.1, so cleartext connections through a
+        // proxy are tunneled rather than forwarded
+        let proxy = Proxy::for_host(&self.proxy, host, is_https)?;
+        let verify = is_https
+            && (self.verify_tls
+                || self.identity.is_some()
+                || headers.iter().any(|(key, _)| is_sensitive_header(key)));
+        let (stream, _) = Self::open_stream(
+            &url,
+            proxy.as_ref(),
+            true,
+            Http2Mode::Disabled,
+            self.identity.as_ref(),
+            verify,
+            timeout_controller.clone(),
+        )
+        .await?;
+
+        match timeout_with_controller(
+            timeout_controller,
+            handshake::connect(stream, authority, path_and_query, &headers, ws_config),
+        )
+        .await
+        {
+            Ok(result) => Ok(result?),
+            Err(_) => Err(HttpError::Timeout("WebSocket handshake")),
+        }
     }
 
-    fn add_params_to_url(&self, url: &str, params: &HashMap<String, String>) -> String {
-        let mut url = Url::parse(url).unwrap();
+    async fn perform_with_retries(
+        &mut self,
+        method: &Method,
+        url: &Url,
+        config: &RequestConfig,
+        strip_credentials: bool,
+    ) -> Result<Response<'a>, HttpError> {
+        let policy = self.retry_policy.clone();
+        // Other requests may have had side effects even if they failed
+        let replayable = config.body.as_ref().is_none_or(RequestBody::is_replayable);
+        let max_retries = if method.is_idempotent() && replayable {
+            policy.max_retries
+        } else {
+            0
+        };
+
+        let mut retry = 0;
+        loop {
+            let result = self
+                .perform_request(
+                    method.clone(),
+                    url.to_string(),
+                    config.clone(),
+                    strip_credentials,
+                )
+                .await;
+            if retry >= max_retries {
+                return result;
+            }
+
+            let delay_ms = match &result {
+                Ok(response) if policy.should_retry_status(response.metadata.status_code) => {
+                    let delay_ms = retry_after_ms(&response.metadata)
+                        .map(|delay_ms| delay_ms.min(policy.max_delay_ms))
+                        .unwrap_or_else(|| policy.delay_ms(retry, &mut self.rng));
+                    warn!(
+                        "{} {} returned {}, retrying in {} ms",
+                        method, url, response.metadata.status_code, delay_ms
+                    );
+                    delay_ms
+                }
+                Err(e) if policy.should_retry_error(e) => {
+                    let delay_ms = policy.delay_ms(retry, &mut self.rng);
+                    warn!(
+                        "{} {} failed: {}, retrying in {} ms",
+                        method, url, e, delay_ms
+                    );
+                    delay_ms
+                }
+                _ => return result,
+            };
+            drop(result);
+
+            sleep_ms(delay_ms).await;
+            retry += 1;
+        }
+    }
+
+    /// Parses `url`, which may come from the caller, and appends `params` to its query
+    fn add_params_to_url(
+        &self,
+        url: &str,
+        params: &HashMap<String, String>,
+    ) -> Result<Url, url::ParseError> {
+        let mut url = Url::parse(url)?;
         for (key, value) in params {
             url.query_pairs_mut().append_pair(key, value);
         }
-        url.to_string()
+        Ok(url)
     }
 }
+
+/// Delay requested by a `Retry-After` header, only the delay-seconds form is supported
+fn retry_after_ms(metadata: &ResponseMetadata) -> Option<u64> {
+    metadata
+        .headers
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case("retry-after"))
+        .and_then(|(_, value)| value.trim().parse::<u64>().ok())
+        .map(|seconds| seconds.saturating_mul(1000))
+}
+
+/// Whether the header `key` carries credentials
+fn is_sensitive_header(key: &str) -> bool {
+    SENSITIVE_HEADERS
+        .iter()
+        .any(|header| key.eq_ignore_ascii_case(header))
+}
@@ -0,0 +1,472 @@
+use crate::errors::decode_error::DecodeError;
+use crate::errors::http_error::HttpError;
+use bytes::Bytes;
+use flate2::write::{DeflateDecoder, MultiGzDecoder, ZlibDecoder};
+use ruzstd::decoding::FrameDecoder;
+use std::io::{self, Write};
+
+/// Value of the `Accept-Encoding` header sent when decompression is enabled
+pub const ACCEPT_ENCODING: &str = "gzip, deflate, zstd, lz4";
+
+/// Compressed input is fed to the inflaters in pieces of this size, which
+/// bounds the output of a single step to about 1000 times as much
+const INFLATE_STEP: usize = 16 * 1024;
+
+/// Largest zstd frame header (RFC 8878, section 3.1.1.1)
+const ZSTD_MAX_HEADER_SIZE: usize = 18;
+
+/// Compressed input is fed to the zstd decoder in pieces of this size where
+/// possible, as a few bytes can encode a 128 KiB block
+const ZSTD_STEP: usize = 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContentEncoding {
+    Gzip,
+    Deflate,
+    Zstd,
+    Lz4,
+}
+
+impl ContentEncoding {
+    /// Parses a `Content-Encoding` header, `None` for uncompressed bodies
+    pub fn from_header(value: &str) -> Result<Option<Self>, HttpError> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "" | "identity" => Ok(None),
+            "gzip" | "x-gzip" => Ok(Some(Self::Gzip)),
+            "deflate" => Ok(Some(Self::Deflate)),
+            "zstd" => Ok(Some(Self::Zstd)),
+            "lz4" => Ok(Some(Self::Lz4)),
+            other => Err(DecodeError::UnsupportedEncoding(other.to_string()).into()),
+        }
+    }
+}
+
+/// The flate2 decoders, which write their output to a buffer
+trait Inflate: Write {
+    fn output(&mut self) -> &mut Vec<u8>;
+    fn finish_stream(&mut self) -> io::Result<()>;
+}
+
+macro_rules! impl_inflate {
+    ($decoder:ident) => {
+        impl Inflate for $decoder<Vec<u8>> {
+            fn output(&mut self) -> &mut Vec<u8> {
+                self.get_mut()
+            }
+
+            fn finish_stream(&mut self) -> io::Result<()> {
+                self.try_finish()
+            }
+        }
+    };
+}
+
+impl_inflate!(MultiGzDecoder);
+impl_inflate!(ZlibDecoder);
+impl_inflate!(DeflateDecoder);
+
+enum Inner {
+    Inflate(Box<dyn Inflate>),
+    /// Until the first two bytes tell whether the stream has a zlib header,
+    /// which some servers leave out
+    DeflatePending(Vec<u8>),
+    Zstd(Box<ZstdDecoder>),
+    Lz4(Lz4Decoder),
+}
+
+/// Decompresses a response body chunk by chunk, failing once the output
+/// exceeds a limit so small bodies cannot expand to fill memory
+pub struct Decoder {
+    inner: Inner,
+    limit: u64,
+    total: u64,
+    /// Whether any compressed data was received, bodies may be empty
+    started: bool,
+}
+
+impl Decoder {
+    pub fn new(encoding: ContentEncoding, limit: u64) -> Self {
+        let inner = match encoding {
+            ContentEncoding::Gzip => Inner::Inflate(Box::new(MultiGzDecoder::new(Vec::new()))),
+            ContentEncoding::Deflate => Inner::DeflatePending(Vec::new()),
+            ContentEncoding::Zstd => Inner::Zstd(Box::new(ZstdDecoder::new())),
+            ContentEncoding::Lz4 => Inner::Lz4(Lz4Decoder::new()),
+        };
+        Self {
+            inner,
+            limit,
+            total: 0,
+            started: false,
+        }
+    }
+
+    /// Decodes the next chunk of the body, returning the data it completes
+    pub fn decode(&mut self, chunk: &[u8]) -> Result<Bytes, HttpError> {
+        self.started |= !chunk.is_empty();
+        let mut output = Vec::new();
+        if let Inner::DeflatePending(buffer) = &mut self.inner {
+            buffer.extend_from_slice(chunk);
+            if buffer.len() < 2 {
+                return Ok(Bytes::new());
+            }
+            let buffer = std::mem::take(buffer);
+            self.inner = if is_zlib_header(buffer[0], buffer[1]) {
+                Inner::Inflate(Box::new(ZlibDecoder::new(Vec::new())))
+            } else {
+                Inner::Inflate(Box::new(DeflateDecoder::new(Vec::new())))
+            };
+            self.feed(&buffer, &mut output)?;
+        } else {
+            self.feed(chunk, &mut output)?;
+        }
+        Ok(Bytes::from(output))
+    }
+
+    /// Flushes the data held back by the decoder at the end of the body,
+    /// failing if the body was truncated
+    pub fn finish(&mut self) -> Result<Bytes, HttpError> {
+        let mut output = Vec::new();
+        if !self.started {
+            return Ok(Bytes::new());
+        }
+        let budget = self.budget();
+        match &mut self.inner {
+            Inner::Inflate(decoder) => {
+                decoder.finish_stream().map_err(decode_error)?;
+                output.append(decoder.output());
+            }
+            Inner::DeflatePending(buffer) => {
+                if !buffer.is_empty() {
+                    return Err(DecodeError::Truncated("deflate").into());
+                }
+            }
+            Inner::Zstd(decoder) => decoder.finish(&mut output, budget)?,
+            Inner::Lz4(decoder) => decoder.finish()?,
+        }
+        self.account(&output)?;
+        Ok(Bytes::from(output))
+    }
+
+    fn feed(&mut self, input: &[u8], output: &mut Vec<u8>) -> Result<(), HttpError> {
+        let budget = self.budget();
+        match &mut self.inner {
+            Inner::Inflate(decoder) => {
+                for step in input.chunks(INFLATE_STEP) {
+                    decoder.write_all(step).map_err(decode_error)?;
+                    output.append(decoder.output());
+                    budget.check(output.len())?;
+                }
+            }
+            Inner::DeflatePending(_) => unreachable!("deflate format is detected before feeding"),
+            Inner::Zstd(decoder) => decoder.decode(input, output, budget)?,
+            Inner::Lz4(decoder) => decoder.decode(input, output, budget)?,
+        }
+        self.account(output)
+    }
+
+    fn budget(&self) -> Budget {
+        Budget {
+            written: self.total,
+            limit: self.limit,
+        }
+    }
+
+    fn account(&mut self, output: &[u8]) -> Result<(), HttpError> {
+        self.budget().check(output.len())?;
+        self.total += output.len() as u64;
+        Ok(())
+    }
+}
+
+/// Output allowed before the limit is reached
+#[derive(Clone, Copy)]
+struct Budget {
+    written: u64,
+    limit: u64,
+}
+
+impl Budget {
+    /// Checks that `len` more bytes of output stay within the limit
+    fn check(&self, len: usize) -> Result<(), HttpError> {
+        if self.written + len as u64 > self.limit {
+            return Err(HttpError::DecompressedTooLarge(self.limit));
+        }
+        Ok(())
+    }
+}
+
+fn decode_error(err: std::io::Error) -> HttpError {
+    DecodeError::Deflate(err).into()
+}
+
+/// Checks for a zlib header (RFC 1950, section 2.2) with the deflate method
+fn is_zlib_header(cmf: u8, flg: u8) -> bool {
+    cmf & 0x0F == 8 && (u16::from(cmf) << 8 | u16::from(flg)) % 31 == 0
+}
+
+/// Streaming zstd decoder, which decodes whole blocks as they arrive
+struct ZstdDecoder {
+    decoder: FrameDecoder,
+    /// Input not yet consumed, such as a partially received block
+    input: Vec<u8>,
+    in_frame: bool,
+}
+
+impl ZstdDecoder {
+    fn new() -> Self {
+        Self {
+            decoder: FrameDecoder::new(),
+            input: Vec::new(),
+            in_frame: false,
+        }
+    }
+
+    fn decode(
+        &mut self,
+        chunk: &[u8],
+        output: &mut Vec<u8>,
+        budget: Budget,
+    ) -> Result<(), HttpError> {
+        self.input.extend_from_slice(chunk);
+        self.process(output, budget, false)
+    }
+
+    fn finish(&mut self, output: &mut Vec<u8>, budget: Budget) -> Result<(), HttpError> {
+        self.process(output, budget, true)?;
+        if self.in_frame || !self.input.is_empty() {
+            return Err(DecodeError::Truncated("zstd").into());
+        }
+        Ok(())
+    }
+
+    fn process(
+        &mut self,
+        output: &mut Vec<u8>,
+        budget: Budget,
+        eof: bool,
+    ) -> Result<(), HttpError> {
+        loop {
+            if !self.in_frame {
+                // The frame header must be complete before it is parsed
+                if self.input.is_empty() || (self.input.len() < ZSTD_MAX_HEADER_SIZE && !eof) {
+                    return Ok(());
+                }
+                self.decoder = FrameDecoder::new();
+                self.in_frame = true;
+            }
+            // The decoder assumes a content checksum is complete once it expects one
+            if self.input.len() < 4 {
+                return Ok(());
+            }
+
+            // Blocks larger than a step are decoded from the whole input
+            let mut input = std::mem::take(&mut self.input);
+            let step = input.len().min(ZSTD_STEP);
+            let mut read = self.decode_from(&input[..step])?;
+            if read == 0 && step < input.len() {
+                read = self.decode_from(&input)?;
+            }
+            input.drain(..read.min(input.len()));
+            self.input = input;
+            if let Some(data) = self.decoder.collect() {
+                output.extend_from_slice(&data);
+            }
+            budget.check(output.len())?;
+
+            if self.decoder.is_finished() && self.decoder.can_collect() == 0 {
+                // Further frames may follow
+                self.in_frame = false;
+                continue;
+            }
+            if read == 0 {
+                return Ok(());
+            }
+        }
+    }
+
+    fn decode_from(&mut self, source: &[u8]) -> Result<usize, HttpError> {
+        let (read, _) = self
+            .decoder
+            .decode_from_to(source, &mut [])
+            .map_err(DecodeError::from)?;
+        Ok(read)
+    }
+}
+
+/// Streaming decoder for the LZ4 frame format, block checksums and content
+/// checksums are skipped rather than verified
+struct Lz4Decoder {
+    input: Vec<u8>,
+    frame: Option<Lz4Frame>,
+    /// Previous output, referenced by blocks that depend on earlier ones
+    window: Vec<u8>,
+}
+
+struct Lz4Frame {
+    independent_blocks: bool,
+    block_checksums: bool,
+    content_checksum: bool,
+    max_block_size: usize,
+}
+
+const LZ4_MAGIC: u32 = 0x184D2204;
+const LZ4_WINDOW_SIZE: usize = 64 * 1024;
+
+impl Lz4Decoder {
+    fn new() -> Self {
+        Self {
+            input: Vec::new(),
+            frame: None,
+            window: Vec::new(),
+        }
+    }
+
+    fn decode(
+        &mut self,
+        chunk: &[u8],
+        output: &mut Vec<u8>,
+        budget: Budget,
+    ) -> Result<(), HttpError> {
+        self.input.extend_from_slice(chunk);
+        let mut consumed = 0;
+        let result = self.process(&mut consumed, output, budget);
+        self.input.drain(..consumed);
+        result
+    }
+
+    fn finish(&mut self) -> Result<(), HttpError> {
+        if self.frame.is_some() || !self.input.is_empty() {
+            return Err(DecodeError::Truncated("lz4").into());
+        }
+        Ok(())
+    }
+
+    fn process(
+        &mut self,
+        consumed: &mut usize,
+        output: &mut Vec<u8>,
+        budget: Budget,
+    ) -> Result<(), HttpError> {
+        loop {
+            let input = &self.input[*consumed..];
+            let Some(frame) = &self.frame else {
+                let Some((frame, header_size)) = parse_lz4_header(input)? else {
+                    return Ok(());
+                };
+                self.frame = frame;
+                *consumed += header_size;
+                continue;
+            };
+
+            let Some(block_size) = input
+                .get(..4)
+                .map(|b| u32::from_le_bytes(b.try_into().unwrap()))
+            else {
+                return Ok(());
+            };
+            if block_size == 0 {
+                // End mark, followed by the content checksum
+                let end_size = 4 + if frame.content_checksum { 4 } else { 0 };
+                if input.len() < end_size {
+                    return Ok(());
+                }
+                *consumed += end_size;
+                self.frame = None;
+                self.window.clear();
+                continue;
+            }
+
+            let uncompressed = block_size & 0x8000_0000 != 0;
+            let data_size = (block_size & 0x7FFF_FFFF) as usize;
+            if data_size > frame.max_block_size {
+                return Err(
+                    DecodeError::Malformed("lz4 block exceeds the maximum block size").into(),
+                );
+            }
+            let block_end = 4 + data_size + if frame.block_checksums { 4 } else { 0 };
+            if input.len() < block_end {
+                return Ok(());
+            }
+            let data = &input[4..4 + data_size];
+
+            let start = output.len();
+            if uncompressed {
+                output.extend_from_slice(data);
+            } else {
+                output.resize(start + frame.max_block_size, 0);
+                let dict: &[u8] = if frame.independent_blocks {
+                    &[]
+                } else {
+                    &self.window
+                };
+                let size =
+                    lz4_flex::block::decompress_into_with_dict(data, &mut output[start..], dict)
+                        .map_err(DecodeError::from)?;
+                output.truncate(start + size);
+            }
+            budget.check(output.len())?;
+
+            if !frame.independent_blocks {
+                self.window.extend_from_slice(&output[start..]);
+                let excess = self.window.len().saturating_sub(LZ4_WINDOW_SIZE);
+                self.window.drain(..excess);
+            }
+            *consumed += block_end;
+        }
+    }
+}
+
+/// Parses an LZ4 frame header, `None` until enough input is available.
+/// Skippable frames are consumed without a frame.
+fn parse_lz4_header(input: &[u8]) -> Result<Option<(Option<Lz4Frame>, usize)>, HttpError> {
+    let Some(magic) = input
+        .get(..4)
+        .map(|b| u32::from_le_bytes(b.try_into().unwrap()))
+    else {
+        return Ok(None);
+    };
+    if magic & 0xFFFF_FFF0 == 0x184D_2A50 {
+        let Some(size) = input
+            .get(4..8)
+            .map(|b| u32::from_le_bytes(b.try_into().unwrap()))
+        else {
+            return Ok(None);
+        };
+        let frame_size = 8 + size as usize;
+        return Ok((input.len() >= frame_size).then_some((None, frame_size)));
+    }
+    if magic != LZ4_MAGIC {
+        return Err(DecodeError::Malformed("Invalid lz4 frame").into());
+    }
+
+    let (Some(&flg), Some(&bd)) = (input.get(4), input.get(5)) else {
+        return Ok(None);
+    };
+    if flg >> 6 != 1 {
+        return Err(DecodeError::Malformed("Unsupported lz4 frame version").into());
+    }
+    if flg & 0x01 != 0 {
+        return Err(DecodeError::Malformed("lz4 dictionaries are not supported").into());
+    }
+    let header_size = 7 + if flg & 0x08 != 0 { 8 } else { 0 };
+    if input.len() < header_size {
+        return Ok(None);
+    }
+
+    let max_block_size = match (bd >> 4) & 0x07 {
+        4 => 64 * 1024,
+        5 => 256 * 1024,
+        6 => 1024 * 1024,
+        7 => 4 * 1024 * 1024,
+        _ => return Err(DecodeError::Malformed("Invalid lz4 block size").into()),
+    };
+    Ok(Some((
+        Some(Lz4Frame {
+            independent_blocks: flg & 0x20 != 0,
+            block_checksums: flg & 0x10 != 0,
+            content_checksum: flg & 0x04 != 0,
+            max_block_size,
+        }),
+        header_size,
+    )))
+}
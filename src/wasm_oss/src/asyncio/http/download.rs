@@ -0,0 +1,373 @@
+use super::client::Client;
+use super::request::RequestConfig;
+use super::response::ResponseMetadata;
+use crate::asyncio::sleep_ms;
+use crate::commands::chain::ChainloadBuffer;
+use crate::controllers::boot::PayloadType;
+use crate::errors::boot_error::BootError;
+use crate::errors::host_error::HostError;
+use crate::errors::http_error::HttpError;
+use crate::errors::neoboot_error::NeoBootError;
+use crate::errors::verify_error::VerifyError;
+use crate::ffi;
+use crate::utils::{parse_int, sys_get_env, verify::PayloadVerifier};
+use futures::StreamExt;
+use http::Method;
+use log::warn;
+
+/// Destination of a download, written to in order
+pub trait DownloadSink {
+    fn write(&mut self, bytes: &[u8]) -> Result<(), NeoBootError>;
+
+    /// Number of bytes the sink holds at most, if limited
+    fn capacity(&self) -> Option<u64> {
+        None
+    }
+}
+
+impl DownloadSink for ChainloadBuffer {
+    fn write(&mut self, bytes: &[u8]) -> Result<(), NeoBootError> {
+        Ok(ChainloadBuffer::write(self, bytes)?)
+    }
+
+    fn capacity(&self) -> Option<u64> {
+        Some(self.len())
+    }
+}
+
+/// Writes to a region of host memory, such as a payload load address
+pub struct MemorySink {
+    address: u64,
+    capacity: u64,
+    offset: u64,
+}
+
+impl MemorySink {
+    pub fn new(address: u64, capacity: u64) -> Self {
+        Self {
+            address,
+            capacity,
+            offset: 0,
+        }
+    }
+
+    /// Writes to the load address of `payload_type` from the environment
+    pub fn for_payload(payload_type: &PayloadType, capacity: u64) -> Result<Self, BootError> {
+        let key = payload_type.as_str();
+        let value = sys_get_env(key)?;
+        let address = parse_int(&value).map_err(|_| BootError::InvalidAddress { key, value })?;
+        Ok(Self::new(address, capacity))
+    }
+
+    pub fn address(&self) -> u64 {
+        self.address
+    }
+
+    /// Number of bytes written so far
+    pub fn len(&self) -> u64 {
+        self.offset
+    }
+}
+
+impl DownloadSink for MemorySink {
+    fn write(&mut self, bytes: &[u8]) -> Result<(), NeoBootError> {
+        let end = self.offset + bytes.len() as u64;
+        if end > self.capacity {
+            return Err(BootError::PayloadTooLarge {
+                capacity: self.capacity,
+            }
+            .into());
+        }
+
+        let result = unsafe {
+            ffi::env_memcpy(
+                bytes.as_ptr(),
+                self.address + self.offset,
+                bytes.len() as u32,
+            )
+        };
+        if result < 0 {
+            return Err(HostError::Memcpy {
+                address: self.address + self.offset,
+                len: bytes.len(),
+            }
+            .into());
+        }
+
+        self.offset = end;
+        Ok(())
+    }
+
+    fn capacity(&self) -> Option<u64> {
+        Some(self.capacity)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DownloadOptions {
+    /// Headers and timeout of each request, the `Range` header is added on resume
+    pub request: RequestConfig,
+    /// Consecutive failed attempts, without any data received, before giving up
+    pub max_failures: u32,
+    pub retry_delay_ms: u64,
+    pub expected_size: Option<u64>,
+    /// Hex-encoded SHA-256 digest to check, empty to skip the check
+    pub expected_sha256: String,
+}
+
+impl Default for DownloadOptions {
+    fn default() -> Self {
+        Self {
+            request: RequestConfig::default(),
+            max_failures: 5,
+            retry_delay_ms: 1000,
+            expected_size: None,
+            expected_sha256: String::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DownloadResult {
+    pub size: u64,
+    /// Hex-encoded SHA-256 digest of the downloaded data
+    pub sha256: String,
+    /// Number of times the download was resumed after an interruption
+    pub resumes: u32,
+}
+
+/// Identity of the resource being downloaded, as announced by the server
+#[derive(Default)]
+struct Validator {
+    etag: Option<String>,
+    total_size: Option<u64>,
+}
+
+impl Validator {
+    /// Checks that a later response describes the same resource
+    fn check(&mut self, etag: Option<String>, total_size: Option<u64>) -> Result<(), HttpError> {
+        if let (Some(known), Some(etag)) = (&self.etag, &etag) {
+            if known != etag {
+                return Err(HttpError::ResourceChanged {
+                    what: "ETag",
+                    before: known.clone(),
+                    after: etag.clone(),
+                });
+            }
+        }
+        if let (Some(known), Some(total_size)) = (self.total_size, total_size) {
+            if known != total_size {
+                return Err(HttpError::ResourceChanged {
+                    what: "size",
+                    before: known.to_string(),
+                    after: total_size.to_string(),
+                });
+            }
+        }
+
+        self.etag = self.etag.take().or(etag);
+        self.total_size = self.total_size.or(total_size);
+        Ok(())
+    }
+
+    /// Only strong validators may be used with `If-Range` (RFC 9110, section 13.1.5)
+    fn if_range(&self) -> Option<&str> {
+        self.etag.as_deref().filter(|etag| !etag.starts_with("W/"))
+    }
+}
+
+/// Downloads `url` into `sink`, resuming with `Range` requests where the
+/// transfer was interrupted. The data is hashed as it arrives, so the digest
+/// is checked without reading the destination back.
+pub async fn download(
+    client: &mut Client<'_>,
+    url: &str,
+    sink: &mut impl DownloadSink,
+    options: DownloadOptions,
+) -> Result<DownloadResult, NeoBootError> {
+    let mut verifier = PayloadVerifier::new(options.expected_size, &options.expected_sha256);
+    let mut validator = Validator::default();
+    let mut offset = 0u64;
+    let mut failures = 0u32;
+    let mut resumes = 0u32;
+
+    loop {
+        let start = offset;
+        match download_part(
+            client,
+            url,
+            sink,
+            &options,
+            &mut verifier,
+            &mut validator,
+            &mut offset,
+        )
+        .await
+        {
+            Ok(PartOutcome::Complete) => break,
+            Ok(PartOutcome::Interrupted(e)) => {
+                warn!("Download of {} interrupted at {} bytes: {}", url, offset, e);
+                if offset > start {
+                    failures = 0;
+                }
+                failures += 1;
+                if failures > options.max_failures {
+                    return Err(HttpError::Interrupted {
+                        url: url.to_string(),
+                        attempts: failures,
+                        last: Box::new(e),
+                    }
+                    .into());
+                }
+            }
+            Err(e) => return Err(e),
+        }
+
+        sleep_ms(options.retry_delay_ms).await;
+        if offset > 0 {
+            resumes += 1;
+        }
+    }
+
+    let size = verifier.size();
+    let sha256 = verifier.finish()?;
+    Ok(DownloadResult {
+        size,
+        sha256,
+        resumes,
+    })
+}
+
+enum PartOutcome {
+    Complete,
+    /// The transfer stopped for a reason that may go away on retry
+    Interrupted(HttpError),
+}
+
+async fn download_part(
+    client: &mut Client<'_>,
+    url: &str,
+    sink: &mut impl DownloadSink,
+    options: &DownloadOptions,
+    verifier: &mut PayloadVerifier,
+    validator: &mut Validator,
+    offset: &mut u64,
+) -> Result<PartOutcome, NeoBootError> {
+    // Ranges refer to the encoded body, so it must not be compressed
+    let mut config = options.request.clone();
+    config
+        .headers
+        .insert("Accept-Encoding".to_string(), "identity".to_string());
+    if *offset > 0 {
+        config
+            .headers
+            .insert("Range".to_string(), format!("bytes={}-", offset));
+        if let Some(etag) = validator.if_range() {
+            config
+                .headers
+                .insert("If-Range".to_string(), etag.to_string());
+        }
+    }
+
+    let response = match client.request(Method::GET, url, config).await {
+        Ok(response) => response,
+        Err(e) => return Ok(PartOutcome::Interrupted(e)),
+    };
+
+    let metadata = &response.metadata;
+    let etag = header(metadata, "etag").map(str::to_string);
+    // Data already received that the response repeats
+    let mut skip = match metadata.status_code {
+        200 => {
+            let content_length = header(metadata, "content-length").and_then(|v| v.parse().ok());
+            validator.check(etag, content_length)?;
+            // The server does not support ranges, or the resource changed
+            // without a validator to tell, so the data is sent from the start
+            *offset
+        }
+        206 => {
+            let (range_start, total_size) = header(metadata, "content-range")
+                .and_then(parse_content_range)
+                .ok_or(HttpError::MalformedResponse(
+                    "invalid Content-Range in partial response",
+                ))?;
+            if range_start != *offset {
+                return Err(HttpError::RangeMismatch {
+                    expected: *offset,
+                    actual: range_start,
+                }
+                .into());
+            }
+            validator.check(etag, total_size)?;
+            0
+        }
+        416 if validator.total_size == Some(*offset) => return Ok(PartOutcome::Complete),
+        500..=599 => {
+            return Ok(PartOutcome::Interrupted(HttpError::Status(
+                metadata.status_code,
+            )))
+        }
+        status_code => return Err(HttpError::Status(status_code).into()),
+    };
+
+    if let (Some(expected), Some(total_size)) = (options.expected_size, validator.total_size) {
+        if expected != total_size {
+            return Err(VerifyError::SizeMismatch {
+                expected,
+                actual: total_size,
+            }
+            .into());
+        }
+    }
+
+    let mut stream = response.stream().await;
+    while let Some(chunk) = stream.next().await {
+        let chunk = match chunk {
+            Ok(chunk) => chunk,
+            Err(e) => return Ok(PartOutcome::Interrupted(e)),
+        };
+
+        let skipped = skip.min(chunk.len() as u64) as usize;
+        skip -= skipped as u64;
+        let chunk = &chunk[skipped..];
+        if chunk.is_empty() {
+            continue;
+        }
+
+        verifier.update(chunk)?;
+        sink.write(chunk)?;
+        *offset += chunk.len() as u64;
+    }
+
+    // The body may end early when the connection is lost. Without a
+    // Content-Length, e.g. when chunked, a lost connection ends the stream
+    // with an error above, and the expected size is checked if it was given.
+    match validator.total_size.or(options.expected_size) {
+        Some(total_size) if *offset < total_size => {
+            Ok(PartOutcome::Interrupted(HttpError::IncompleteBody {
+                received: *offset,
+                expected: total_size,
+            }))
+        }
+        _ => Ok(PartOutcome::Complete),
+    }
+}
+
+fn header<'m>(metadata: &'m ResponseMetadata, name: &str) -> Option<&'m str> {
+    metadata
+        .headers
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.trim())
+}
+
+/// Parses `bytes <start>-<end>/<total>` into the start and the total size, if known
+fn parse_content_range(value: &str) -> Option<(u64, Option<u64>)> {
+    let (range, total) = value.strip_prefix("bytes ")?.split_once('/')?;
+    let (start, _) = range.split_once('-')?;
+    let total = match total {
+        "*" => None,
+        total => Some(total.parse().ok()?),
+    };
+    Some((start.parse().ok()?, total))
+}
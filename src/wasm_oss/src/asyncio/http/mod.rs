@@ -1,7 +1,15 @@
+pub mod auth;
 pub mod client;
+pub mod decode;
+pub mod download;
+pub mod policy;
+pub mod pool;
+pub mod proxy;
 pub mod request;
 pub mod response;
+pub mod router;
 pub mod server;
 pub mod stream;
 pub mod timeout;
 pub mod tls;
+pub mod websocket;
@@ -0,0 +1,116 @@
+use crate::errors::http_error::HttpError;
+use rand::Rng;
+
+/// Headers that carry credentials, which are not sent to other origins on redirects
+pub const SENSITIVE_HEADERS: [&str; 3] = ["authorization", "proxy-authorization", "cookie"];
+
+/// How many redirects are followed before giving up
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RedirectPolicy {
+    pub max_redirects: usize,
+}
+
+impl RedirectPolicy {
+    /// Returns 3xx responses as they are
+    pub fn none() -> Self {
+        Self { max_redirects: 0 }
+    }
+
+    pub fn limited(max_redirects: usize) -> Self {
+        Self { max_redirects }
+    }
+}
+
+impl Default for RedirectPolicy {
+    fn default() -> Self {
+        Self::limited(10)
+    }
+}
+
+/// A kind of failure that can be retried
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RetryOn {
+    /// DNS resolution or TCP connection failures
+    Connect,
+    /// TLS handshake failures
+    Tls,
+    /// Any phase of the request timing out before the response
+    Timeout,
+    /// Responses with this status code, such as 503
+    Status(u16),
+}
+
+/// Retries failed idempotent requests with exponential backoff and jitter
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt, 0 disables retrying
+    pub max_retries: u32,
+    /// Delay before the first retry, doubled for each further one
+    pub base_delay_ms: u64,
+    pub max_delay_ms: u64,
+    pub retry_on: Vec<RetryOn>,
+}
+
+impl RetryPolicy {
+    pub fn none() -> Self {
+        Self::new(0)
+    }
+
+    /// Retries connection, TLS and timeout failures and unavailable servers
+    pub fn new(max_retries: u32) -> Self {
+        Self {
+            max_retries,
+            base_delay_ms: 250,
+            max_delay_ms: 10_000,
+            retry_on: vec![
+                RetryOn::Connect,
+                RetryOn::Tls,
+                RetryOn::Timeout,
+                RetryOn::Status(502),
+                RetryOn::Status(503),
+                RetryOn::Status(504),
+            ],
+        }
+    }
+
+    pub fn with_retry_on(mut self, retry_on: Vec<RetryOn>) -> Self {
+        self.retry_on = retry_on;
+        self
+    }
+
+    pub fn with_delays(mut self, base_delay_ms: u64, max_delay_ms: u64) -> Self {
+        self.base_delay_ms = base_delay_ms;
+        self.max_delay_ms = max_delay_ms;
+        self
+    }
+
+    pub fn should_retry_error(&self, err: &HttpError) -> bool {
+        let kind = match err {
+            HttpError::Dns(_) | HttpError::Connect(_) | HttpError::Io(_) => RetryOn::Connect,
+            HttpError::Tls(_) | HttpError::ClockNotSynced => RetryOn::Tls,
+            HttpError::Timeout(_) => RetryOn::Timeout,
+            _ => return false,
+        };
+        self.retry_on.contains(&kind)
+    }
+
+    pub fn should_retry_status(&self, status_code: u16) -> bool {
+        self.retry_on.contains(&RetryOn::Status(status_code))
+    }
+
+    /// Delay before retry number `retry` (from 0), between half and all of
+    /// the exponential backoff, so clients failing together do not retry together
+    pub fn delay_ms(&self, retry: u32, rng: &mut impl Rng) -> u64 {
+        let backoff = self
+            .base_delay_ms
+            .saturating_mul(1u64 << retry.min(32))
+            .min(self.max_delay_ms);
+        rng.random_range(backoff / 2..=backoff)
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::none()
+    }
+}
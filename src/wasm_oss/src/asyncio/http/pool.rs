@@ -0,0 +1,183 @@
+use super::request::Body;
+use crate::ffi;
+use hyper::body::Incoming;
+use hyper::client::conn::{http1, http2};
+use hyper::{Request, Response};
+use std::collections::HashMap;
+
+/// Handle to send requests on an established connection
+pub enum Sender {
+    Http1(http1::SendRequest<Body>),
+    /// Cloned for each request, as they are multiplexed on the connection
+    Http2(http2::SendRequest<Body>),
+}
+
+impl Sender {
+    pub fn is_http2(&self) -> bool {
+        matches!(self, Self::Http2(_))
+    }
+
+    pub fn is_closed(&self) -> bool {
+        match self {
+            Self::Http1(sender) => sender.is_closed(),
+            Self::Http2(sender) => sender.is_closed(),
+        }
+    }
+
+    /// Waits until the connection can take another request
+    pub async fn ready(&mut self) -> Result<(), hyper::Error> {
+        match self {
+            Self::Http1(sender) => sender.ready().await,
+            Self::Http2(sender) => sender.ready().await,
+        }
+    }
+
+    pub async fn send_request(
+        &mut self,
+        req: Request<Body>,
+    ) -> Result<Response<Incoming>, hyper::Error> {
+        match self {
+            Self::Http1(sender) => sender.send_request(req).await,
+            Self::Http2(sender) => sender.send_request(req).await,
+        }
+    }
+
+    /// Returns another handle to an HTTP/2 connection
+    pub fn share(&self) -> Option<Self> {
+        match self {
+            Self::Http1(_) => None,
+            Self::Http2(sender) => Some(Self::Http2(sender.clone())),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolConfig {
+    /// Idle connections kept per scheme, host and port
+    pub max_idle_per_host: usize,
+    /// Idle connections kept in total, 0 disables pooling
+    pub max_idle: usize,
+    /// Time after which an idle connection is closed, kept below the
+    /// keep-alive timeout of common servers
+    pub idle_timeout_ms: u64,
+}
+
+impl Default for PoolConfig {
+    fn default() -> Self {
+        Self {
+            max_idle_per_host: 2,
+            max_idle: 8,
+            idle_timeout_ms: 15_000,
+        }
+    }
+}
+
+/// Identifies the connections that can serve a request
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PoolKey {
+    pub https: bool,
+    pub host: String,
+    pub port: u16,
+    /// Whether the server certificate was verified, required to send credentials
+    pub verified: bool,
+}
+
+struct IdleConnection {
+    sender: Sender,
+    idle_since: u64,
+}
+
+/// Idle HTTP/1 connections and HTTP/2 connections kept open for reuse by later requests
+pub struct Pool {
+    config: PoolConfig,
+    idle: HashMap<PoolKey, Vec<IdleConnection>>,
+}
+
+impl Pool {
+    pub fn new(config: PoolConfig) -> Self {
+        Self {
+            config,
+            idle: HashMap::new(),
+        }
+    }
+
+    /// Takes the most recently used idle connection for `key`, if any is still open.
+    /// HTTP/2 connections are shared rather than taken.
+    pub fn checkout(&mut self, key: &PoolKey) -> Option<Sender> {
+        self.evict_expired();
+        let connections = self.idle.get_mut(key)?;
+        if let Some(connection) = connections.last_mut() {
+            if let Some(shared) = connection.sender.share() {
+                connection.idle_since = unsafe { ffi::env_now() };
+                return Some(shared);
+            }
+        }
+
+        let sender = connections.pop().map(|connection| connection.sender);
+        if connections.is_empty() {
+            self.idle.remove(key);
+        }
+        sender
+    }
+
+    /// Returns a connection whose response was fully read, so it can be reused,
+    /// or makes a new HTTP/2 connection available to other requests
+    pub fn checkin(&mut self, key: PoolKey, sender: Sender) {
+        if sender.is_closed() || self.config.max_idle == 0 {
+            return;
+        }
+
+        self.evict_expired();
+        let connections = self.idle.entry(key).or_default();
+        if sender.is_http2() {
+            // A single connection serves all requests to the server
+            if let Some(connection) = connections.iter_mut().find(|c| c.sender.is_http2()) {
+                connection.idle_since = unsafe { ffi::env_now() };
+                return;
+            }
+        }
+        connections.push(IdleConnection {
+            sender,
+            idle_since: unsafe { ffi::env_now() },
+        });
+        if connections.len() > self.config.max_idle_per_host {
+            connections.remove(0);
+        }
+
+        // Close the connections idle for the longest time over the total cap
+        while self.idle.values().map(Vec::len).sum::<usize>() > self.config.max_idle {
+            let oldest = self
+                .idle
+                .iter()
+                .filter_map(|(key, connections)| Some((key, connections.first()?.idle_since)))
+                .min_by_key(|(_, idle_since)| *idle_since)
+                .map(|(key, _)| key.clone());
+            let Some(oldest) = oldest else {
+                break;
+            };
+            self.remove_first(&oldest);
+        }
+    }
+
+    /// Drops connections that timed out or were closed by the server
+    fn evict_expired(&mut self) {
+        let now = unsafe { ffi::env_now() };
+        let timeout = self.config.idle_timeout_ms;
+        self.idle.retain(|_, connections| {
+            connections.retain(|connection| {
+                !connection.sender.is_closed()
+                    && now.saturating_sub(connection.idle_since) < timeout
+            });
+            !connections.is_empty()
+        });
+    }
+
+    fn remove_first(&mut self, key: &PoolKey) {
+        if let Some(connections) = self.idle.get_mut(key) {
+            connections.remove(0);
+            if connections.is_empty() {
+                self.idle.remove(key);
+            }
+        }
+    }
+}
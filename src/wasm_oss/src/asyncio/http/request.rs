@@ -1,13 +1,65 @@
+use std::cell::RefCell;
 use std::collections::HashMap;
+use std::convert::Infallible;
+use std::fmt;
+use std::pin::Pin;
+use std::rc::Rc;
 use std::time::Duration;
 
 use bytes::Bytes;
+use futures::Stream;
 use serde_json::Value;
 
+/// Body of the requests sent by the client, which need not be `Send`
+pub type Body = Pin<Box<dyn hyper::body::Body<Data = Bytes, Error = Infallible>>>;
+
+type ChunkStream = Pin<Box<dyn Stream<Item = Bytes>>>;
+
 #[derive(Clone, Debug)]
 pub enum RequestBody {
     Json(Value),
     Data(Bytes),
+    Stream(BodyStream),
+}
+
+impl RequestBody {
+    /// A body sent as `stream` produces it, with chunked transfer encoding
+    /// unless `length` is known
+    pub fn stream(stream: impl Stream<Item = Bytes> + 'static, length: Option<u64>) -> Self {
+        Self::Stream(BodyStream {
+            stream: Rc::new(RefCell::new(Some(Box::pin(stream)))),
+            length,
+        })
+    }
+
+    /// Whether the body can be sent again, for retries and redirects
+    pub fn is_replayable(&self) -> bool {
+        !matches!(self, Self::Stream(_))
+    }
+}
+
+/// A body that is not held in memory, and can therefore only be sent once.
+/// Clones share the stream.
+#[derive(Clone)]
+pub struct BodyStream {
+    stream: Rc<RefCell<Option<ChunkStream>>>,
+    pub length: Option<u64>,
+}
+
+impl BodyStream {
+    /// Takes the stream to send it, `None` if it was already sent
+    pub fn take(&self) -> Option<ChunkStream> {
+        self.stream.borrow_mut().take()
+    }
+}
+
+impl fmt::Debug for BodyStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BodyStream")
+            .field("length", &self.length)
+            .field("sent", &self.stream.borrow().is_none())
+            .finish()
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -16,6 +68,8 @@ pub struct RequestConfig {
     pub headers: HashMap<String, String>,
     pub params: HashMap<String, String>,
     pub body: Option<RequestBody>,
+    /// Limit on the size of a response body after decompression
+    pub max_decompressed_size: u64,
 }
 
 impl RequestConfig {
@@ -25,6 +79,7 @@ impl RequestConfig {
             headers: HashMap::new(),
             params: HashMap::new(),
             body: None,
+            max_decompressed_size: 512 * 1024 * 1024,
         }
     }
 
@@ -43,6 +98,11 @@ impl RequestConfig {
         self
     }
 
+    pub fn with_max_decompressed_size(mut self, max_decompressed_size: u64) -> Self {
+        self.max_decompressed_size = max_decompressed_size;
+        self
+    }
+
     pub fn with_params(mut self, params: HashMap<String, String>) -> Self {
         self.params = params;
         self
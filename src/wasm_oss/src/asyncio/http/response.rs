@@ -1,5 +1,10 @@
+use super::download::{DownloadSink, MemorySink};
 use super::request::RequestConfig;
-use crate::errors::lwip_error::LwipError;
+use crate::errors::boot_error::BootError;
+use crate::errors::http_error::HttpError;
+use crate::errors::neoboot_error::NeoBootError;
+use crate::errors::verify_error::VerifyError;
+use crate::utils::verify::PayloadVerifier;
 use bytes::Bytes;
 use futures::StreamExt;
 use futures_lite::Stream;
@@ -9,7 +14,7 @@ use serde_json::Value;
 use std::pin::Pin;
 
 pub enum ResponseData {
-    Metadata(ResponseMetadata),
+    Metadata(Box<ResponseMetadata>),
     Stream(ResponseChunk),
 }
 
@@ -26,30 +31,38 @@ pub struct ResponseChunk {
     pub data: Bytes,
 }
 
+/// Size and digest of a response body that was copied out of WASM memory
+#[derive(Debug, Clone)]
+pub struct HostCopy {
+    pub size: u64,
+    /// Hex-encoded SHA-256 digest of the body
+    pub sha256: String,
+}
+
 pub struct Response<'a> {
     pub metadata: ResponseMetadata,
-    body_stream: Pin<Box<dyn Stream<Item = Result<ResponseData, Box<dyn std::error::Error>>> + 'a>>,
+    body_stream: Pin<Box<dyn Stream<Item = Result<ResponseData, HttpError>> + 'a>>,
 }
 
 impl<'a> Response<'a> {
     pub async fn new(
-        body_stream: impl Stream<Item = Result<ResponseData, Box<dyn std::error::Error>>> + 'a,
-    ) -> Result<Self, Box<dyn std::error::Error>> {
+        body_stream: impl Stream<Item = Result<ResponseData, HttpError>> + 'a,
+    ) -> Result<Self, HttpError> {
         let mut body_stream = Box::pin(body_stream);
         let metadata = body_stream.next().await;
 
         if metadata.is_none() {
-            return Err(Box::new(LwipError::InvalidValue));
+            return Err(HttpError::MalformedResponse("empty response"));
         }
 
         let metadata = metadata.unwrap();
 
         match metadata {
             Ok(ResponseData::Metadata(metadata)) => Ok(Self {
-                metadata,
+                metadata: *metadata,
                 body_stream,
             }),
-            Ok(ResponseData::Stream(_)) => Err(Box::new(LwipError::InvalidValue)),
+            Ok(ResponseData::Stream(_)) => Err(HttpError::MalformedResponse("body before headers")),
             Err(e) => {
                 info!("Error: {:?}", e);
                 Err(e)
@@ -57,41 +70,87 @@ impl<'a> Response<'a> {
         }
     }
 
-    pub async fn text(&mut self) -> Result<String, Box<dyn std::error::Error>> {
+    pub async fn text(&mut self) -> Result<String, HttpError> {
         let body = self.bytes().await?;
         let body = String::from_utf8_lossy(&body).to_string();
         Ok(body)
     }
 
-    pub async fn json(&mut self) -> Result<Value, Box<dyn std::error::Error>> {
+    pub async fn json(&mut self) -> Result<Value, HttpError> {
         let body = self.text().await?;
-        let json = serde_json::from_str(&body);
-        match json {
-            Ok(json) => Ok(json),
-            Err(e) => Err(Box::new(e)),
-        }
+        Ok(serde_json::from_str(&body)?)
     }
 
-    pub async fn stream(
-        self,
-    ) -> impl Stream<Item = Result<Bytes, Box<dyn std::error::Error>>> + use<'a> {
+    pub async fn stream(self) -> impl Stream<Item = Result<Bytes, HttpError>> + use<'a> {
         self.body_stream.map(|chunk| match chunk {
             Ok(ResponseData::Stream(chunk)) => Ok(chunk.data),
             Ok(ResponseData::Metadata(_)) => {
-                let err: Box<dyn std::error::Error> = Box::new(std::io::Error::new(
-                    std::io::ErrorKind::InvalidData,
-                    "Expected stream data, got metadata",
-                ));
-                Err(err)
-            }
-            Err(e) => {
-                let err: Box<dyn std::error::Error> = e;
-                Err(err)
+                Err(HttpError::MalformedResponse("headers after the body"))
             }
+            Err(e) => Err(e),
         })
     }
 
-    pub async fn bytes(&mut self) -> Result<Bytes, Box<dyn std::error::Error>> {
+    /// Length of the body announced by the server
+    pub fn content_length(&self) -> Option<u64> {
+        self.metadata
+            .headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case("content-length"))
+            .and_then(|(_, value)| value.trim().parse().ok())
+    }
+
+    /// Copies the body chunk by chunk to host memory at `address`, hashing it
+    /// on the way, so it is never held in WASM memory as a whole. Fails once
+    /// the body exceeds `max_len` bytes.
+    pub async fn copy_to_host(
+        &mut self,
+        address: u64,
+        max_len: u64,
+    ) -> Result<HostCopy, NeoBootError> {
+        let mut sink = MemorySink::new(address, max_len);
+        self.copy_to_sink(&mut sink, PayloadVerifier::new(None, ""))
+            .await
+    }
+
+    /// Copies the body chunk by chunk into `sink`, checking it with `verifier`
+    /// as it arrives
+    pub async fn copy_to_sink(
+        &mut self,
+        sink: &mut impl DownloadSink,
+        mut verifier: PayloadVerifier,
+    ) -> Result<HostCopy, NeoBootError> {
+        // Reject a body announced to be too large before copying any of it
+        if let (Some(content_length), Some(capacity)) = (self.content_length(), sink.capacity()) {
+            if content_length > capacity {
+                return Err(BootError::PayloadTooLarge { capacity }.into());
+            }
+        }
+
+        while let Some(chunk) = self.body_stream.next().await {
+            if let ResponseData::Stream(chunk) = chunk? {
+                verifier.update(&chunk.data)?;
+                sink.write(&chunk.data)?;
+            }
+        }
+
+        // A body cut short by a lost connection must not pass as complete
+        if let Some(content_length) = self.content_length() {
+            if verifier.size() != content_length {
+                return Err(VerifyError::SizeMismatch {
+                    expected: content_length,
+                    actual: verifier.size(),
+                }
+                .into());
+            }
+        }
+
+        let size = verifier.size();
+        let sha256 = verifier.finish()?;
+        Ok(HostCopy { size, sha256 })
+    }
+
+    pub async fn bytes(&mut self) -> Result<Bytes, HttpError> {
         let mut body_bytes = Vec::new();
         loop {
             let chunk = self.body_stream.next().await;
@@ -0,0 +1,280 @@
+//! Routes requests of the HTTP server to handlers by method and path.
+//!
+//! Paths are patterns of segments, where `{name}` matches a single segment
+//! and a final `{*name}` the rest of the path, e.g. `/api/v1/payloads/{name}`.
+//! The values are available to the handler through [`extract::RequestExt`].
+//!
+//! Middleware added with [`Router::layer`] wraps every request, including
+//! the ones no route matches, while [`Router::route_layer`] only wraps the
+//! routes added before it.
+
+use crate::errors::route_error::RouteError;
+use bytes::Bytes;
+use futures::future::LocalBoxFuture;
+use http::{HeaderValue, Method, Request, Response};
+use http_body_util::combinators::UnsyncBoxBody;
+use http_body_util::{BodyExt, Full};
+use hyper::body::Incoming;
+use percent_encoding::percent_decode_str;
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::future::Future;
+use std::rc::Rc;
+
+pub mod extract;
+pub mod middleware;
+
+/// Body of the responses, which are usually sent in one piece, but may be streamed
+pub type ResponseBody = UnsyncBoxBody<Bytes, Infallible>;
+
+pub type ResponseFuture<'a> = LocalBoxFuture<'a, Response<ResponseBody>>;
+
+/// Result of a handler, where errors are answered with their status code
+pub type RouteResult = Result<Response<ResponseBody>, RouteError>;
+
+type Endpoint<'a> = Rc<dyn Fn(Request<Incoming>) -> ResponseFuture<'a> + 'a>;
+
+/// Wraps the handling of requests, e.g. to reject or log them
+pub trait Middleware<'a> {
+    /// Handles `req`, usually by passing it on with `next.run`
+    fn call(&self, req: Request<Incoming>, next: Next<'a>) -> ResponseFuture<'a>;
+}
+
+/// The remaining middleware and the handler of a request
+pub struct Next<'a> {
+    middleware: Rc<Vec<Rc<dyn Middleware<'a> + 'a>>>,
+    index: usize,
+    endpoint: Endpoint<'a>,
+}
+
+impl<'a> Next<'a> {
+    pub fn run(mut self, req: Request<Incoming>) -> ResponseFuture<'a> {
+        match self.middleware.get(self.index).cloned() {
+            Some(middleware) => {
+                self.index += 1;
+                middleware.call(req, self)
+            }
+            None => (self.endpoint)(req),
+        }
+    }
+}
+
+/// Values of the `{name}` segments of the matched route
+#[derive(Debug, Clone, Default)]
+pub struct PathParams(pub HashMap<String, String>);
+
+enum Segment {
+    Literal(String),
+    Param(String),
+    Rest(String),
+}
+
+struct Route<'a> {
+    method: Method,
+    segments: Vec<Segment>,
+    handler: Endpoint<'a>,
+    middleware: Rc<Vec<Rc<dyn Middleware<'a> + 'a>>>,
+}
+
+impl Route<'_> {
+    /// Returns the path parameters if `path` matches the pattern
+    fn matches(&self, path: &str) -> Option<PathParams> {
+        let mut params = HashMap::new();
+        let mut parts = path.trim_start_matches('/').split('/');
+        for segment in &self.segments {
+            match segment {
+                Segment::Rest(name) => {
+                    let rest: Vec<&str> = parts.by_ref().collect();
+                    params.insert(name.clone(), decode(&rest.join("/"))?);
+                }
+                Segment::Literal(literal) => {
+                    if parts.next()? != literal {
+                        return None;
+                    }
+                }
+                Segment::Param(name) => match parts.next()? {
+                    "" => return None,
+                    part => {
+                        params.insert(name.clone(), decode(part)?);
+                    }
+                },
+            }
+        }
+
+        parts.next().is_none().then_some(PathParams(params))
+    }
+}
+
+fn decode(part: &str) -> Option<String> {
+    percent_decode_str(part)
+        .decode_utf8()
+        .ok()
+        .map(|part| part.into_owned())
+}
+
+fn parse_pattern(path: &str) -> Vec<Segment> {
+    let segments: Vec<Segment> = path
+        .trim_start_matches('/')
+        .split('/')
+        .map(
+            |segment| match segment.strip_prefix('{').and_then(|s| s.strip_suffix('}')) {
+                Some(name) => match name.strip_prefix('*') {
+                    Some(name) => Segment::Rest(name.to_string()),
+                    None => Segment::Param(name.to_string()),
+                },
+                None => Segment::Literal(segment.to_string()),
+            },
+        )
+        .collect();
+
+    if let Some(position) = segments.iter().position(|s| matches!(s, Segment::Rest(_))) {
+        assert!(
+            position + 1 == segments.len(),
+            "{{*name}} must be the last segment of {}",
+            path
+        );
+    }
+    segments
+}
+
+pub struct Router<'a> {
+    routes: Vec<Route<'a>>,
+    middleware: Rc<Vec<Rc<dyn Middleware<'a> + 'a>>>,
+}
+
+impl Default for Router<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a> Router<'a> {
+    pub fn new() -> Self {
+        Self {
+            routes: Vec::new(),
+            middleware: Rc::new(Vec::new()),
+        }
+    }
+
+    /// Routes requests with `method` and a path matching `path` to `handler`.
+    /// The first matching route is used.
+    pub fn route<F, Fut>(mut self, method: Method, path: &str, handler: F) -> Self
+    where
+        F: Fn(Request<Incoming>) -> Fut + 'a,
+        Fut: Future<Output = RouteResult> + 'a,
+    {
+        let handler: Endpoint<'a> = Rc::new(move |req| {
+            let response = handler(req);
+            Box::pin(async move { response.await.unwrap_or_else(error_response) })
+        });
+        self.routes.push(Route {
+            method,
+            segments: parse_pattern(path),
+            handler,
+            middleware: Rc::new(Vec::new()),
+        });
+        self
+    }
+
+    pub fn get<F, Fut>(self, path: &str, handler: F) -> Self
+    where
+        F: Fn(Request<Incoming>) -> Fut + 'a,
+        Fut: Future<Output = RouteResult> + 'a,
+    {
+        self.route(Method::GET, path, handler)
+    }
+
+    pub fn post<F, Fut>(self, path: &str, handler: F) -> Self
+    where
+        F: Fn(Request<Incoming>) -> Fut + 'a,
+        Fut: Future<Output = RouteResult> + 'a,
+    {
+        self.route(Method::POST, path, handler)
+    }
+
+    /// Wraps all requests in `middleware`, the last one added runs first
+    pub fn layer(mut self, middleware: impl Middleware<'a> + 'a) -> Self {
+        Rc::make_mut(&mut self.middleware).insert(0, Rc::new(middleware));
+        self
+    }
+
+    /// Wraps the routes added so far in `middleware`, once one of them matched
+    pub fn route_layer(mut self, middleware: impl Middleware<'a> + 'a) -> Self {
+        let middleware: Rc<dyn Middleware<'a> + 'a> = Rc::new(middleware);
+        for route in &mut self.routes {
+            Rc::make_mut(&mut route.middleware).insert(0, middleware.clone());
+        }
+        self
+    }
+
+    /// Passes `req` through the middleware to the handler of the matching route
+    pub fn handle(self: &Rc<Self>, req: Request<Incoming>) -> ResponseFuture<'a> {
+        let router = self.clone();
+        Next {
+            middleware: self.middleware.clone(),
+            index: 0,
+            endpoint: Rc::new(move |req| router.dispatch(req)),
+        }
+        .run(req)
+    }
+
+    fn dispatch(&self, mut req: Request<Incoming>) -> ResponseFuture<'a> {
+        let mut allowed = Vec::new();
+        for route in &self.routes {
+            let Some(params) = route.matches(req.uri().path()) else {
+                continue;
+            };
+            if route.method != req.method() {
+                allowed.push(route.method.clone());
+                continue;
+            }
+
+            req.extensions_mut().insert(params);
+            return Next {
+                middleware: route.middleware.clone(),
+                index: 0,
+                endpoint: route.handler.clone(),
+            }
+            .run(req);
+        }
+
+        let err = if allowed.is_empty() {
+            RouteError::NotFound
+        } else {
+            RouteError::MethodNotAllowed(allowed)
+        };
+        Box::pin(async move { error_response(err) })
+    }
+}
+
+/// A response with `body` sent in one piece
+pub fn full(body: impl Into<Bytes>) -> Response<ResponseBody> {
+    Response::new(Full::new(body.into()).boxed_unsync())
+}
+
+/// Answers `err` with its status code and message
+pub fn error_response(err: RouteError) -> Response<ResponseBody> {
+    let mut response = full(format!("{}\n", err));
+    *response.status_mut() = err.status();
+    let headers = response.headers_mut();
+    match &err {
+        RouteError::MethodNotAllowed(methods) => {
+            let allow = methods
+                .iter()
+                .map(Method::as_str)
+                .collect::<Vec<_>>()
+                .join(", ");
+            if let Ok(allow) = HeaderValue::from_str(&allow) {
+                headers.insert(http::header::ALLOW, allow);
+            }
+        }
+        RouteError::Unauthorized => {
+            headers.insert(
+                http::header::WWW_AUTHENTICATE,
+                HeaderValue::from_static("Bearer"),
+            );
+        }
+        _ => {}
+    }
+    response
+}
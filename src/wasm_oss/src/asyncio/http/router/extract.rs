@@ -0,0 +1,135 @@
+use super::PathParams;
+use crate::errors::route_error::RouteError;
+use base64::prelude::*;
+use bytes::Bytes;
+use http::Request;
+use http_body_util::{BodyExt, Limited};
+use hyper::body::Incoming;
+use std::fmt::Display;
+use std::str::FromStr;
+
+/// Typed access to the parts of a request
+pub trait RequestExt {
+    /// Parses the `{name}` segment of the matched route
+    fn path_param<T>(&self, name: &'static str) -> Result<T, RouteError>
+    where
+        T: FromStr,
+        T::Err: Display;
+
+    /// Parses the query parameter `name`, if it is present
+    fn query_param<T>(&self, name: &'static str) -> Result<Option<T>, RouteError>
+    where
+        T: FromStr,
+        T::Err: Display;
+
+    /// Parses the header `name`, if it is present
+    fn header<T>(&self, name: &'static str) -> Result<Option<T>, RouteError>
+    where
+        T: FromStr,
+        T::Err: Display;
+
+    /// Parses the header `name`, failing if it is missing
+    fn required_header<T>(&self, name: &'static str) -> Result<T, RouteError>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        self.header(name)?.ok_or(RouteError::MissingHeader(name))
+    }
+
+    /// Decodes a base64-encoded protobuf message from the header `name`
+    fn protobuf_header<T>(&self, name: &'static str) -> Result<T, RouteError>
+    where
+        T: prost::Message + Default;
+}
+
+impl<B> RequestExt for Request<B> {
+    fn path_param<T>(&self, name: &'static str) -> Result<T, RouteError>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        let value = self
+            .extensions()
+            .get::<PathParams>()
+            .and_then(|params| params.0.get(name))
+            .ok_or(RouteError::MissingParam(name))?;
+        parse_param(name, value)
+    }
+
+    fn query_param<T>(&self, name: &'static str) -> Result<Option<T>, RouteError>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        let query = self.uri().query().unwrap_or("");
+        url::form_urlencoded::parse(query.as_bytes())
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| parse_param(name, &value))
+            .transpose()
+    }
+
+    fn header<T>(&self, name: &'static str) -> Result<Option<T>, RouteError>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        let Some(value) = self.headers().get(name) else {
+            return Ok(None);
+        };
+        let invalid = |reason: String| RouteError::InvalidHeader { name, reason };
+        let value = value
+            .to_str()
+            .map_err(|_| invalid("Invalid header encoding".to_string()))?;
+        value
+            .trim()
+            .parse()
+            .map(Some)
+            .map_err(|e: T::Err| invalid(e.to_string()))
+    }
+
+    fn protobuf_header<T>(&self, name: &'static str) -> Result<T, RouteError>
+    where
+        T: prost::Message + Default,
+    {
+        let invalid = |reason: String| RouteError::InvalidHeader { name, reason };
+        let value: String = self.required_header(name)?;
+        let bytes = BASE64_STANDARD
+            .decode(value)
+            .map_err(|e| invalid(format!("Invalid base64 encoding: {}", e)))?;
+        T::decode(bytes.as_slice()).map_err(|e| invalid(format!("Invalid encoding: {}", e)))
+    }
+}
+
+fn parse_param<T>(name: &'static str, value: &str) -> Result<T, RouteError>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    value.parse().map_err(|e: T::Err| RouteError::InvalidParam {
+        name,
+        reason: e.to_string(),
+    })
+}
+
+/// Reads the whole body, failing once it exceeds `limit` bytes. Bodies too
+/// large to hold in memory are streamed by their handlers instead.
+pub async fn read_body(req: Request<Incoming>, limit: u64) -> Result<Bytes, RouteError> {
+    let body = Limited::new(req.into_body(), limit as usize);
+    match body.collect().await {
+        Ok(collected) => Ok(collected.to_bytes()),
+        Err(e) if e.is::<http_body_util::LengthLimitError>() => {
+            Err(RouteError::PayloadTooLarge(limit))
+        }
+        Err(e) => Err(RouteError::InvalidBody(e.to_string())),
+    }
+}
+
+/// Reads the body, of at most `limit` bytes, and decodes it as a protobuf message
+pub async fn protobuf_body<T>(req: Request<Incoming>, limit: u64) -> Result<T, RouteError>
+where
+    T: prost::Message + Default,
+{
+    let body = read_body(req, limit).await?;
+    T::decode(body).map_err(|e| RouteError::InvalidBody(e.to_string()))
+}
@@ -0,0 +1,140 @@
+use super::{error_response, Middleware, Next, ResponseFuture};
+use crate::errors::route_error::RouteError;
+use crate::ffi;
+use http::{HeaderValue, Request};
+use hyper::body::Incoming;
+use log::info;
+use std::cell::Cell;
+use std::rc::Rc;
+
+/// Header carrying the id of a request, set by the client or generated
+pub const REQUEST_ID_HEADER: &str = "X-Request-Id";
+
+/// Longest request id accepted from a client
+const MAX_REQUEST_ID_LEN: usize = 64;
+
+/// Id of the request, available to handlers as an extension
+#[derive(Debug, Clone)]
+pub struct RequestId(pub String);
+
+/// Tags each request with an id, taken from the `X-Request-Id` header if
+/// the client sent a valid one, and returns it in the response
+pub struct RequestIds {
+    /// Distinguishes the ids of different boots
+    prefix: u64,
+    counter: Rc<Cell<u64>>,
+}
+
+impl RequestIds {
+    pub fn new() -> Self {
+        Self {
+            prefix: unsafe { ffi::env_now() },
+            counter: Rc::new(Cell::new(0)),
+        }
+    }
+}
+
+impl Default for RequestIds {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a> Middleware<'a> for RequestIds {
+    fn call(&self, mut req: Request<Incoming>, next: Next<'a>) -> ResponseFuture<'a> {
+        let id = req
+            .headers()
+            .get(REQUEST_ID_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .filter(|id| !id.is_empty() && id.len() <= MAX_REQUEST_ID_LEN)
+            .map(str::to_string)
+            .unwrap_or_else(|| {
+                self.counter.set(self.counter.get() + 1);
+                format!("{:x}-{}", self.prefix, self.counter.get())
+            });
+        req.extensions_mut().insert(RequestId(id.clone()));
+
+        let response = next.run(req);
+        Box::pin(async move {
+            let mut response = response.await;
+            if let Ok(value) = HeaderValue::from_str(&id) {
+                response.headers_mut().insert(REQUEST_ID_HEADER, value);
+            }
+            response
+        })
+    }
+}
+
+/// Logs the method, path, status and duration of each request
+pub struct AccessLog;
+
+impl<'a> Middleware<'a> for AccessLog {
+    fn call(&self, req: Request<Incoming>, next: Next<'a>) -> ResponseFuture<'a> {
+        let start = unsafe { ffi::env_now() };
+        let method = req.method().clone();
+        let path = req.uri().path().to_string();
+        let id = req.extensions().get::<RequestId>().cloned();
+
+        let response = next.run(req);
+        Box::pin(async move {
+            let response = response.await;
+            let elapsed = unsafe { ffi::env_now() } - start;
+            match id {
+                Some(RequestId(id)) => info!(
+                    "{} {} {} in {} ms ({})",
+                    method,
+                    path,
+                    response.status().as_u16(),
+                    elapsed,
+                    id
+                ),
+                None => info!(
+                    "{} {} {} in {} ms",
+                    method,
+                    path,
+                    response.status().as_u16(),
+                    elapsed
+                ),
+            }
+            response
+        })
+    }
+}
+
+/// Rejects requests without `Authorization: Bearer <token>`
+pub struct BearerAuth {
+    expected: String,
+}
+
+impl BearerAuth {
+    pub fn new(token: impl AsRef<str>) -> Self {
+        Self {
+            expected: format!("Bearer {}", token.as_ref()),
+        }
+    }
+
+    /// Compares without returning early, so the time taken does not tell
+    /// how much of the token was right
+    fn is_authorized(&self, value: &[u8]) -> bool {
+        let expected = self.expected.as_bytes();
+        value.len() == expected.len()
+            && value
+                .iter()
+                .zip(expected)
+                .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+                == 0
+    }
+}
+
+impl<'a> Middleware<'a> for BearerAuth {
+    fn call(&self, req: Request<Incoming>, next: Next<'a>) -> ResponseFuture<'a> {
+        let authorized = req
+            .headers()
+            .get(http::header::AUTHORIZATION)
+            .is_some_and(|value| self.is_authorized(value.as_bytes()));
+        if !authorized {
+            return Box::pin(async { error_response(RouteError::Unauthorized) });
+        }
+        next.run(req)
+    }
+}
@@ -1,29 +1,46 @@
-use http::{Method, Request, Response, StatusCode};
-use http_body_util::{BodyExt, Full};
-use hyper::body::{Bytes, Incoming};
+use http::Request;
+use hyper::body::Incoming;
 use hyper::server::conn::http1::Builder;
 use hyper::service::service_fn;
 use log::{error, info};
+use std::convert::Infallible;
 use std::net::Ipv4Addr;
+use std::rc::Rc;
 
+use crate::asyncio::http::router::extract::read_body;
+use crate::asyncio::http::router::middleware::AccessLog;
+use crate::asyncio::http::router::{full, RouteResult, Router};
 use crate::asyncio::http::stream::AnyHttpStream;
 use crate::asyncio::net::TcpListener;
 use crate::executor::Executor;
 
+/// Largest body echoed, which is held in memory until it is sent back
+const ECHO_BODY_LIMIT: u64 = 1024 * 1024;
+
 pub async fn run_server(executor: &Executor<'_>) -> Result<(), Box<dyn std::error::Error>> {
     let addr = Ipv4Addr::UNSPECIFIED;
     let port = 8080;
 
     info!("Starting to serve on http://{}:{}", addr, port);
 
-    let incoming = TcpListener::bind(addr.to_string().as_str(), port)?;
+    let incoming = TcpListener::bind(addr, port)?;
 
-    let service = service_fn(echo);
+    let router = Rc::new(
+        Router::new()
+            .get("/", help)
+            .post("/echo", echo)
+            .layer(AccessLog),
+    );
 
     loop {
         let tcp_stream = AnyHttpStream::Http(incoming.accept().await?);
+        let router = router.clone();
 
         executor.spawn(async move {
+            let service = service_fn(move |req| {
+                let response = router.handle(req);
+                async move { Ok::<_, Infallible>(response.await) }
+            });
             if let Err(err) = Builder::new().serve_connection(tcp_stream, service).await {
                 error!("failed to serve connection: {err:#}");
             }
@@ -31,23 +48,10 @@ pub async fn run_server(executor: &Executor<'_>) -> Result<(), Box<dyn std::erro
     }
 }
 
-// Custom echo service, handling two different routes and a
-// catch-all 404 responder.
-async fn echo(req: Request<Incoming>) -> Result<Response<Full<Bytes>>, hyper::Error> {
-    let mut response = Response::new(Full::default());
-    match (req.method(), req.uri().path()) {
-        // Help route.
-        (&Method::GET, "/") => {
-            *response.body_mut() = Full::from("Try POST /echo\n");
-        }
-        // Echo service route.
-        (&Method::POST, "/echo") => {
-            *response.body_mut() = Full::from(req.into_body().collect().await?.to_bytes());
-        }
-        // Catch-all 404.
-        _ => {
-            *response.status_mut() = StatusCode::NOT_FOUND;
-        }
-    };
-    Ok(response)
+async fn help(_: Request<Incoming>) -> RouteResult {
+    Ok(full("Try POST /echo\n"))
+}
+
+async fn echo(req: Request<Incoming>) -> RouteResult {
+    Ok(full(read_body(req, ECHO_BODY_LIMIT).await?))
 }
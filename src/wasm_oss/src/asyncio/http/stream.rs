@@ -3,16 +3,32 @@ use futures_rustls::client::TlsStream;
 use hyper::rt::{self};
 use std::{
     io,
+    mem::MaybeUninit,
     pin::Pin,
     task::{Context, Poll},
 };
 
+/// Size of the buffer TLS streams are read through, a full TLS record fits
+const TLS_READ_BUFFER_SIZE: usize = 16 * 1024;
+
+/// Streams that read into uninitialized memory, so hyper's read buffer is
+/// filled in place without being zeroed first
+pub trait ReadUninit: AsyncRead + Unpin {
+    fn poll_read_uninit(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [MaybeUninit<u8>],
+    ) -> Poll<io::Result<usize>>;
+}
+
 pub enum AnyHttpStream<T>
 where
     T: AsyncRead + AsyncWrite,
 {
     Http(T),
-    Https(Box<TlsStream<T>>),
+    /// rustls copies plaintext into initialized memory, so hyper's reads go
+    /// through a buffer of the connection that was zeroed once
+    Https(Box<TlsStream<T>>, Box<[u8]>),
 }
 
 impl<T: AsyncRead + AsyncWrite> From<T> for AnyHttpStream<T> {
@@ -23,37 +39,36 @@ impl<T: AsyncRead + AsyncWrite> From<T> for AnyHttpStream<T> {
 
 impl<T: AsyncRead + AsyncWrite> From<TlsStream<T>> for AnyHttpStream<T> {
     fn from(inner: TlsStream<T>) -> Self {
-        Self::Https(Box::new(inner))
+        Self::Https(
+            Box::new(inner),
+            vec![0; TLS_READ_BUFFER_SIZE].into_boxed_slice(),
+        )
     }
 }
 
-impl<T: AsyncRead + AsyncWrite + Unpin> rt::Read for AnyHttpStream<T> {
+impl<T: ReadUninit + AsyncWrite> rt::Read for AnyHttpStream<T> {
     #[inline]
     fn poll_read(
         self: Pin<&mut Self>,
         cx: &mut Context,
         mut buf: rt::ReadBufCursor<'_>,
     ) -> Poll<Result<(), io::Error>> {
-        let mut buf_size = 512;
-        if buf_size > buf.remaining() {
-            buf_size = buf.remaining();
-        }
-        let mut ibuf = vec![0u8; buf_size];
-
-        match Pin::get_mut(self) {
-            Self::Http(s) => {
-                let pinned = std::pin::pin!(s);
-                pinned.poll_read(cx, &mut ibuf).map_ok(|n| {
-                    buf.put_slice(&ibuf[..n]);
-                })
-            }
-            Self::Https(s) => {
-                let pinned = std::pin::pin!(s);
-                pinned.poll_read(cx, &mut ibuf).map_ok(|n| {
-                    buf.put_slice(&ibuf[..n]);
+        // SAFETY: only initialized bytes are written, and `advance` covers no more than were read
+        let spare = unsafe { buf.as_mut() };
+        let result = match Pin::get_mut(self) {
+            Self::Http(s) => Pin::new(s).poll_read_uninit(cx, spare),
+            Self::Https(s, scratch) => {
+                let len = spare.len().min(scratch.len());
+                Pin::new(s).poll_read(cx, &mut scratch[..len]).map_ok(|n| {
+                    for (dst, src) in spare[..n].iter_mut().zip(&scratch[..n]) {
+                        dst.write(*src);
+                    }
+                    n
                 })
             }
-        }
+        };
+
+        result.map_ok(|n| unsafe { buf.advance(n) })
     }
 }
 
@@ -69,7 +84,7 @@ impl<T: AsyncRead + AsyncWrite + Unpin> rt::Write for AnyHttpStream<T> {
                 let pinned = std::pin::pin!(s);
                 pinned.poll_write(cx, buf)
             }
-            Self::Https(s) => {
+            Self::Https(s, _) => {
                 let pinned = std::pin::pin!(s);
                 pinned.poll_write(cx, buf)
             }
@@ -83,7 +98,7 @@ impl<T: AsyncRead + AsyncWrite + Unpin> rt::Write for AnyHttpStream<T> {
                 let pinned = std::pin::pin!(s);
                 pinned.poll_flush(cx)
             }
-            Self::Https(s) => {
+            Self::Https(s, _) => {
                 let pinned = std::pin::pin!(s);
                 pinned.poll_flush(cx)
             }
@@ -97,7 +112,7 @@ impl<T: AsyncRead + AsyncWrite + Unpin> rt::Write for AnyHttpStream<T> {
                 let pinned = std::pin::pin!(s);
                 pinned.poll_close(cx)
             }
-            Self::Https(s) => {
+            Self::Https(s, _) => {
                 let pinned = std::pin::pin!(s);
                 pinned.poll_close(cx)
             }
@@ -106,15 +121,149 @@ impl<T: AsyncRead + AsyncWrite + Unpin> rt::Write for AnyHttpStream<T> {
 
     #[inline]
     fn is_write_vectored(&self) -> bool {
-        false
+        true
     }
 
     #[inline]
     fn poll_write_vectored(
         self: Pin<&mut Self>,
-        _: &mut Context<'_>,
-        _: &[io::IoSlice<'_>],
+        cx: &mut Context<'_>,
+        bufs: &[io::IoSlice<'_>],
     ) -> Poll<Result<usize, io::Error>> {
-        todo!()
+        match Pin::get_mut(self) {
+            Self::Http(s) => {
+                let pinned = std::pin::pin!(s);
+                pinned.poll_write_vectored(cx, bufs)
+            }
+            Self::Https(s, _) => {
+                let pinned = std::pin::pin!(s);
+                pinned.poll_write_vectored(cx, bufs)
+            }
+        }
+    }
+}
+
+// Byte stream access, e.g. for WebSockets once the HTTP handshake is done
+impl<T: AsyncRead + AsyncWrite + Unpin> AsyncRead for AnyHttpStream<T> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        match Pin::get_mut(self) {
+            Self::Http(s) => Pin::new(s).poll_read(cx, buf),
+            Self::Https(s, _) => Pin::new(s).poll_read(cx, buf),
+        }
+    }
+}
+
+impl<T: AsyncRead + AsyncWrite + Unpin> AsyncWrite for AnyHttpStream<T> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        match Pin::get_mut(self) {
+            Self::Http(s) => Pin::new(s).poll_write(cx, buf),
+            Self::Https(s, _) => Pin::new(s).poll_write(cx, buf),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match Pin::get_mut(self) {
+            Self::Http(s) => Pin::new(s).poll_flush(cx),
+            Self::Https(s, _) => Pin::new(s).poll_flush(cx),
+        }
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match Pin::get_mut(self) {
+            Self::Http(s) => Pin::new(s).poll_close(cx),
+            Self::Https(s, _) => Pin::new(s).poll_close(cx),
+        }
+    }
+}
+
+/// A stream whose first bytes were already read, e.g. to detect the protocol,
+/// and are returned again before the rest
+pub struct Rewind<T> {
+    prefix: Vec<u8>,
+    position: usize,
+    inner: T,
+}
+
+impl<T> Rewind<T> {
+    pub fn new(prefix: Vec<u8>, inner: T) -> Self {
+        Self {
+            prefix,
+            position: 0,
+            inner,
+        }
+    }
+}
+
+impl<T: AsyncRead + Unpin> AsyncRead for Rewind<T> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = Pin::get_mut(self);
+        let remaining = &this.prefix[this.position..];
+        if remaining.is_empty() {
+            return Pin::new(&mut this.inner).poll_read(cx, buf);
+        }
+
+        let n = remaining.len().min(buf.len());
+        buf[..n].copy_from_slice(&remaining[..n]);
+        this.position += n;
+        Poll::Ready(Ok(n))
+    }
+}
+
+impl<T: ReadUninit> ReadUninit for Rewind<T> {
+    fn poll_read_uninit(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [MaybeUninit<u8>],
+    ) -> Poll<io::Result<usize>> {
+        let this = Pin::get_mut(self);
+        let remaining = &this.prefix[this.position..];
+        if remaining.is_empty() {
+            return Pin::new(&mut this.inner).poll_read_uninit(cx, buf);
+        }
+
+        let n = remaining.len().min(buf.len());
+        for (dst, src) in buf[..n].iter_mut().zip(remaining) {
+            dst.write(*src);
+        }
+        this.position += n;
+        Poll::Ready(Ok(n))
+    }
+}
+
+impl<T: AsyncWrite + Unpin> AsyncWrite for Rewind<T> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut Pin::get_mut(self).inner).poll_write(cx, buf)
+    }
+
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut Pin::get_mut(self).inner).poll_write_vectored(cx, bufs)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut Pin::get_mut(self).inner).poll_flush(cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut Pin::get_mut(self).inner).poll_close(cx)
     }
 }
@@ -1,8 +1,26 @@
 use std::sync::Arc;
 
+use super::auth::ClientIdentity;
+use crate::errors::http_error::HttpError;
+use crate::services::time::wall_clock;
 use futures_rustls::TlsConnector;
 use rustls::ClientConfig;
+use rustls_pki_types::UnixTime;
 use rustls_rustcrypto::provider;
+use std::time::Duration;
+
+/// Time for certificate validity checks, from the SNTP-synced wall clock.
+/// Connectors that verify are not created while the clock is unsynced.
+#[derive(Debug)]
+struct WallClockTime {}
+
+impl rustls::time_provider::TimeProvider for WallClockTime {
+    fn current_time(&self) -> Option<UnixTime> {
+        wall_clock()
+            .unix_ms
+            .map(|unix_ms| UnixTime::since_unix_epoch(Duration::from_millis(unix_ms)))
+    }
+}
 
 #[derive(Debug)]
 struct NoVerifyCert {}
@@ -55,18 +73,49 @@ impl rustls::client::danger::ServerCertVerifier for NoVerifyCert {
     }
 }
 
-pub fn create_tls_connector() -> TlsConnector {
+/// Creates a TLS connector, offering HTTP/2 via ALPN if `http2` is set and
+/// presenting `identity` to servers that ask for a client certificate.
+/// Server certificates are only checked with `verify`, which must be set
+/// whenever credentials are sent, including `identity`. Fails with
+/// `ClockNotSynced` if `verify` is set before SNTP has set the wall clock,
+/// as certificate validity cannot be checked without the time.
+pub fn create_tls_connector(
+    http2: bool,
+    identity: Option<&ClientIdentity>,
+    verify: bool,
+) -> Result<TlsConnector, HttpError> {
+    if identity.is_some() && !verify {
+        return Err(HttpError::Identity(
+            "client certificate requires server verification",
+        ));
+    }
+    if verify && wall_clock().unix_ms.is_none() {
+        return Err(HttpError::ClockNotSynced);
+    }
+
     let root_store =
         rustls::RootCertStore::from_iter(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
 
-    let mut config = rustls::ClientConfig::builder_with_provider(provider().into())
-        .with_safe_default_protocol_versions()
-        .unwrap()
-        .with_root_certificates(root_store)
-        .with_no_client_auth();
+    let builder =
+        rustls::ClientConfig::builder_with_details(provider().into(), Arc::new(WallClockTime {}))
+            .with_safe_default_protocol_versions()
+            .unwrap()
+            .with_root_certificates(root_store);
+    let mut config = match identity {
+        Some(identity) => builder
+            .with_client_auth_cert(identity.cert_chain.clone(), identity.key.clone_key())
+            .map_err(HttpError::ClientAuth)?,
+        None => builder.with_no_client_auth(),
+    };
 
-    let mut config = rustls::ClientConfig::dangerous(&mut config);
-    config.set_certificate_verifier(Arc::new(NoVerifyCert {}));
-    let client_config: ClientConfig = config.cfg.clone();
-    TlsConnector::from(Arc::new(client_config))
+    if !verify {
+        config
+            .dangerous()
+            .set_certificate_verifier(Arc::new(NoVerifyCert {}));
+    }
+    let mut client_config: ClientConfig = config;
+    if http2 {
+        client_config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+    }
+    Ok(TlsConnector::from(Arc::new(client_config)))
 }
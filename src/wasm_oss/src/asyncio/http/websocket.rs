@@ -0,0 +1,406 @@
+//! WebSocket connections (RFC 6455) with the permessage-deflate extension (RFC 7692).
+//!
+//! Connections are opened with `Client::websocket`, or accepted from a server
+//! route with `handshake::upgrade`. Pings are answered and the close handshake
+//! is completed while receiving.
+use crate::errors::websocket_error::WebSocketError;
+use crate::ffi;
+use bytes::{Bytes, BytesMut};
+use deflate::{Deflate, DeflateParams};
+use frame::{
+    apply_mask, FrameHeader, MAX_CONTROL_PAYLOAD, MAX_HEADER_LEN, OP_BINARY, OP_CLOSE,
+    OP_CONTINUATION, OP_PING, OP_PONG, OP_TEXT,
+};
+use futures::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::borrow::Cow;
+
+pub mod deflate;
+pub mod frame;
+pub mod handshake;
+
+/// Close status codes (RFC 6455, section 7.4.1)
+pub const CLOSE_NORMAL: u16 = 1000;
+pub const CLOSE_GOING_AWAY: u16 = 1001;
+
+/// Bytes read from the connection at a time
+const READ_CHUNK: usize = 16 * 1024;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Message {
+    Text(String),
+    Binary(Bytes),
+    Ping(Bytes),
+    Pong(Bytes),
+    Close(Option<CloseFrame>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CloseFrame {
+    pub code: u16,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct WebSocketConfig {
+    /// Largest message received, after decompression
+    pub max_message_size: usize,
+    /// Messages sent are split into frames of at most this many bytes
+    pub max_frame_size: usize,
+    /// Whether to negotiate permessage-deflate
+    pub compression: bool,
+}
+
+impl Default for WebSocketConfig {
+    fn default() -> Self {
+        Self {
+            max_message_size: 16 * 1024 * 1024,
+            max_frame_size: 64 * 1024,
+            compression: true,
+        }
+    }
+}
+
+/// Side of the connection, only clients mask their frames
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    Client,
+    Server,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Open,
+    /// A close frame was sent, the peer's is awaited
+    CloseSent,
+    Closed,
+}
+
+/// A message that is received in several frames
+struct Partial {
+    opcode: u8,
+    compressed: bool,
+    data: Vec<u8>,
+}
+
+pub struct WebSocket<S> {
+    stream: S,
+    role: Role,
+    config: WebSocketConfig,
+    deflate: Option<Deflate>,
+    read_buf: BytesMut,
+    partial: Option<Partial>,
+    state: State,
+    /// Source of the masking keys of a client
+    rng: StdRng,
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> WebSocket<S> {
+    /// Wraps a connection after the handshake, where `read_buf` holds the
+    /// bytes already read past it
+    pub fn from_raw(
+        stream: S,
+        role: Role,
+        deflate: Option<DeflateParams>,
+        config: WebSocketConfig,
+        read_buf: Vec<u8>,
+    ) -> Self {
+        Self {
+            stream,
+            role,
+            config,
+            deflate: deflate.map(|params| Deflate::new(params, role == Role::Server)),
+            read_buf: BytesMut::from(&read_buf[..]),
+            partial: None,
+            state: State::Open,
+            rng: StdRng::seed_from_u64(unsafe { ffi::env_now() }),
+        }
+    }
+
+    /// Whether permessage-deflate was negotiated
+    pub fn is_compressed(&self) -> bool {
+        self.deflate.is_some()
+    }
+
+    /// Receives the next message, returning `None` once the connection is
+    /// closed. Pings are answered, and a close frame from the peer is
+    /// answered before it is returned.
+    pub async fn recv(&mut self) -> Option<Result<Message, WebSocketError>> {
+        if self.state == State::Closed {
+            return None;
+        }
+
+        match self.recv_message().await {
+            Ok(message) => Some(Ok(message)),
+            Err(e) => {
+                self.fail(&e).await;
+                Some(Err(e))
+            }
+        }
+    }
+
+    pub async fn send(&mut self, message: Message) -> Result<(), WebSocketError> {
+        if self.state != State::Open {
+            return Err(WebSocketError::Closed);
+        }
+
+        match message {
+            Message::Text(text) => self.send_data(OP_TEXT, text.as_bytes()).await,
+            Message::Binary(data) => self.send_data(OP_BINARY, &data).await,
+            Message::Ping(data) => self.send_control(OP_PING, &data).await,
+            Message::Pong(data) => self.send_control(OP_PONG, &data).await,
+            Message::Close(frame) => self.close(frame).await,
+        }
+    }
+
+    /// Starts the close handshake and waits for the peer to answer it,
+    /// dropping messages that arrive in the meantime
+    pub async fn close(&mut self, frame: Option<CloseFrame>) -> Result<(), WebSocketError> {
+        if self.state != State::Open {
+            return Ok(());
+        }
+
+        let mut payload = Vec::new();
+        if let Some(frame) = &frame {
+            payload.extend_from_slice(&frame.code.to_be_bytes());
+            // A reason cut inside a character would make the peer fail with 1007
+            let mut len = frame.reason.len().min(MAX_CONTROL_PAYLOAD - 2);
+            while !frame.reason.is_char_boundary(len) {
+                len -= 1;
+            }
+            payload.extend_from_slice(&frame.reason.as_bytes()[..len]);
+        }
+        self.write_frame(OP_CLOSE, &payload, true, false).await?;
+        self.state = State::CloseSent;
+
+        loop {
+            match self.recv_message().await {
+                Ok(Message::Close(_)) => return Ok(()),
+                Ok(_) => continue,
+                Err(e) => {
+                    self.fail(&e).await;
+                    return Err(e);
+                }
+            }
+        }
+    }
+
+    /// Fails the connection, sending a close frame with the matching status
+    /// code if the error has one (RFC 6455, section 7.1.7)
+    async fn fail(&mut self, err: &WebSocketError) {
+        if let (Some(code), State::Open) = (err.close_code(), self.state) {
+            let _ = self
+                .write_frame(OP_CLOSE, &code.to_be_bytes(), true, false)
+                .await;
+        }
+        self.state = State::Closed;
+        let _ = self.stream.close().await;
+    }
+
+    async fn recv_message(&mut self) -> Result<Message, WebSocketError> {
+        loop {
+            let (header, payload) = self.read_frame().await?;
+
+            if header.is_control() {
+                if !header.fin || header.rsv1 || payload.len() > MAX_CONTROL_PAYLOAD {
+                    return Err(WebSocketError::Protocol("invalid control frame"));
+                }
+
+                match header.opcode {
+                    OP_PING => {
+                        if self.state == State::Open {
+                            self.write_frame(OP_PONG, &payload, true, false).await?;
+                        }
+                        return Ok(Message::Ping(payload.freeze()));
+                    }
+                    OP_PONG => return Ok(Message::Pong(payload.freeze())),
+                    OP_CLOSE => {
+                        let frame = parse_close(&payload)?;
+                        if self.state == State::Open {
+                            // Echo the status code to complete the handshake
+                            let code = frame.as_ref().map(|frame| frame.code.to_be_bytes());
+                            self.write_frame(
+                                OP_CLOSE,
+                                code.as_ref().map_or(&[][..], |c| c),
+                                true,
+                                false,
+                            )
+                            .await?;
+                        }
+                        // The server closes the TCP connection first (RFC 6455, section 7.1.1)
+                        self.state = State::Closed;
+                        if self.role == Role::Server {
+                            let _ = self.stream.close().await;
+                        }
+                        return Ok(Message::Close(frame));
+                    }
+                    _ => return Err(WebSocketError::Protocol("reserved opcode")),
+                }
+            }
+
+            let partial = match header.opcode {
+                OP_TEXT | OP_BINARY => {
+                    if self.partial.is_some() {
+                        return Err(WebSocketError::Protocol("expected continuation frame"));
+                    }
+                    if header.rsv1 && self.deflate.is_none() {
+                        return Err(WebSocketError::Protocol("compression was not negotiated"));
+                    }
+                    Partial {
+                        opcode: header.opcode,
+                        compressed: header.rsv1,
+                        data: payload.to_vec(),
+                    }
+                }
+                OP_CONTINUATION => {
+                    if header.rsv1 {
+                        return Err(WebSocketError::Protocol("RSV1 set on continuation frame"));
+                    }
+                    let mut partial = self
+                        .partial
+                        .take()
+                        .ok_or(WebSocketError::Protocol("unexpected continuation frame"))?;
+                    partial.data.extend_from_slice(&payload);
+                    partial
+                }
+                _ => return Err(WebSocketError::Protocol("reserved opcode")),
+            };
+
+            if !header.fin {
+                self.partial = Some(partial);
+                continue;
+            }
+
+            let data = match (&mut self.deflate, partial.compressed) {
+                (Some(deflate), true) => {
+                    deflate.decompress(&partial.data, self.config.max_message_size)?
+                }
+                _ => partial.data,
+            };
+            return match partial.opcode {
+                OP_TEXT => String::from_utf8(data)
+                    .map(Message::Text)
+                    .map_err(|_| WebSocketError::InvalidUtf8),
+                _ => Ok(Message::Binary(data.into())),
+            };
+        }
+    }
+
+    /// Reads the next frame and unmasks its payload
+    async fn read_frame(&mut self) -> Result<(FrameHeader, BytesMut), WebSocketError> {
+        loop {
+            if let Some((header, header_len)) = FrameHeader::parse(&self.read_buf)? {
+                match (self.role, header.mask.is_some()) {
+                    (Role::Server, false) => {
+                        return Err(WebSocketError::Protocol("client frame not masked"))
+                    }
+                    (Role::Client, true) => {
+                        return Err(WebSocketError::Protocol("server frame masked"))
+                    }
+                    _ => {}
+                }
+
+                let received = self.partial.as_ref().map_or(0, |p| p.data.len());
+                let limit = self.config.max_message_size.saturating_sub(received);
+                if header.length > limit as u64 {
+                    return Err(WebSocketError::MessageTooLarge(
+                        self.config.max_message_size,
+                    ));
+                }
+
+                let frame_len = header_len + header.length as usize;
+                if self.read_buf.len() >= frame_len {
+                    let mut frame = self.read_buf.split_to(frame_len);
+                    let mut payload = frame.split_off(header_len);
+                    if let Some(mask) = header.mask {
+                        apply_mask(&mut payload, mask);
+                    }
+                    return Ok((header, payload));
+                }
+                self.read_buf.reserve(frame_len - self.read_buf.len());
+            }
+
+            let start = self.read_buf.len();
+            self.read_buf.resize(start + READ_CHUNK, 0);
+            let result = self.stream.read(&mut self.read_buf[start..]).await;
+            let n = *result.as_ref().unwrap_or(&0);
+            self.read_buf.truncate(start + n);
+            if result? == 0 {
+                return Err(WebSocketError::Io(std::io::ErrorKind::UnexpectedEof.into()));
+            }
+        }
+    }
+
+    async fn send_data(&mut self, opcode: u8, data: &[u8]) -> Result<(), WebSocketError> {
+        let (data, compressed) = match &mut self.deflate {
+            Some(deflate) => (Cow::Owned(deflate.compress(data)?), true),
+            None => (Cow::Borrowed(data), false),
+        };
+
+        // An empty message is still sent as one frame
+        let frame_size = self.config.max_frame_size.max(1);
+        let frame_count = data.len().div_ceil(frame_size).max(1);
+        for index in 0..frame_count {
+            let start = index * frame_size;
+            let end = (start + frame_size).min(data.len());
+            let opcode = if index == 0 { opcode } else { OP_CONTINUATION };
+            let fin = index + 1 == frame_count;
+            self.write_frame(opcode, &data[start..end], fin, compressed && index == 0)
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn send_control(&mut self, opcode: u8, data: &[u8]) -> Result<(), WebSocketError> {
+        if data.len() > MAX_CONTROL_PAYLOAD {
+            return Err(WebSocketError::Protocol("control frame payload too long"));
+        }
+        self.write_frame(opcode, data, true, false).await
+    }
+
+    async fn write_frame(
+        &mut self,
+        opcode: u8,
+        payload: &[u8],
+        fin: bool,
+        rsv1: bool,
+    ) -> Result<(), WebSocketError> {
+        let mask = (self.role == Role::Client).then(|| self.rng.random::<[u8; 4]>());
+        let header = FrameHeader {
+            fin,
+            rsv1,
+            opcode,
+            mask,
+            length: payload.len() as u64,
+        };
+
+        let mut frame = Vec::with_capacity(MAX_HEADER_LEN + payload.len());
+        header.write(&mut frame);
+        let start = frame.len();
+        frame.extend_from_slice(payload);
+        if let Some(mask) = mask {
+            apply_mask(&mut frame[start..], mask);
+        }
+
+        self.stream.write_all(&frame).await?;
+        self.stream.flush().await?;
+        Ok(())
+    }
+}
+
+/// Parses the status code and reason of a close frame (RFC 6455, section 5.5.1)
+fn parse_close(payload: &[u8]) -> Result<Option<CloseFrame>, WebSocketError> {
+    let (code, reason) = match payload {
+        [] => return Ok(None),
+        [_] => return Err(WebSocketError::Protocol("truncated close frame")),
+        [high, low, reason @ ..] => (u16::from_be_bytes([*high, *low]), reason),
+    };
+
+    // Codes that may be sent, 1004 to 1006 and 1015 are reserved for local use
+    if !matches!(code, 1000..=1003 | 1007..=1011 | 3000..=4999) {
+        return Err(WebSocketError::Protocol("invalid close code"));
+    }
+    let reason = String::from_utf8(reason.to_vec()).map_err(|_| WebSocketError::InvalidUtf8)?;
+    Ok(Some(CloseFrame { code, reason }))
+}
@@ -0,0 +1,222 @@
+use crate::errors::websocket_error::WebSocketError;
+use flate2::{Compress, Compression, Decompress, FlushCompress, FlushDecompress, Status};
+
+pub const EXTENSION_NAME: &str = "permessage-deflate";
+
+/// Offer sent by clients. The compressor always uses a 32 KiB window, so
+/// `client_max_window_bits` is not offered, which keeps servers from asking
+/// for a smaller one.
+pub const CLIENT_OFFER: &str = "permessage-deflate";
+
+/// Tail of a sync flush, left out of compressed messages (RFC 7692, section 7.2.1)
+const SYNC_FLUSH_TAIL: [u8; 4] = [0x00, 0x00, 0xff, 0xff];
+
+/// Parameters of the permessage-deflate extension agreed on in the handshake
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DeflateParams {
+    /// The server compresses each message on its own
+    pub server_no_context_takeover: bool,
+    /// The client compresses each message on its own
+    pub client_no_context_takeover: bool,
+}
+
+impl DeflateParams {
+    /// Value of the `Sec-WebSocket-Extensions` header accepting these parameters
+    pub fn response(&self) -> String {
+        let mut value = EXTENSION_NAME.to_string();
+        if self.server_no_context_takeover {
+            value.push_str("; server_no_context_takeover");
+        }
+        if self.client_no_context_takeover {
+            value.push_str("; client_no_context_takeover");
+        }
+        value
+    }
+
+    /// Picks the first offer in a client's `Sec-WebSocket-Extensions` header
+    /// that can be accepted. Offers limiting the server's window below 15
+    /// bits are declined, as the compressor cannot honour them.
+    pub fn accept_offer(header: &str) -> Option<Self> {
+        header.split(',').find_map(|offer| {
+            let mut parts = offer.split(';').map(str::trim);
+            if parts.next() != Some(EXTENSION_NAME) {
+                return None;
+            }
+
+            let mut params = Self::default();
+            let mut seen = Vec::new();
+            for part in parts {
+                let (name, value) = match part.split_once('=') {
+                    Some((name, value)) => (name.trim(), Some(value.trim().trim_matches('"'))),
+                    None => (part, None),
+                };
+                // Each parameter may only be given once (RFC 7692, section 5.1)
+                if seen.contains(&name) {
+                    return None;
+                }
+                seen.push(name);
+
+                match (name, value) {
+                    ("server_no_context_takeover", None) => {
+                        params.server_no_context_takeover = true
+                    }
+                    ("client_no_context_takeover", None) => {
+                        params.client_no_context_takeover = true
+                    }
+                    ("server_max_window_bits", Some(bits)) if parse_window_bits(bits)? == 15 => {}
+                    // Messages from the client are inflated with the largest window anyway
+                    ("client_max_window_bits", None) => {}
+                    ("client_max_window_bits", Some(bits)) => {
+                        parse_window_bits(bits)?;
+                    }
+                    _ => return None,
+                }
+            }
+            Some(params)
+        })
+    }
+
+    /// Checks a server's response to `CLIENT_OFFER`
+    pub fn parse_response(header: &str) -> Result<Self, WebSocketError> {
+        let invalid =
+            || WebSocketError::Handshake(format!("invalid extension response: {}", header));
+        if header.contains(',') {
+            return Err(invalid());
+        }
+
+        let mut parts = header.split(';').map(str::trim);
+        if parts.next() != Some(EXTENSION_NAME) {
+            return Err(invalid());
+        }
+
+        let mut params = Self::default();
+        for part in parts {
+            let (name, value) = match part.split_once('=') {
+                Some((name, value)) => (name.trim(), Some(value.trim().trim_matches('"'))),
+                None => (part, None),
+            };
+            match (name, value) {
+                ("server_no_context_takeover", None) => params.server_no_context_takeover = true,
+                ("client_no_context_takeover", None) => params.client_no_context_takeover = true,
+                // Messages from the server are inflated with the largest window anyway
+                ("server_max_window_bits", Some(bits)) if parse_window_bits(bits).is_some() => {}
+                _ => return Err(invalid()),
+            }
+        }
+        Ok(params)
+    }
+}
+
+fn parse_window_bits(bits: &str) -> Option<u8> {
+    let bits: u8 = bits.parse().ok()?;
+    (8..=15).contains(&bits).then_some(bits)
+}
+
+/// Compresses and inflates the messages of one connection
+pub struct Deflate {
+    compress: Compress,
+    decompress: Decompress,
+    /// Whether the compression context is dropped after each message sent
+    reset_compress: bool,
+    /// Whether the peer drops its compression context after each message
+    reset_decompress: bool,
+}
+
+impl Deflate {
+    pub fn new(params: DeflateParams, is_server: bool) -> Self {
+        let (reset_compress, reset_decompress) = if is_server {
+            (
+                params.server_no_context_takeover,
+                params.client_no_context_takeover,
+            )
+        } else {
+            (
+                params.client_no_context_takeover,
+                params.server_no_context_takeover,
+            )
+        };
+
+        Self {
+            compress: Compress::new(Compression::fast(), false),
+            decompress: Decompress::new(false),
+            reset_compress,
+            reset_decompress,
+        }
+    }
+
+    pub fn compress(&mut self, data: &[u8]) -> Result<Vec<u8>, WebSocketError> {
+        let mut out = Vec::with_capacity(data.len() / 2 + 64);
+        let start = self.compress.total_in();
+        loop {
+            if out.capacity() - out.len() < 64 {
+                out.reserve(out.capacity().max(1024));
+            }
+            let consumed = (self.compress.total_in() - start) as usize;
+            self.compress
+                .compress_vec(&data[consumed..], &mut out, FlushCompress::Sync)
+                .map_err(|e| WebSocketError::Compression(e.to_string()))?;
+
+            // Spare room in the output means the flush is complete
+            let consumed = (self.compress.total_in() - start) as usize;
+            if consumed == data.len() && out.len() < out.capacity() {
+                break;
+            }
+        }
+
+        if out.ends_with(&SYNC_FLUSH_TAIL) {
+            out.truncate(out.len() - SYNC_FLUSH_TAIL.len());
+        }
+        if self.reset_compress {
+            self.compress.reset();
+        }
+        Ok(out)
+    }
+
+    /// Inflates a complete message, failing once it exceeds `limit` bytes
+    pub fn decompress(&mut self, data: &[u8], limit: usize) -> Result<Vec<u8>, WebSocketError> {
+        let mut input = Vec::with_capacity(data.len() + SYNC_FLUSH_TAIL.len());
+        input.extend_from_slice(data);
+        input.extend_from_slice(&SYNC_FLUSH_TAIL);
+
+        let mut out = Vec::with_capacity((data.len() * 4).clamp(64, limit.max(64)));
+        let start = self.decompress.total_in();
+        loop {
+            if out.len() > limit {
+                return Err(WebSocketError::MessageTooLarge(limit));
+            }
+            if out.len() == out.capacity() {
+                out.reserve(out.capacity());
+            }
+
+            let consumed = (self.decompress.total_in() - start) as usize;
+            let produced = out.len();
+            let status = self
+                .decompress
+                .decompress_vec(&input[consumed..], &mut out, FlushDecompress::Sync)
+                .map_err(|e| WebSocketError::Compression(e.to_string()))?;
+
+            let consumed_now = (self.decompress.total_in() - start) as usize;
+            if status == Status::StreamEnd {
+                // A final block ends the message, the rest of the input is ignored
+                self.decompress.reset(false);
+                break;
+            }
+            if consumed_now == input.len() && out.len() < out.capacity() {
+                break;
+            }
+            if consumed_now == consumed && out.len() == produced && out.len() < out.capacity() {
+                return Err(WebSocketError::Compression(
+                    "truncated compressed data".to_string(),
+                ));
+            }
+        }
+
+        if out.len() > limit {
+            return Err(WebSocketError::MessageTooLarge(limit));
+        }
+        if self.reset_decompress {
+            self.decompress.reset(false);
+        }
+        Ok(out)
+    }
+}
@@ -0,0 +1,117 @@
+use crate::errors::websocket_error::WebSocketError;
+
+/// Frame opcodes (RFC 6455, section 5.2)
+pub const OP_CONTINUATION: u8 = 0x0;
+pub const OP_TEXT: u8 = 0x1;
+pub const OP_BINARY: u8 = 0x2;
+pub const OP_CLOSE: u8 = 0x8;
+pub const OP_PING: u8 = 0x9;
+pub const OP_PONG: u8 = 0xa;
+
+/// Largest payload of a control frame
+pub const MAX_CONTROL_PAYLOAD: usize = 125;
+
+/// Longest header, with a 64-bit length and a masking key
+pub const MAX_HEADER_LEN: usize = 14;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameHeader {
+    /// Last frame of the message
+    pub fin: bool,
+    /// Set on the first frame of a compressed message (RFC 7692, section 6)
+    pub rsv1: bool,
+    pub opcode: u8,
+    /// Key the payload is masked with, set on all frames sent by clients
+    pub mask: Option<[u8; 4]>,
+    pub length: u64,
+}
+
+impl FrameHeader {
+    pub fn is_control(&self) -> bool {
+        self.opcode & 0x8 != 0
+    }
+
+    /// Parses the header at the start of `buf`, returning it and its length,
+    /// or `None` if more bytes are needed
+    pub fn parse(buf: &[u8]) -> Result<Option<(Self, usize)>, WebSocketError> {
+        if buf.len() < 2 {
+            return Ok(None);
+        }
+        // RSV2 and RSV3 have no meaning without an extension that defines them
+        if buf[0] & 0x30 != 0 {
+            return Err(WebSocketError::Protocol("reserved bits set"));
+        }
+
+        let (length, mut offset) = match buf[1] & 0x7f {
+            126 => match buf.get(2..4) {
+                Some(bytes) => (u16::from_be_bytes([bytes[0], bytes[1]]) as u64, 4),
+                None => return Ok(None),
+            },
+            127 => match buf.get(2..10) {
+                Some(bytes) => {
+                    let length = u64::from_be_bytes(bytes.try_into().unwrap());
+                    if length >> 63 != 0 {
+                        return Err(WebSocketError::Protocol("invalid frame length"));
+                    }
+                    (length, 10)
+                }
+                None => return Ok(None),
+            },
+            length => (length as u64, 2),
+        };
+
+        let mask = if buf[1] & 0x80 != 0 {
+            match buf.get(offset..offset + 4) {
+                Some(bytes) => {
+                    offset += 4;
+                    Some(bytes.try_into().unwrap())
+                }
+                None => return Ok(None),
+            }
+        } else {
+            None
+        };
+
+        let header = Self {
+            fin: buf[0] & 0x80 != 0,
+            rsv1: buf[0] & 0x40 != 0,
+            opcode: buf[0] & 0x0f,
+            mask,
+            length,
+        };
+        Ok(Some((header, offset)))
+    }
+
+    /// Appends the header to `out`, using the shortest length encoding
+    pub fn write(&self, out: &mut Vec<u8>) {
+        out.push((self.fin as u8) << 7 | (self.rsv1 as u8) << 6 | self.opcode);
+
+        let mask_bit = if self.mask.is_some() { 0x80 } else { 0 };
+        if self.length < 126 {
+            out.push(mask_bit | self.length as u8);
+        } else if self.length <= u16::MAX as u64 {
+            out.push(mask_bit | 126);
+            out.extend_from_slice(&(self.length as u16).to_be_bytes());
+        } else {
+            out.push(mask_bit | 127);
+            out.extend_from_slice(&self.length.to_be_bytes());
+        }
+
+        if let Some(mask) = self.mask {
+            out.extend_from_slice(&mask);
+        }
+    }
+}
+
+/// Masks or unmasks a payload, as the operation is its own inverse (RFC 6455, section 5.3)
+pub fn apply_mask(data: &mut [u8], mask: [u8; 4]) {
+    let word = u32::from_ne_bytes(mask);
+    let mut chunks = data.chunks_exact_mut(4);
+    for chunk in &mut chunks {
+        let masked = u32::from_ne_bytes((&*chunk).try_into().unwrap()) ^ word;
+        chunk.copy_from_slice(&masked.to_ne_bytes());
+    }
+    for (byte, mask) in chunks.into_remainder().iter_mut().zip(mask) {
+        *byte ^= mask;
+    }
+}
@@ -0,0 +1,249 @@
+use super::deflate::{DeflateParams, CLIENT_OFFER};
+use super::{Role, WebSocket, WebSocketConfig};
+use crate::errors::websocket_error::WebSocketError;
+use crate::ffi;
+use base64::prelude::*;
+use bytes::Bytes;
+use futures::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use http::{header, HeaderMap, Method, Request, Response, StatusCode};
+use http_body_util::Full;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use sha1::{Digest, Sha1};
+
+/// Appended to the key before hashing it (RFC 6455, section 1.3)
+const ACCEPT_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+/// Largest response head accepted from a server
+const MAX_RESPONSE_HEAD: usize = 16 * 1024;
+
+/// Value of `Sec-WebSocket-Accept` for a `Sec-WebSocket-Key`
+pub fn accept_key(key: &str) -> String {
+    let mut hasher = Sha1::new();
+    hasher.update(key.as_bytes());
+    hasher.update(ACCEPT_GUID.as_bytes());
+    BASE64_STANDARD.encode(hasher.finalize())
+}
+
+/// Whether a header holds `token` in its comma separated list, ignoring case
+fn has_token(headers: &HeaderMap, name: header::HeaderName, token: &str) -> bool {
+    headers
+        .get_all(name)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|value| value.trim().eq_ignore_ascii_case(token))
+}
+
+/// Joins all values of a header, which may be split across several lines
+fn joined(headers: &HeaderMap, name: header::HeaderName) -> Option<String> {
+    let values: Vec<&str> = headers
+        .get_all(name)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .collect();
+    (!values.is_empty()).then(|| values.join(", "))
+}
+
+pub fn is_upgrade_request<B>(req: &Request<B>) -> bool {
+    req.method() == Method::GET
+        && has_token(req.headers(), header::CONNECTION, "upgrade")
+        && has_token(req.headers(), header::UPGRADE, "websocket")
+}
+
+/// An accepted upgrade request, whose WebSocket is available once the
+/// `101 Switching Protocols` response was sent
+pub struct PendingWebSocket {
+    on_upgrade: hyper::upgrade::OnUpgrade,
+    deflate: Option<DeflateParams>,
+    config: WebSocketConfig,
+}
+
+impl PendingWebSocket {
+    /// Waits for hyper to hand back the connection, whose type `S` is the
+    /// one the server was serving
+    pub async fn accept<S>(self) -> Result<WebSocket<S>, WebSocketError>
+    where
+        S: AsyncRead + AsyncWrite + hyper::rt::Read + hyper::rt::Write + Unpin + 'static,
+    {
+        let upgraded = self
+            .on_upgrade
+            .await
+            .map_err(|e| WebSocketError::Handshake(e.to_string()))?;
+        let parts = upgraded
+            .downcast::<S>()
+            .map_err(|_| WebSocketError::Handshake("unexpected connection type".to_string()))?;
+        Ok(WebSocket::from_raw(
+            parts.io,
+            Role::Server,
+            self.deflate,
+            self.config,
+            parts.read_buf.to_vec(),
+        ))
+    }
+}
+
+/// Accepts a WebSocket upgrade request, returning the response to send
+pub fn upgrade(
+    req: &mut Request<hyper::body::Incoming>,
+    config: WebSocketConfig,
+) -> Result<(Response<Full<Bytes>>, PendingWebSocket), WebSocketError> {
+    if !is_upgrade_request(req) {
+        return Err(WebSocketError::Handshake(
+            "not an upgrade request".to_string(),
+        ));
+    }
+    if req
+        .headers()
+        .get(header::SEC_WEBSOCKET_VERSION)
+        .map(|v| v.as_bytes())
+        != Some(b"13")
+    {
+        return Err(WebSocketError::Handshake("unsupported version".to_string()));
+    }
+    let key = req
+        .headers()
+        .get(header::SEC_WEBSOCKET_KEY)
+        .and_then(|key| key.to_str().ok())
+        .filter(|key| BASE64_STANDARD.decode(key).is_ok_and(|key| key.len() == 16))
+        .ok_or_else(|| WebSocketError::Handshake("invalid Sec-WebSocket-Key".to_string()))?;
+
+    let deflate = joined(req.headers(), header::SEC_WEBSOCKET_EXTENSIONS)
+        .filter(|_| config.compression)
+        .and_then(|offers| DeflateParams::accept_offer(&offers));
+
+    let mut response = Response::new(Full::default());
+    *response.status_mut() = StatusCode::SWITCHING_PROTOCOLS;
+    let headers = response.headers_mut();
+    headers.insert(
+        header::UPGRADE,
+        header::HeaderValue::from_static("websocket"),
+    );
+    headers.insert(
+        header::CONNECTION,
+        header::HeaderValue::from_static("Upgrade"),
+    );
+    // Both values are built from base64 and fixed tokens, which are valid header values
+    headers.insert(
+        header::SEC_WEBSOCKET_ACCEPT,
+        header::HeaderValue::from_str(&accept_key(key)).unwrap(),
+    );
+    if let Some(params) = &deflate {
+        headers.insert(
+            header::SEC_WEBSOCKET_EXTENSIONS,
+            header::HeaderValue::from_str(&params.response()).unwrap(),
+        );
+    }
+
+    let pending = PendingWebSocket {
+        on_upgrade: hyper::upgrade::on(req),
+        deflate,
+        config,
+    };
+    Ok((response, pending))
+}
+
+/// Performs the client side of the handshake on an open connection.
+/// `headers` are added to the request, e.g. for authorization.
+pub async fn connect<S>(
+    mut stream: S,
+    host: &str,
+    path_and_query: &str,
+    headers: &[(String, String)],
+    config: WebSocketConfig,
+) -> Result<WebSocket<S>, WebSocketError>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    // The key only proves that the server understood the handshake, it is not a secret
+    let mut rng = StdRng::seed_from_u64(unsafe { ffi::env_now() });
+    let key = BASE64_STANDARD.encode(rng.random::<[u8; 16]>());
+
+    let mut request = format!(
+        "GET {} HTTP/1.1\r\nHost: {}\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n\
+         Sec-WebSocket-Key: {}\r\nSec-WebSocket-Version: 13\r\n",
+        path_and_query, host, key
+    );
+    if config.compression {
+        request.push_str(&format!("Sec-WebSocket-Extensions: {}\r\n", CLIENT_OFFER));
+    }
+    for (name, value) in headers {
+        request.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    request.push_str("\r\n");
+    stream.write_all(request.as_bytes()).await?;
+    stream.flush().await?;
+
+    // Read the response head, bytes after it already belong to the first frames
+    let mut buf = Vec::new();
+    let head_len = loop {
+        if let Some(pos) = buf.windows(4).position(|window| window == b"\r\n\r\n") {
+            break pos + 4;
+        }
+        if buf.len() > MAX_RESPONSE_HEAD {
+            return Err(WebSocketError::Handshake(
+                "response head too large".to_string(),
+            ));
+        }
+
+        let mut chunk = [0u8; 1024];
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 {
+            return Err(WebSocketError::Handshake("connection closed".to_string()));
+        }
+        buf.extend_from_slice(&chunk[..n]);
+    };
+
+    let mut parsed_headers = [httparse::EMPTY_HEADER; 32];
+    let mut response = httparse::Response::new(&mut parsed_headers);
+    response
+        .parse(&buf[..head_len])
+        .map_err(|e| WebSocketError::Handshake(e.to_string()))?;
+    if response.code != Some(101) {
+        return Err(WebSocketError::Handshake(format!(
+            "server answered {}",
+            response.code.unwrap_or(0)
+        )));
+    }
+
+    let mut header_map = HeaderMap::new();
+    for header in response.headers.iter() {
+        if let (Ok(name), Ok(value)) = (
+            header::HeaderName::from_bytes(header.name.as_bytes()),
+            header::HeaderValue::from_bytes(header.value),
+        ) {
+            header_map.append(name, value);
+        }
+    }
+    if !has_token(&header_map, header::UPGRADE, "websocket")
+        || !has_token(&header_map, header::CONNECTION, "upgrade")
+    {
+        return Err(WebSocketError::Handshake(
+            "missing upgrade headers".to_string(),
+        ));
+    }
+    let accept = header_map.get(header::SEC_WEBSOCKET_ACCEPT);
+    if accept.map(|v| v.as_bytes()) != Some(accept_key(&key).as_bytes()) {
+        return Err(WebSocketError::Handshake(
+            "invalid Sec-WebSocket-Accept".to_string(),
+        ));
+    }
+
+    let deflate = match joined(&header_map, header::SEC_WEBSOCKET_EXTENSIONS) {
+        Some(_) if !config.compression => {
+            return Err(WebSocketError::Handshake(
+                "unexpected extension".to_string(),
+            ));
+        }
+        Some(extensions) => Some(DeflateParams::parse_response(&extensions)?),
+        None => None,
+    };
+
+    Ok(WebSocket::from_raw(
+        stream,
+        Role::Client,
+        deflate,
+        config,
+        buf[head_len..].to_vec(),
+    ))
+}
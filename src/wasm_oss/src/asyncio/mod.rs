@@ -3,6 +3,8 @@ pub mod http;
 pub mod keyboard;
 pub mod net;
 pub mod sleep;
+pub mod sntp;
+pub mod tftp;
 
 pub async fn sleep_ms(duration_ms: u64) {
     let sleep = sleep::Sleep::new(duration_ms);
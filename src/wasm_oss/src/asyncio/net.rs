@@ -1,15 +1,85 @@
+use crate::asyncio::http::stream::ReadUninit;
 use crate::errors::lwip_error::LwipError;
 use crate::ffi;
-use crate::utils::ip_addr_to_u32;
-use bytes::BufMut;
+use crate::utils::{ip_addr_to_u32, u32_to_ip_addr};
+use futures::future::poll_fn;
 use futures::{AsyncRead, AsyncWrite};
 use log::{error, info};
 use std::cell::RefCell;
 use std::future::Future;
+use std::io::IoSlice;
+use std::mem::MaybeUninit;
+use std::net::{Ipv4Addr, Shutdown, SocketAddrV4};
 use std::pin::Pin;
 use std::rc::Rc;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
 use std::task::{Context, Poll};
 
+// region: Stats
+
+static BYTES_RECEIVED: AtomicU64 = AtomicU64::new(0);
+static BYTES_SENT: AtomicU64 = AtomicU64::new(0);
+static OPEN_SOCKETS: AtomicU32 = AtomicU32::new(0);
+static CONNECTIONS_ACCEPTED: AtomicU64 = AtomicU64::new(0);
+static ACCEPT_FAILURES: AtomicU64 = AtomicU64::new(0);
+static CONNECTIONS_ESTABLISHED: AtomicU64 = AtomicU64::new(0);
+static CONNECT_FAILURES: AtomicU64 = AtomicU64::new(0);
+
+/// Error occurrences, indexed by the negated lwIP error code
+static ERRORS: [AtomicU64; 17] = [const { AtomicU64::new(0) }; 17];
+
+/// Counters of the network stack since startup
+#[derive(Debug, Clone, Default)]
+pub struct NetStats {
+    pub bytes_received: u64,
+    pub bytes_sent: u64,
+    /// Sockets currently open through this module
+    pub open_sockets: u32,
+    pub connections_accepted: u64,
+    pub accept_failures: u64,
+    pub connections_established: u64,
+    pub connect_failures: u64,
+    /// Occurrences of each error that occurred at least once
+    pub errors: Vec<(LwipError, u64)>,
+    /// Size of the host's socket pool, shared with other users of the stack
+    pub max_sockets: Option<u32>,
+    /// Sockets of the host's pool currently in use
+    pub used_sockets: Option<u32>,
+}
+
+/// Returns a snapshot of the network counters and the host's socket pool usage
+pub fn stats() -> NetStats {
+    let errors = ERRORS
+        .iter()
+        .enumerate()
+        .map(|(index, count)| (index, count.load(Ordering::Relaxed)))
+        .filter(|(index, count)| *index > 0 && *count > 0)
+        .map(|(index, count)| (LwipError::from_code(-(index as i32)), count))
+        .collect();
+
+    NetStats {
+        bytes_received: BYTES_RECEIVED.load(Ordering::Relaxed),
+        bytes_sent: BYTES_SENT.load(Ordering::Relaxed),
+        open_sockets: OPEN_SOCKETS.load(Ordering::Relaxed),
+        connections_accepted: CONNECTIONS_ACCEPTED.load(Ordering::Relaxed),
+        accept_failures: ACCEPT_FAILURES.load(Ordering::Relaxed),
+        connections_established: CONNECTIONS_ESTABLISHED.load(Ordering::Relaxed),
+        connect_failures: CONNECT_FAILURES.load(Ordering::Relaxed),
+        errors,
+        max_sockets: u32::try_from(unsafe { ffi::env_max_sockets() }).ok(),
+        used_sockets: u32::try_from(unsafe { ffi::env_used_sockets() }).ok(),
+    }
+}
+
+/// Converts an error code returned by the host, counting its occurrence
+fn net_error(code: i32) -> LwipError {
+    let error = LwipError::from_code(code);
+    ERRORS[(-error.to_code()) as usize].fetch_add(1, Ordering::Relaxed);
+    error
+}
+
+// endregion: Stats
+
 // region: Socket
 struct SocketInner {
     socket: i32,
@@ -18,9 +88,10 @@ struct SocketInner {
 impl Drop for SocketInner {
     fn drop(&mut self) {
         info!("Closing socket: {}", self.socket);
+        OPEN_SOCKETS.fetch_sub(1, Ordering::Relaxed);
         let result = unsafe { ffi::env_net_socket_free(self.socket) };
         if result != LwipError::Ok.to_code() {
-            error!("Failed to close socket: {}", LwipError::from_code(result));
+            error!("Failed to close socket: {}", net_error(result));
         }
     }
 }
@@ -34,40 +105,50 @@ unsafe impl Send for Socket {}
 unsafe impl Sync for Socket {}
 
 impl Socket {
+    /// Takes ownership of a socket handle returned by the host
+    fn from_handle(socket: i32) -> Self {
+        OPEN_SOCKETS.fetch_add(1, Ordering::Relaxed);
+        Socket {
+            inner: Rc::new(RefCell::new(SocketInner { socket })),
+        }
+    }
+
     fn create_tcp() -> Result<Self, LwipError> {
         let socket = unsafe { ffi::env_net_socket_new_tcp() };
         if socket < 0 {
-            return Err(LwipError::from_code(socket));
+            return Err(net_error(socket));
         }
         info!("Creating TCP socket: {}", socket);
-        Ok(Socket {
-            inner: Rc::new(RefCell::new(SocketInner { socket })),
-        })
+        Ok(Socket::from_handle(socket))
     }
 
     fn create_udp() -> Result<Self, LwipError> {
         let socket = unsafe { ffi::env_net_socket_new_udp() };
         if socket < 0 {
-            return Err(LwipError::from_code(socket));
+            return Err(net_error(socket));
         }
-        Ok(Socket {
-            inner: Rc::new(RefCell::new(SocketInner { socket })),
-        })
+        Ok(Socket::from_handle(socket))
     }
-}
 
-impl AsyncRead for Socket {
-    fn poll_read(
-        self: std::pin::Pin<&mut Self>,
-        cx: &mut std::task::Context<'_>,
-        mut buf: &mut [u8],
-    ) -> std::task::Poll<std::io::Result<usize>> {
+    fn poll_recv(&self, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<Result<usize, LwipError>> {
+        // SAFETY: the host only writes initialized bytes into the buffer
+        let buf = unsafe { &mut *(buf as *mut [u8] as *mut [MaybeUninit<u8>]) };
+        self.poll_recv_uninit(cx, buf)
+    }
+
+    /// Reads into memory that may be uninitialized, the host writes the bytes
+    /// read without looking at the rest
+    fn poll_recv_uninit(
+        &self,
+        cx: &mut Context<'_>,
+        buf: &mut [MaybeUninit<u8>],
+    ) -> Poll<Result<usize, LwipError>> {
         unsafe { ffi::env_net_rx() };
         let read_bytes = unsafe {
             ffi::env_net_socket_read(
                 self.inner.borrow().socket,
-                buf.as_mut_ptr(),
-                buf.remaining_mut() as u32,
+                buf.as_mut_ptr() as *const u8,
+                buf.len() as u32,
             )
         };
 
@@ -82,32 +163,153 @@ impl AsyncRead for Socket {
         }
 
         if read_bytes < 0 {
-            return Poll::Ready(Err(LwipError::from_code(read_bytes).into()));
+            return Poll::Ready(Err(net_error(read_bytes)));
         }
 
-        unsafe { buf.advance_mut(read_bytes as usize) };
+        BYTES_RECEIVED.fetch_add(read_bytes as u64, Ordering::Relaxed);
         Poll::Ready(Ok(read_bytes as usize))
     }
-}
 
-impl AsyncWrite for Socket {
-    fn poll_write(
-        self: std::pin::Pin<&mut Self>,
-        _: &mut std::task::Context<'_>,
+    /// Queues as much of `buf` as fits in the send buffer, returning `Pending`
+    /// while the buffer is full.
+    fn poll_send(&self, cx: &mut Context<'_>, buf: &[u8]) -> Poll<Result<usize, LwipError>> {
+        if buf.is_empty() {
+            return Poll::Ready(Ok(0));
+        }
+
+        unsafe { ffi::env_net_rx() };
+        let socket = self.inner.borrow().socket;
+        let available = unsafe { ffi::env_net_socket_sndbuf(socket) };
+        if available < 0 {
+            return Poll::Ready(Err(net_error(available)));
+        }
+
+        if available == 0 {
+            cx.waker().wake_by_ref();
+            return Poll::Pending;
+        }
+
+        let len = buf.len().min(available as usize);
+        let write_bytes = unsafe { ffi::env_net_socket_write(socket, buf.as_ptr(), len as u32) };
+
+        if write_bytes == LwipError::WouldBlock.to_code() || write_bytes == 0 {
+            cx.waker().wake_by_ref();
+            return Poll::Pending;
+        }
+
+        if write_bytes < 0 {
+            return Poll::Ready(Err(net_error(write_bytes)));
+        }
+
+        BYTES_SENT.fetch_add(write_bytes as u64, Ordering::Relaxed);
+        Poll::Ready(Ok(write_bytes as usize))
+    }
+
+    /// Queues the buffers in order, stopping at the first one that does not fit.
+    fn poll_send_vectored(
+        &self,
+        cx: &mut Context<'_>,
+        bufs: &[IoSlice<'_>],
+    ) -> Poll<Result<usize, LwipError>> {
+        let mut total = 0;
+        for buf in bufs.iter().filter(|buf| !buf.is_empty()) {
+            match self.poll_send(cx, buf) {
+                Poll::Ready(Ok(n)) => {
+                    total += n;
+                    if n < buf.len() {
+                        break;
+                    }
+                }
+                // Report what was already queued, the error or wakeup repeats on the next call
+                Poll::Ready(Err(_)) | Poll::Pending if total > 0 => break,
+                other => return other,
+            }
+        }
+
+        Poll::Ready(Ok(total))
+    }
+
+    /// Sends `buf` as a single datagram, returning `Pending` while no buffer is available.
+    fn poll_send_datagram(
+        &self,
+        cx: &mut Context<'_>,
         buf: &[u8],
-    ) -> std::task::Poll<std::io::Result<usize>> {
+    ) -> Poll<Result<usize, LwipError>> {
         unsafe { ffi::env_net_rx() };
         let write_bytes = unsafe {
             ffi::env_net_socket_write(self.inner.borrow().socket, buf.as_ptr(), buf.len() as u32)
         };
 
+        if write_bytes == LwipError::WouldBlock.to_code() {
+            cx.waker().wake_by_ref();
+            return Poll::Pending;
+        }
+
         if write_bytes < 0 {
-            return Poll::Ready(Err(LwipError::from_code(write_bytes).into()));
+            return Poll::Ready(Err(net_error(write_bytes)));
         }
 
+        BYTES_SENT.fetch_add(buf.len() as u64, Ordering::Relaxed);
         Poll::Ready(Ok(buf.len()))
     }
 
+    fn addr(
+        &self,
+        get_addr: unsafe extern "C" fn(i32, *mut u32, *mut u32) -> i32,
+    ) -> Result<SocketAddrV4, LwipError> {
+        let mut addr = 0;
+        let mut port = 0;
+        let result = unsafe { get_addr(self.inner.borrow().socket, &mut addr, &mut port) };
+        if result != LwipError::Ok.to_code() {
+            return Err(net_error(result));
+        }
+
+        Ok(SocketAddrV4::new(u32_to_ip_addr(addr), port as u16))
+    }
+
+    fn shutdown(&self, how: Shutdown) -> Result<(), LwipError> {
+        let how = match how {
+            Shutdown::Read => 0,
+            Shutdown::Write => 1,
+            Shutdown::Both => 2,
+        };
+        let result = unsafe { ffi::env_net_socket_shutdown(self.inner.borrow().socket, how) };
+        // Shutting down a connection the peer already closed is not an error
+        if result != LwipError::Ok.to_code() && result != LwipError::NotConnected.to_code() {
+            return Err(net_error(result));
+        }
+
+        Ok(())
+    }
+}
+
+impl AsyncRead for Socket {
+    fn poll_read(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+        buf: &mut [u8],
+    ) -> std::task::Poll<std::io::Result<usize>> {
+        self.poll_recv(cx, buf).map_err(Into::into)
+    }
+}
+
+impl AsyncWrite for Socket {
+    fn poll_write(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+        buf: &[u8],
+    ) -> std::task::Poll<std::io::Result<usize>> {
+        self.poll_send(cx, buf).map_err(Into::into)
+    }
+
+    fn poll_write_vectored(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+        bufs: &[IoSlice<'_>],
+    ) -> std::task::Poll<std::io::Result<usize>> {
+        self.poll_send_vectored(cx, bufs).map_err(Into::into)
+    }
+
     fn poll_flush(
         self: std::pin::Pin<&mut Self>,
         cx: &mut std::task::Context<'_>,
@@ -124,7 +326,7 @@ impl AsyncWrite for Socket {
             return Poll::Ready(Ok(()));
         }
 
-        Poll::Ready(Err(LwipError::from_code(err).into()))
+        Poll::Ready(Err(net_error(err).into()))
     }
 
     fn poll_close(
@@ -139,25 +341,54 @@ impl AsyncWrite for Socket {
 // endregion: Socket
 
 // region: TCP
+
+/// TCP keepalive probing, used to detect peers that disappeared without
+/// closing the connection
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TcpKeepalive {
+    /// Idle time before the first probe is sent
+    pub idle_ms: u32,
+    /// Time between unanswered probes
+    pub interval_ms: u32,
+    /// Number of unanswered probes before the connection is dropped
+    pub count: u32,
+}
+
+impl Default for TcpKeepalive {
+    fn default() -> Self {
+        Self {
+            idle_ms: 60_000,
+            interval_ms: 10_000,
+            count: 6,
+        }
+    }
+}
+
 pub struct TcpListener {
     socket: Socket,
 }
 
 impl TcpListener {
-    pub fn bind(addr_str: &str, port: u16) -> Result<Self, LwipError> {
+    pub fn bind(addr: Ipv4Addr, port: u16) -> Result<Self, LwipError> {
+        Self::bind_with_backlog(addr, port, 8)
+    }
+
+    /// Binds a listener that queues up to `backlog` connections not yet accepted.
+    pub fn bind_with_backlog(addr: Ipv4Addr, port: u16, backlog: u8) -> Result<Self, LwipError> {
         let socket = Socket::create_tcp()?;
-        let addr = ip_addr_to_u32(addr_str)?;
+        let addr = ip_addr_to_u32(addr);
         let result =
             unsafe { ffi::env_net_socket_bind(socket.inner.borrow().socket, addr, port.into()) };
         if result != LwipError::Ok.to_code() {
             info!("Failed to bind TCP listener: {}", result);
-            return Err(LwipError::from_code(result));
+            return Err(net_error(result));
         }
 
-        let result = unsafe { ffi::env_net_socket_listen(socket.inner.borrow().socket, 8) };
+        let result =
+            unsafe { ffi::env_net_socket_listen(socket.inner.borrow().socket, backlog.into()) };
         if result != LwipError::Ok.to_code() {
             info!("Failed to listen on TCP listener: {}", result);
-            return Err(LwipError::from_code(result));
+            return Err(net_error(result));
         }
 
         Ok(Self { socket })
@@ -182,20 +413,19 @@ impl TcpListener {
                 }
 
                 if result < 0 {
-                    return Poll::Ready(Err(LwipError::from_code(result)));
+                    return Poll::Ready(Err(net_error(result)));
                 }
 
                 Poll::Ready(Ok(TcpStream {
-                    socket: Socket {
-                        inner: Rc::new(RefCell::new(SocketInner { socket: result })),
-                    },
+                    socket: Socket::from_handle(result),
                 }))
             }
         }
 
         let result = unsafe { ffi::env_net_socket_accept(self.socket.inner.borrow().socket) };
         if result != LwipError::Ok.to_code() {
-            return Err(LwipError::from_code(result));
+            ACCEPT_FAILURES.fetch_add(1, Ordering::Relaxed);
+            return Err(net_error(result));
         }
 
         let accept_result = TcpAccept {
@@ -204,9 +434,11 @@ impl TcpListener {
         .await;
 
         if accept_result.is_err() {
+            ACCEPT_FAILURES.fetch_add(1, Ordering::Relaxed);
             return Err(accept_result.err().unwrap());
         }
 
+        CONNECTIONS_ACCEPTED.fetch_add(1, Ordering::Relaxed);
         Ok(accept_result.unwrap())
     }
 }
@@ -217,7 +449,7 @@ pub struct TcpStream {
 }
 
 impl TcpStream {
-    pub async fn connect(ip: &str, port: u16) -> Result<Self, LwipError> {
+    pub async fn connect(ip: Ipv4Addr, port: u16) -> Result<Self, LwipError> {
         struct TcpConnection {
             socket: i32,
         }
@@ -240,24 +472,26 @@ impl TcpStream {
                 }
 
                 log::error!("Failed to connect to socket poll: {}", err);
-                Poll::Ready(Err(LwipError::from_code(err)))
+                Poll::Ready(Err(net_error(err)))
             }
         }
 
         let socket = Socket::create_tcp();
 
         if socket.is_err() {
+            CONNECT_FAILURES.fetch_add(1, Ordering::Relaxed);
             return Err(socket.err().unwrap());
         }
 
         let socket = socket.unwrap();
-        let addr = ip_addr_to_u32(ip)?;
+        let addr = ip_addr_to_u32(ip);
         let result: i32 =
             unsafe { ffi::env_net_socket_connect(socket.inner.borrow().socket, addr, port.into()) };
 
         if result != LwipError::Ok.to_code() {
             log::error!("Failed to connect to socket: {}", result);
-            return Err(LwipError::from_code(result));
+            CONNECT_FAILURES.fetch_add(1, Ordering::Relaxed);
+            return Err(net_error(result));
         }
 
         let result = TcpConnection {
@@ -266,11 +500,77 @@ impl TcpStream {
         .await;
 
         if result.is_err() {
+            CONNECT_FAILURES.fetch_add(1, Ordering::Relaxed);
             return Err(result.err().unwrap());
         }
 
+        CONNECTIONS_ESTABLISHED.fetch_add(1, Ordering::Relaxed);
+
         Ok(Self { socket })
     }
+
+    /// Returns the address of the remote end of the connection.
+    pub fn peer_addr(&self) -> Result<SocketAddrV4, LwipError> {
+        self.socket.addr(ffi::env_net_socket_peer_addr)
+    }
+
+    /// Returns the local address the connection is bound to.
+    pub fn local_addr(&self) -> Result<SocketAddrV4, LwipError> {
+        self.socket.addr(ffi::env_net_socket_local_addr)
+    }
+
+    /// Enables or disables Nagle's algorithm.
+    pub fn set_nodelay(&self, nodelay: bool) -> Result<(), LwipError> {
+        let result = unsafe {
+            ffi::env_net_socket_set_nodelay(self.socket.inner.borrow().socket, nodelay.into())
+        };
+        if result != LwipError::Ok.to_code() {
+            return Err(net_error(result));
+        }
+
+        Ok(())
+    }
+
+    /// Enables keepalive probing with the given settings, or disables it with `None`.
+    pub fn set_keepalive(&self, keepalive: Option<TcpKeepalive>) -> Result<(), LwipError> {
+        let result = unsafe {
+            match keepalive {
+                Some(keepalive) => ffi::env_net_socket_set_keepalive(
+                    self.socket.inner.borrow().socket,
+                    1,
+                    keepalive.idle_ms,
+                    keepalive.interval_ms,
+                    keepalive.count,
+                ),
+                None => {
+                    ffi::env_net_socket_set_keepalive(self.socket.inner.borrow().socket, 0, 0, 0, 0)
+                }
+            }
+        };
+        if result != LwipError::Ok.to_code() {
+            return Err(net_error(result));
+        }
+
+        Ok(())
+    }
+
+    /// Shuts down the read half, the write half or both halves of the connection.
+    ///
+    /// Shutting down the write half sends a FIN once all queued data has been sent,
+    /// while the peer can keep sending until it closes its end.
+    pub fn shutdown(&self, how: Shutdown) -> Result<(), LwipError> {
+        self.socket.shutdown(how)
+    }
+}
+
+impl ReadUninit for TcpStream {
+    fn poll_read_uninit(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [MaybeUninit<u8>],
+    ) -> Poll<std::io::Result<usize>> {
+        self.socket.poll_recv_uninit(cx, buf).map_err(Into::into)
+    }
 }
 
 impl AsyncRead for TcpStream {
@@ -294,6 +594,15 @@ impl AsyncWrite for TcpStream {
         pinned.poll_write(cx, buf)
     }
 
+    fn poll_write_vectored(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+        bufs: &[IoSlice<'_>],
+    ) -> std::task::Poll<std::io::Result<usize>> {
+        let pinned = std::pin::pin!(self.socket.clone());
+        pinned.poll_write_vectored(cx, bufs)
+    }
+
     fn poll_flush(
         self: std::pin::Pin<&mut Self>,
         cx: &mut std::task::Context<'_>,
@@ -306,38 +615,133 @@ impl AsyncWrite for TcpStream {
         self: std::pin::Pin<&mut Self>,
         cx: &mut std::task::Context<'_>,
     ) -> std::task::Poll<std::io::Result<()>> {
+        // Send any queued data before the FIN
         let pinned = std::pin::pin!(self.socket.clone());
-        pinned.poll_close(cx)
+        match pinned.poll_flush(cx) {
+            Poll::Ready(Ok(())) => (),
+            other => return other,
+        }
+
+        Poll::Ready(self.socket.shutdown(Shutdown::Write).map_err(Into::into))
     }
 }
 // endregion: TCP
 
 // region: UDP
 
-struct UdpSocket {
+pub struct UdpSocket {
     socket: Socket,
 }
 
 impl UdpSocket {
-    pub fn bind(addr_str: &str, port: u16) -> Result<Self, LwipError> {
+    pub fn bind(addr: Ipv4Addr, port: u16) -> Result<Self, LwipError> {
         let socket = Socket::create_udp()?;
-        let addr = ip_addr_to_u32(addr_str)?;
+        let addr = ip_addr_to_u32(addr);
         let result =
             unsafe { ffi::env_net_socket_bind(socket.inner.borrow().socket, addr, port.into()) };
         if result != LwipError::Ok.to_code() {
-            return Err(LwipError::from_code(result));
+            return Err(net_error(result));
         }
 
         Ok(Self { socket })
     }
 
-    pub fn connect(self, addr_str: &str, port: u16) -> Result<(), LwipError> {
-        let addr = ip_addr_to_u32(addr_str)?;
+    pub fn connect(&self, addr: Ipv4Addr, port: u16) -> Result<(), LwipError> {
+        let addr = ip_addr_to_u32(addr);
         let result = unsafe {
             ffi::env_net_socket_connect(self.socket.inner.borrow().socket, addr, port.into())
         };
         if result != LwipError::Ok.to_code() {
-            return Err(LwipError::from_code(result));
+            return Err(net_error(result));
+        }
+
+        Ok(())
+    }
+
+    /// Sends a datagram to the connected peer.
+    pub async fn send(&self, buf: &[u8]) -> Result<usize, LwipError> {
+        poll_fn(|cx| self.socket.poll_send_datagram(cx, buf)).await
+    }
+
+    /// Receives pending data from the socket into `buf`.
+    pub async fn recv(&self, buf: &mut [u8]) -> Result<usize, LwipError> {
+        poll_fn(|cx| self.socket.poll_recv(cx, buf)).await
+    }
+
+    /// Sends a datagram to the given address, regardless of the connected peer.
+    pub fn send_to(&self, buf: &[u8], addr: Ipv4Addr, port: u16) -> Result<usize, LwipError> {
+        let addr = ip_addr_to_u32(addr);
+        unsafe { ffi::env_net_rx() };
+        let result = unsafe {
+            ffi::env_net_socket_sendto(
+                self.socket.inner.borrow().socket,
+                buf.as_ptr(),
+                buf.len() as u32,
+                addr,
+                port.into(),
+            )
+        };
+        if result < 0 {
+            return Err(net_error(result));
+        }
+
+        BYTES_SENT.fetch_add(buf.len() as u64, Ordering::Relaxed);
+        Ok(buf.len())
+    }
+
+    /// Receives a single datagram into `buf`, returning its length and source.
+    ///
+    /// Datagrams larger than `buf` are truncated.
+    pub async fn recv_from(&self, buf: &mut [u8]) -> Result<(usize, Ipv4Addr, u16), LwipError> {
+        poll_fn(|cx| {
+            let mut addr = 0;
+            let mut port = 0;
+            unsafe { ffi::env_net_rx() };
+            let read_bytes = unsafe {
+                ffi::env_net_socket_recvfrom(
+                    self.socket.inner.borrow().socket,
+                    buf.as_mut_ptr(),
+                    buf.len() as u32,
+                    &mut addr,
+                    &mut port,
+                )
+            };
+
+            if read_bytes == LwipError::WouldBlock.to_code() || read_bytes == 0 {
+                cx.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+
+            if read_bytes < 0 {
+                return Poll::Ready(Err(net_error(read_bytes)));
+            }
+
+            BYTES_RECEIVED.fetch_add(read_bytes as u64, Ordering::Relaxed);
+            Poll::Ready(Ok((read_bytes as usize, u32_to_ip_addr(addr), port as u16)))
+        })
+        .await
+    }
+
+    /// Subscribes the socket to a multicast group.
+    pub fn join_multicast(&self, group: Ipv4Addr) -> Result<(), LwipError> {
+        let group = ip_addr_to_u32(group);
+        let result =
+            unsafe { ffi::env_net_socket_join_multicast(self.socket.inner.borrow().socket, group) };
+        if result != LwipError::Ok.to_code() {
+            return Err(net_error(result));
+        }
+
+        Ok(())
+    }
+
+    /// Unsubscribes the socket from a multicast group.
+    pub fn leave_multicast(&self, group: Ipv4Addr) -> Result<(), LwipError> {
+        let group = ip_addr_to_u32(group);
+        let result = unsafe {
+            ffi::env_net_socket_leave_multicast(self.socket.inner.borrow().socket, group)
+        };
+        if result != LwipError::Ok.to_code() {
+            return Err(net_error(result));
         }
 
         Ok(())
@@ -361,8 +765,8 @@ impl AsyncWrite for UdpSocket {
         cx: &mut std::task::Context<'_>,
         buf: &[u8],
     ) -> std::task::Poll<std::io::Result<usize>> {
-        let pinned = std::pin::pin!(self.socket.clone());
-        pinned.poll_write(cx, buf)
+        // Each write is sent as one datagram
+        self.socket.poll_send_datagram(cx, buf).map_err(Into::into)
     }
 
     fn poll_flush(
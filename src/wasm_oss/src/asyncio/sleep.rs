@@ -1,6 +1,7 @@
 use std::future::Future;
 use std::pin::Pin;
 use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
 
 use crate::ffi;
 
@@ -28,3 +29,19 @@ impl Future for Sleep {
         }
     }
 }
+
+impl hyper::rt::Sleep for Sleep {}
+
+/// Timer used by hyper for its timeouts, such as the header read timeout
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HyperTimer;
+
+impl hyper::rt::Timer for HyperTimer {
+    fn sleep(&self, duration: Duration) -> Pin<Box<dyn hyper::rt::Sleep>> {
+        Box::pin(Sleep::new(duration.as_millis() as u64))
+    }
+
+    fn sleep_until(&self, deadline: Instant) -> Pin<Box<dyn hyper::rt::Sleep>> {
+        self.sleep(deadline.saturating_duration_since(Instant::now()))
+    }
+}
@@ -0,0 +1,110 @@
+use crate::asyncio::net::UdpSocket;
+use crate::asyncio::sleep_ms;
+use crate::errors::{protocol_error::ProtocolError, sntp_error::SntpError};
+use crate::ffi;
+use futures::future::{select, Either};
+use std::net::Ipv4Addr;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+const NTP_PORT: u16 = 123;
+const PACKET_SIZE: usize = 48;
+const QUERY_TIMEOUT_MS: u64 = 2000;
+
+/// Seconds between the NTP epoch (1900) and the Unix epoch (1970)
+const NTP_UNIX_OFFSET_SECS: i64 = 2_208_988_800;
+
+const MODE_CLIENT: u8 = 3;
+const MODE_SERVER: u8 = 4;
+const VERSION: u8 = 4;
+/// Leap indicator value of a server that is not synchronized itself
+const LEAP_ALARM: u8 = 3;
+
+static NEXT_COOKIE: AtomicU64 = AtomicU64::new(0);
+
+/// A single measurement of a server's clock against the local monotonic clock
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sample {
+    pub server: Ipv4Addr,
+    /// Unix time in ms minus the local monotonic time in ms
+    pub offset_ms: i64,
+    /// Round-trip delay, excluding the time spent in the server
+    pub delay_ms: u64,
+    pub stratum: u8,
+}
+
+/// Queries `server` once, as described by RFC 4330
+pub async fn query(server: Ipv4Addr) -> Result<Sample, SntpError> {
+    let socket = UdpSocket::bind(Ipv4Addr::UNSPECIFIED, 0)?;
+    socket.connect(server, NTP_PORT)?;
+
+    // The transmit timestamp is echoed back as the origin timestamp, so a
+    // unique value identifies the response without revealing the local time
+    let cookie = (unsafe { ffi::env_now() } << 20) ^ NEXT_COOKIE.fetch_add(1, Ordering::Relaxed);
+    let mut request = [0u8; PACKET_SIZE];
+    request[0] = (VERSION << 3) | MODE_CLIENT;
+    request[40..48].copy_from_slice(&cookie.to_be_bytes());
+
+    let sent_at = unsafe { ffi::env_now() } as i64;
+    socket.send(&request).await?;
+
+    let receive = Box::pin(async {
+        let mut buf = [0u8; PACKET_SIZE];
+        loop {
+            let len = socket.recv(&mut buf).await?;
+            if len >= PACKET_SIZE && buf[24..32] == cookie.to_be_bytes() {
+                return Ok::<_, SntpError>((buf, unsafe { ffi::env_now() } as i64));
+            }
+        }
+    });
+
+    let (response, received_at) = match select(receive, Box::pin(sleep_ms(QUERY_TIMEOUT_MS))).await
+    {
+        Either::Left((response, _)) => response?,
+        Either::Right((_, _)) => return Err(SntpError::Timeout),
+    };
+
+    let leap = response[0] >> 6;
+    let mode = response[0] & 0x07;
+    let stratum = response[1];
+    if mode != MODE_SERVER {
+        return Err(SntpError::Protocol(ProtocolError::Malformed(
+            "not a server reply",
+        )));
+    }
+    // Stratum 0 is a kiss-of-death packet telling the client to back off
+    if leap == LEAP_ALARM || !(1..=15).contains(&stratum) {
+        return Err(SntpError::Unsynchronized);
+    }
+
+    let server_received = ntp_to_unix_ms(&response[32..40]);
+    let server_sent = ntp_to_unix_ms(&response[40..48]);
+    if server_sent == 0 {
+        return Err(SntpError::Protocol(ProtocolError::Malformed(
+            "missing transmit timestamp",
+        )));
+    }
+
+    Ok(Sample {
+        server,
+        offset_ms: ((server_received - sent_at) + (server_sent - received_at)) / 2,
+        delay_ms: ((received_at - sent_at) - (server_sent - server_received)).max(0) as u64,
+        stratum,
+    })
+}
+
+/// Converts a 64-bit NTP timestamp into ms since the Unix epoch
+fn ntp_to_unix_ms(timestamp: &[u8]) -> i64 {
+    let seconds = u32::from_be_bytes([timestamp[0], timestamp[1], timestamp[2], timestamp[3]]);
+    let fraction = u32::from_be_bytes([timestamp[4], timestamp[5], timestamp[6], timestamp[7]]);
+    if seconds == 0 && fraction == 0 {
+        return 0;
+    }
+
+    // Timestamps with the top bit cleared are in era 1, starting in 2036 (RFC 4330, section 3)
+    let mut seconds = seconds as i64;
+    if seconds & 0x8000_0000 == 0 {
+        seconds += 1 << 32;
+    }
+
+    (seconds - NTP_UNIX_OFFSET_SECS) * 1000 + ((fraction as i64 * 1000) >> 32)
+}
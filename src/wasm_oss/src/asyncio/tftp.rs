@@ -0,0 +1,348 @@
+use crate::asyncio::net::UdpSocket;
+use crate::asyncio::sleep_ms;
+use crate::errors::tftp_error::TftpError;
+use bytes::Bytes;
+use futures::future::{select, Either};
+use std::net::Ipv4Addr;
+
+pub const TFTP_PORT: u16 = 69;
+
+const OP_RRQ: u16 = 1;
+const OP_DATA: u16 = 3;
+const OP_ACK: u16 = 4;
+const OP_ERROR: u16 = 5;
+const OP_OACK: u16 = 6;
+
+const ERROR_UNKNOWN_TID: u16 = 5;
+/// Sent by servers refusing the requested options (RFC 2347)
+const ERROR_OPTIONS: u16 = 8;
+
+/// Block size used when the server does not support the blksize option
+const DEFAULT_BLOCK_SIZE: u16 = 512;
+/// Bounds of the blksize option (RFC 2348)
+const MIN_BLOCK_SIZE: u16 = 8;
+const MAX_BLOCK_SIZE: u16 = 65464;
+
+#[derive(Debug, Clone)]
+pub struct TftpOptions {
+    /// Requested block size, the default fills a single Ethernet frame
+    pub block_size: u16,
+    /// Requested number of blocks per acknowledgement (RFC 7440)
+    pub window_size: u16,
+    /// Time to wait for a packet before retransmitting
+    pub timeout_ms: u64,
+    /// Retransmissions before giving up
+    pub retries: u32,
+}
+
+impl Default for TftpOptions {
+    fn default() -> Self {
+        Self {
+            block_size: 1468,
+            window_size: 8,
+            timeout_ms: 1000,
+            retries: 5,
+        }
+    }
+}
+
+/// A TFTP read request (RFC 1350) in progress, yielding the file block by block.
+///
+/// The blksize, tsize and windowsize options are requested, falling back to
+/// plain 512 byte blocks for servers that do not support them.
+pub struct TftpTransfer {
+    socket: UdpSocket,
+    server: Ipv4Addr,
+    /// Port the server answers from, which identifies the transfer
+    port: Option<u16>,
+    options: TftpOptions,
+    block_size: usize,
+    window_size: u16,
+    transfer_size: Option<u64>,
+    /// Number of the last block received in order
+    block: u16,
+    /// Blocks received since the last acknowledgement
+    unacked: u16,
+    /// Whether the last block received in order was already re-acknowledged
+    resynced: bool,
+    received: u64,
+    done: bool,
+    /// Packet retransmitted when the server does not answer in time
+    last_sent: Vec<u8>,
+    /// Length of a data packet in `buf` that is yet to be handled
+    pending: Option<usize>,
+    buf: Vec<u8>,
+}
+
+impl TftpTransfer {
+    /// Requests `filename` from `server` and negotiates the transfer options
+    pub async fn open(
+        server: Ipv4Addr,
+        filename: &str,
+        mut options: TftpOptions,
+    ) -> Result<Self, TftpError> {
+        options.block_size = options.block_size.clamp(MIN_BLOCK_SIZE, MAX_BLOCK_SIZE);
+        options.window_size = options.window_size.max(1);
+
+        let mut transfer = Self {
+            socket: UdpSocket::bind(Ipv4Addr::UNSPECIFIED, 0)?,
+            server,
+            port: None,
+            block_size: DEFAULT_BLOCK_SIZE as usize,
+            window_size: 1,
+            transfer_size: None,
+            block: 0,
+            unacked: 0,
+            resynced: false,
+            received: 0,
+            done: false,
+            last_sent: read_request(filename, Some(&options)),
+            pending: None,
+            buf: vec![0; options.block_size as usize + 4],
+            options,
+        };
+
+        transfer.send_last()?;
+        let mut with_options = true;
+        loop {
+            let len = transfer.receive().await?;
+            match opcode(&transfer.buf[..len]) {
+                OP_OACK => {
+                    if let Err(e) = transfer.accept_options(len) {
+                        transfer.send_error(ERROR_OPTIONS, "Unexpected options");
+                        return Err(e);
+                    }
+                    transfer.send_ack(0)?;
+                    return Ok(transfer);
+                }
+                // The server ignored the options, so this is the first block
+                OP_DATA => {
+                    transfer.pending = Some(len);
+                    return Ok(transfer);
+                }
+                OP_ERROR if with_options && error_code(&transfer.buf[..len]) == ERROR_OPTIONS => {
+                    with_options = false;
+                    transfer.port = None;
+                    transfer.last_sent = read_request(filename, None);
+                    transfer.send_last()?;
+                }
+                OP_ERROR => return Err(remote_error(&transfer.buf[..len])),
+                op => return Err(TftpError::Protocol(format!("Unexpected opcode {}", op))),
+            }
+        }
+    }
+
+    /// Size of the file as announced by the server
+    pub fn transfer_size(&self) -> Option<u64> {
+        self.transfer_size
+    }
+
+    pub fn block_size(&self) -> usize {
+        self.block_size
+    }
+
+    pub fn window_size(&self) -> u16 {
+        self.window_size
+    }
+
+    /// Returns the next block of the file, or `None` once it is complete
+    pub async fn next_block(&mut self) -> Result<Option<Bytes>, TftpError> {
+        while !self.done {
+            let len = match self.pending.take() {
+                Some(len) => len,
+                None => self.receive().await?,
+            };
+
+            let packet = &self.buf[..len];
+            match opcode(packet) {
+                OP_DATA => {
+                    let block = u16::from_be_bytes([packet[2], packet[3]]);
+                    if block != self.block.wrapping_add(1) {
+                        // Acknowledge the last block received in order once, so the
+                        // server resends the window from there (RFC 7440, section 4)
+                        if !self.resynced {
+                            self.resynced = true;
+                            self.unacked = 0;
+                            self.send_ack(self.block)?;
+                        }
+                        continue;
+                    }
+
+                    let data = Bytes::copy_from_slice(&packet[4..]);
+                    if data.len() > self.block_size {
+                        return Err(TftpError::Protocol("Block exceeds block size".to_string()));
+                    }
+
+                    self.block = block;
+                    self.resynced = false;
+                    self.received += data.len() as u64;
+                    if matches!(self.transfer_size, Some(size) if self.received > size) {
+                        return Err(TftpError::Protocol(
+                            "File exceeds announced size".to_string(),
+                        ));
+                    }
+
+                    // A short block ends the transfer. The final acknowledgement is
+                    // not repeated if lost, the server gives up on its own.
+                    self.done = data.len() < self.block_size;
+                    self.unacked += 1;
+                    if self.done || self.unacked >= self.window_size {
+                        self.unacked = 0;
+                        self.send_ack(block)?;
+                    }
+
+                    if !data.is_empty() {
+                        return Ok(Some(data));
+                    }
+                }
+                // Our acknowledgement of the options got lost
+                OP_OACK if self.block == 0 => self.send_last()?,
+                OP_ERROR => return Err(remote_error(packet)),
+                op => return Err(TftpError::Protocol(format!("Unexpected opcode {}", op))),
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn accept_options(&mut self, len: usize) -> Result<(), TftpError> {
+        let mut fields = self.buf[2..len].split(|b| *b == 0);
+        while let Some(name) = fields.next().filter(|name| !name.is_empty()) {
+            let name = String::from_utf8_lossy(name).to_ascii_lowercase();
+            let value = fields
+                .next()
+                .and_then(|value| std::str::from_utf8(value).ok())
+                .and_then(|value| value.parse::<u64>().ok())
+                .ok_or_else(|| TftpError::Protocol(format!("Invalid value for {}", name)))?;
+
+            match name.as_str() {
+                "blksize"
+                    if (MIN_BLOCK_SIZE as u64..=self.options.block_size as u64)
+                        .contains(&value) =>
+                {
+                    self.block_size = value as usize
+                }
+                "windowsize" if (1..=self.options.window_size as u64).contains(&value) => {
+                    self.window_size = value as u16
+                }
+                "tsize" => self.transfer_size = Some(value),
+                _ => {
+                    return Err(TftpError::Protocol(format!(
+                        "Unexpected option {}={}",
+                        name, value
+                    )))
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Waits for a packet from the server, retransmitting the last packet sent
+    /// on timeouts, and returns its length
+    async fn receive(&mut self) -> Result<usize, TftpError> {
+        let mut retries = 0;
+        loop {
+            let received = match select(
+                Box::pin(self.socket.recv_from(&mut self.buf)),
+                Box::pin(sleep_ms(self.options.timeout_ms)),
+            )
+            .await
+            {
+                Either::Left((received, _)) => Some(received),
+                Either::Right(_) => None,
+            };
+
+            let Some(received) = received else {
+                retries += 1;
+                if retries > self.options.retries {
+                    return Err(TftpError::Timeout);
+                }
+                self.send_last()?;
+                continue;
+            };
+
+            let (len, addr, port) = received?;
+            // Stray and truncated packets are ignored instead of ending the transfer
+            if addr != self.server || len < 4 {
+                continue;
+            }
+            match self.port {
+                None => self.port = Some(port),
+                // Packets from other ports belong to another transfer (RFC 1350, section 4)
+                Some(expected) if expected != port => {
+                    let packet = error_packet(ERROR_UNKNOWN_TID, "Unknown transfer ID");
+                    let _ = self.socket.send_to(&packet, addr, port);
+                    continue;
+                }
+                Some(_) => (),
+            }
+
+            return Ok(len);
+        }
+    }
+
+    fn send_last(&self) -> Result<(), TftpError> {
+        self.socket
+            .send_to(&self.last_sent, self.server, self.port.unwrap_or(TFTP_PORT))?;
+        Ok(())
+    }
+
+    fn send_ack(&mut self, block: u16) -> Result<(), TftpError> {
+        self.last_sent = [OP_ACK.to_be_bytes(), block.to_be_bytes()].concat();
+        self.send_last()
+    }
+
+    /// Aborts the transfer, errors are ignored as the server times out anyway
+    fn send_error(&self, code: u16, message: &str) {
+        if let Some(port) = self.port {
+            let packet = error_packet(code, message);
+            let _ = self.socket.send_to(&packet, self.server, port);
+        }
+    }
+}
+
+fn read_request(filename: &str, options: Option<&TftpOptions>) -> Vec<u8> {
+    let mut fields = vec![filename.to_string(), "octet".to_string()];
+    if let Some(options) = options {
+        fields.extend([
+            "blksize".to_string(),
+            options.block_size.to_string(),
+            "tsize".to_string(),
+            "0".to_string(),
+        ]);
+        if options.window_size > 1 {
+            fields.extend(["windowsize".to_string(), options.window_size.to_string()]);
+        }
+    }
+
+    let mut packet = OP_RRQ.to_be_bytes().to_vec();
+    for field in fields {
+        packet.extend_from_slice(field.as_bytes());
+        packet.push(0);
+    }
+    packet
+}
+
+fn error_packet(code: u16, message: &str) -> Vec<u8> {
+    let mut packet = [OP_ERROR.to_be_bytes(), code.to_be_bytes()].concat();
+    packet.extend_from_slice(message.as_bytes());
+    packet.push(0);
+    packet
+}
+
+fn opcode(packet: &[u8]) -> u16 {
+    u16::from_be_bytes([packet[0], packet[1]])
+}
+
+fn error_code(packet: &[u8]) -> u16 {
+    u16::from_be_bytes([packet[2], packet[3]])
+}
+
+fn remote_error(packet: &[u8]) -> TftpError {
+    let message = packet[4..].split(|b| *b == 0).next().unwrap_or_default();
+    TftpError::Remote {
+        code: error_code(packet),
+        message: String::from_utf8_lossy(message).into_owned(),
+    }
+}
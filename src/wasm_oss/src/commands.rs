@@ -8,7 +8,8 @@ use proto_rs::schema::{
     },
     client_response::{client_response_inner, ClientResponseInner},
     BootClientRequest, ChainClientRequest, ClientRequest, ClientResponse, HelpClientRequest,
-    NonceClientRequest, PrintClientRequest, QuitClientRequest, StatusClientRequest,
+    NetstatClientRequest, NetworkClientRequest, NonceClientRequest, PrintClientRequest,
+    QuitClientRequest, StatusClientRequest, TftpClientRequest,
 };
 use std::{
     any::TypeId,
@@ -22,10 +23,13 @@ use std::{
 pub mod boot;
 pub mod chain;
 pub mod help;
+pub mod netstat;
+pub mod network;
 pub mod nonce;
 pub mod print;
 pub mod quit;
 pub mod status;
+pub mod tftp;
 
 pub type HandleStream<'a> = Pin<Box<dyn Stream<Item = Result<Bytes, hyper::Error>> + Send + 'a>>;
 
@@ -138,6 +142,13 @@ impl<'a> CommandDispatcher<'a> {
             client_request_inner::Payload::ChainRequest(_) => TypeId::of::<ChainClientRequest>(),
             client_request_inner::Payload::StatusRequest(_) => TypeId::of::<StatusClientRequest>(),
             client_request_inner::Payload::BootRequest(_) => TypeId::of::<BootClientRequest>(),
+            client_request_inner::Payload::NetworkRequest(_) => {
+                TypeId::of::<NetworkClientRequest>()
+            }
+            client_request_inner::Payload::TftpRequest(_) => TypeId::of::<TftpClientRequest>(),
+            client_request_inner::Payload::NetstatRequest(_) => {
+                TypeId::of::<NetstatClientRequest>()
+            }
         };
 
         let response_payload = match self.handlers.get(&type_id) {
@@ -1,7 +1,12 @@
 use super::{CommandDispatcher, CommandHandler, CommandRole, HandleStream};
 use crate::{
     controllers::boot::{BootController, PayloadType},
-    utils::msgpack::MessagePackByteStream,
+    errors::neoboot_error::{error_response, NeoBootError},
+    utils::{
+        events::{self, BootStage, UploadProgress},
+        msgpack::MessagePackByteStream,
+        verify::PayloadVerifier,
+    },
 };
 use bytes::Bytes;
 use futures::{lock::Mutex, Stream};
@@ -10,7 +15,7 @@ use log::{error, info};
 use proto_rs::schema::{
     client_request::client_request_inner,
     client_response::client_response_inner::{self},
-    BootClientResponse, ErrorClientResponse, NonceClientRequest, NonceClientResponse,
+    BootClientResponse, NonceClientRequest, NonceClientResponse,
 };
 use std::{
     cell::RefCell,
@@ -65,27 +70,41 @@ impl CommandHandler for BootCommandHandler {
             let message = match message {
                 Some(message) => message,
                 None => {
-                    return client_response_inner::Payload::ErrorResponse(ErrorClientResponse {
-                        error: "No message provided".to_string(),
-                    });
+                    return client_response_inner::Payload::ErrorResponse(
+                        NeoBootError::InvalidRequest("No message provided".to_string()).into(),
+                    );
                 }
             };
 
             let mut stream = match stream {
                 Some(stream) => stream,
                 None => {
-                    return client_response_inner::Payload::ErrorResponse(ErrorClientResponse {
-                        error: "No stream provided".to_string(),
-                    });
+                    return client_response_inner::Payload::ErrorResponse(
+                        NeoBootError::InvalidRequest("No stream provided".to_string()).into(),
+                    );
                 }
             };
 
             let mut msgpack_stream = MessagePackByteStream::new();
 
-            // TODO: Check hash and size
+            let mut verifier = PayloadVerifier::new(
+                (message.payload_size > 0).then_some(message.payload_size as u64),
+                &message.payload_sha256,
+            );
+            let mut progress = UploadProgress::new("boot", verifier.expected_size());
 
             while let Some(item) = stream.next().await {
-                msgpack_stream.extend_buffer(item.unwrap());
+                let item = item.unwrap();
+                progress.update(item.len());
+                if let Err(e) = verifier.update(&item) {
+                    boot_controller.lock().await.clear_payloads();
+                    events::boot_stage(BootStage::Rejected, "boot", Some(e.to_string()));
+                    return client_response_inner::Payload::ErrorResponse(error_response(
+                        "Boot payload rejected",
+                        &e,
+                    ));
+                }
+                msgpack_stream.extend_buffer(item);
 
                 while let Some(keyed_bytes) = msgpack_stream.process_bytes() {
                     if (keyed_bytes.is_err()) {
@@ -115,6 +134,20 @@ impl CommandHandler for BootCommandHandler {
                     }
                 }
             }
+
+            progress.finish();
+
+            if let Err(e) = verifier.finish() {
+                boot_controller.lock().await.clear_payloads();
+                events::boot_stage(BootStage::Rejected, "boot", Some(e.to_string()));
+                return client_response_inner::Payload::ErrorResponse(error_response(
+                    "Boot payload rejected",
+                    &e,
+                ));
+            }
+
+            events::boot_stage(BootStage::Verified, "boot", None);
+            events::boot_stage(BootStage::Booting, "boot", None);
             *shutdown_flag.lock().unwrap() = true;
             client_response_inner::Payload::BootResponse(BootClientResponse {})
         })
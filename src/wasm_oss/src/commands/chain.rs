@@ -1,5 +1,10 @@
 use super::{CommandDispatcher, CommandHandler, CommandRole, HandleStream};
-use crate::ffi;
+use crate::asyncio::http::response::{HostCopy, Response};
+use crate::errors::boot_error::BootError;
+use crate::errors::host_error::HostError;
+use crate::errors::neoboot_error::{error_response, NeoBootError};
+use crate::utils::events::{self, BootStage, UploadProgress};
+use crate::{ffi, utils::verify::PayloadVerifier};
 use bytes::Bytes;
 use futures::Stream;
 use futures_lite::StreamExt;
@@ -7,11 +12,100 @@ use log::info;
 use proto_rs::schema::{
     client_request::client_request_inner,
     client_response::client_response_inner::{self},
-    ChainClientResponse, ErrorClientResponse,
+    ChainClientResponse,
 };
-use sha2::{Digest, Sha256};
 use std::{collections::HashMap, error::Error, future::Future, pin::Pin};
 
+/// Host memory holding a payload that is chain-loaded once the executor exits.
+///
+/// The memory is freed again unless the buffer is committed.
+pub struct ChainloadBuffer {
+    ptr: u64,
+    len: u32,
+    offset: u32,
+}
+
+impl ChainloadBuffer {
+    pub fn new(len: u64) -> Result<Self, BootError> {
+        let len = u32::try_from(len).map_err(|_| BootError::PayloadTooLarge {
+            capacity: u32::MAX as u64,
+        })?;
+        let ptr = unsafe { ffi::env_malloc(len) };
+        if ptr == 0 {
+            return Err(HostError::OutOfMemory(len as u64).into());
+        }
+
+        Ok(Self {
+            ptr,
+            len,
+            offset: 0,
+        })
+    }
+
+    pub fn write(&mut self, bytes: &[u8]) -> Result<(), BootError> {
+        let end = self.offset as u64 + bytes.len() as u64;
+        if end > self.len as u64 {
+            return Err(BootError::PayloadTooLarge {
+                capacity: self.len as u64,
+            });
+        }
+
+        let result = unsafe {
+            ffi::env_memcpy(
+                bytes.as_ptr(),
+                self.ptr + self.offset as u64,
+                bytes.len() as u32,
+            )
+        };
+        if result < 0 {
+            return Err(HostError::Memcpy {
+                address: self.ptr + self.offset as u64,
+                len: bytes.len(),
+            }
+            .into());
+        }
+
+        self.offset = end as u32;
+        Ok(())
+    }
+
+    /// Copies a chain-load image from an HTTP response, which must announce
+    /// its length so the buffer can be allocated up front
+    pub async fn from_response(
+        response: &mut Response<'_>,
+        expected_sha256: &str,
+    ) -> Result<(Self, HostCopy), Box<dyn Error>> {
+        let len = response
+            .content_length()
+            .ok_or("Chain-load response has no Content-Length")?;
+        let mut buffer = Self::new(len)?;
+        let copy = response
+            .copy_to_sink(
+                &mut buffer,
+                PayloadVerifier::new(Some(len), expected_sha256),
+            )
+            .await?;
+        Ok((buffer, copy))
+    }
+
+    /// Size of the buffer in bytes
+    pub fn len(&self) -> u64 {
+        self.len as u64
+    }
+
+    /// Hands the buffer over to the host to chain-load
+    pub fn commit(self) {
+        unsafe { ffi::env_set_wasm_chainload(self.ptr, self.len) };
+        std::mem::forget(self);
+    }
+}
+
+impl Drop for ChainloadBuffer {
+    fn drop(&mut self) {
+        unsafe { ffi::env_free(self.ptr) };
+    }
+}
+
 pub struct ChainCommandHandler {}
 
 impl CommandHandler for ChainCommandHandler {
@@ -52,42 +146,61 @@ impl CommandHandler for ChainCommandHandler {
             let message = match message {
                 Some(message) => message,
                 None => {
-                    return client_response_inner::Payload::ErrorResponse(ErrorClientResponse {
-                        error: "No message provided".to_string(),
-                    });
+                    return client_response_inner::Payload::ErrorResponse(
+                        NeoBootError::InvalidRequest("No message provided".to_string()).into(),
+                    );
                 }
             };
 
             let mut stream = match stream {
                 Some(stream) => stream,
                 None => {
-                    return client_response_inner::Payload::ErrorResponse(ErrorClientResponse {
-                        error: "No stream provided".to_string(),
-                    });
+                    return client_response_inner::Payload::ErrorResponse(
+                        NeoBootError::InvalidRequest("No stream provided".to_string()).into(),
+                    );
                 }
             };
 
-            // Allocate a buffer to store the stream data
-            // TODO: Figure out when to free this buffer
-            let buf_ptr = unsafe { ffi::env_malloc(message.payload_size as u32) };
-            let buf_len = message.payload_size as u32;
-            let mut buf_hasher = Sha256::new();
-            let mut offset = 0;
-
-            while let Some(item) = stream.next().await {
-                let item = item.unwrap();
-                // info!("Received item of size {} bytes", item.len());
-                // Print the hex of the item
-                unsafe { ffi::env_memcpy(item.as_ptr(), buf_ptr + offset, item.len() as u32) };
-                offset += item.len() as u64;
-                buf_hasher.update(item);
-            }
-            let buf_hash = buf_hasher.finalize();
-            info!("chainload payload hash: {:x}", buf_hash);
+            let mut buffer = match ChainloadBuffer::new(message.payload_size as u64) {
+                Ok(buffer) => buffer,
+                Err(e) => {
+                    events::boot_stage(BootStage::Rejected, "chain", Some(e.to_string()));
+                    return client_response_inner::Payload::ErrorResponse(error_response(
+                        "Failed to allocate chainload buffer",
+                        &e,
+                    ));
+                }
+            };
+            let mut verifier =
+                PayloadVerifier::new(Some(message.payload_size as u64), &message.payload_sha256);
+            let mut progress = UploadProgress::new("chain", Some(message.payload_size as u64));
 
-            unsafe {
-                ffi::env_set_wasm_chainload(buf_ptr, buf_len);
+            let result: Result<String, Box<dyn Error + Send + Sync>> = async {
+                while let Some(item) = stream.next().await {
+                    let item = item?;
+                    progress.update(item.len());
+                    verifier.update(&item)?;
+                    buffer.write(&item)?;
+                }
+                progress.finish();
+                Ok(verifier.finish()?)
             }
+            .await;
+
+            let buf_hash = match result {
+                Ok(buf_hash) => buf_hash,
+                Err(e) => {
+                    events::boot_stage(BootStage::Rejected, "chain", Some(e.to_string()));
+                    return client_response_inner::Payload::ErrorResponse(error_response(
+                        "Chainload payload rejected",
+                        &*e,
+                    ));
+                }
+            };
+            info!("chainload payload hash: {}", buf_hash);
+            events::boot_stage(BootStage::Verified, "chain", None);
+            buffer.commit();
+            events::boot_stage(BootStage::Chainloading, "chain", None);
 
             *shutdown_flag.lock().unwrap() = true;
 
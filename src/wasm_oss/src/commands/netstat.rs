@@ -0,0 +1,112 @@
+use super::{CommandDispatcher, CommandHandler, CommandRole, HandleStream};
+use crate::asyncio::net::{self, NetStats};
+use proto_rs::schema::{
+    client_request::client_request_inner,
+    client_response::client_response_inner::{self},
+    NetstatClientRequest, NetstatClientResponse, NetworkStatistics,
+};
+use std::{collections::HashMap, error::Error, future::Future, pin::Pin};
+
+pub struct NetstatCommandHandler {}
+
+impl From<&NetStats> for NetworkStatistics {
+    fn from(stats: &NetStats) -> Self {
+        let pool_size = |value: Option<u32>| value.map_or(-1, |value| value as i32);
+        Self {
+            bytes_received: stats.bytes_received,
+            bytes_sent: stats.bytes_sent,
+            open_sockets: stats.open_sockets,
+            connections_accepted: stats.connections_accepted,
+            accept_failures: stats.accept_failures,
+            connections_established: stats.connections_established,
+            connect_failures: stats.connect_failures,
+            errors: stats
+                .errors
+                .iter()
+                .map(|(error, count)| (format!("{:?}", error), *count))
+                .collect(),
+            max_sockets: pool_size(stats.max_sockets),
+            used_sockets: pool_size(stats.used_sockets),
+        }
+    }
+}
+
+/// Formats network statistics for display on the console
+pub fn format_statistics(statistics: &NetworkStatistics) -> String {
+    let pool = |value: i32| {
+        if value < 0 {
+            "unknown".to_string()
+        } else {
+            value.to_string()
+        }
+    };
+
+    let mut output = format!(
+        "bytes: {} received, {} sent\nsockets: {} open, {} of {} host sockets in use\n\
+         connections: {} accepted, {} accept failures, {} established, {} connect failures",
+        statistics.bytes_received,
+        statistics.bytes_sent,
+        statistics.open_sockets,
+        pool(statistics.used_sockets),
+        pool(statistics.max_sockets),
+        statistics.connections_accepted,
+        statistics.accept_failures,
+        statistics.connections_established,
+        statistics.connect_failures
+    );
+
+    let mut errors: Vec<_> = statistics.errors.iter().collect();
+    errors.sort();
+    for (error, count) in errors {
+        output.push_str(&format!("\nerror {}: {}", error, count));
+    }
+    output
+}
+
+impl CommandHandler for NetstatCommandHandler {
+    fn cmd_pattern(&self) -> &'static str {
+        "netstat"
+    }
+
+    fn cmd_description(&self) -> &'static str {
+        "Show network statistics and socket usage"
+    }
+
+    fn cmd_roles(&self) -> Vec<CommandRole> {
+        vec![CommandRole::Console, CommandRole::System]
+    }
+
+    fn parse_args(
+        &self,
+        _: &HashMap<String, String>,
+    ) -> Result<client_request_inner::Payload, Box<dyn Error>> {
+        Ok(client_request_inner::Payload::NetstatRequest(
+            NetstatClientRequest {},
+        ))
+    }
+
+    fn handle<'a>(
+        &self,
+        _: &CommandDispatcher,
+        _: &client_request_inner::Payload,
+        _: Option<HandleStream<'a>>,
+    ) -> Pin<Box<dyn Future<Output = client_response_inner::Payload> + Send + 'a>> {
+        let statistics = NetworkStatistics::from(&net::stats());
+        Box::pin(async move {
+            client_response_inner::Payload::NetstatResponse(NetstatClientResponse {
+                statistics: Some(statistics),
+            })
+        })
+    }
+
+    fn response_as_string(&self, response: &client_response_inner::Payload) -> String {
+        match response {
+            client_response_inner::Payload::NetstatResponse(NetstatClientResponse {
+                statistics: Some(statistics),
+            }) => format_statistics(statistics),
+            _ => "".to_string(),
+        }
+    }
+
+    fn on_shutdown(&self) {}
+}
@@ -0,0 +1,122 @@
+use super::{CommandDispatcher, CommandHandler, CommandRole, HandleStream};
+use crate::errors::neoboot_error::{error_response, NeoBootError};
+use crate::{configuration::NetworkConfig, controllers::network::NetworkController};
+use futures::lock::Mutex;
+use proto_rs::schema::{
+    client_request::client_request_inner,
+    client_response::client_response_inner::{self},
+    NetworkClientRequest, NetworkClientResponse, NetworkLease,
+};
+use std::{collections::HashMap, error::Error, future::Future, pin::Pin, sync::Arc};
+
+pub struct NetworkCommandHandler {
+    pub network_controller: Arc<Mutex<NetworkController>>,
+}
+
+/// Formats a lease for display on the console
+pub fn format_lease(lease: &NetworkLease) -> String {
+    let mode = if lease.dhcp { "dhcp" } else { "static" };
+    let mut output = format!(
+        "mode: {}\naddress: {}\nnetmask: {}\ngateway: {}\ndns: {}",
+        mode,
+        lease.address,
+        lease.netmask,
+        lease.gateway,
+        lease.dns.join(", ")
+    );
+    if lease.dhcp {
+        output.push_str(&format!("\nlease time: {}s", lease.lease_time));
+    }
+    output
+}
+
+impl CommandHandler for NetworkCommandHandler {
+    fn cmd_pattern(&self) -> &'static str {
+        "ifconfig"
+    }
+
+    fn cmd_description(&self) -> &'static str {
+        "Show the network configuration"
+    }
+
+    fn cmd_roles(&self) -> Vec<CommandRole> {
+        vec![CommandRole::Console, CommandRole::System]
+    }
+
+    fn parse_args(
+        &self,
+        _: &HashMap<String, String>,
+    ) -> Result<client_request_inner::Payload, Box<dyn Error>> {
+        Ok(client_request_inner::Payload::NetworkRequest(
+            NetworkClientRequest { config: None },
+        ))
+    }
+
+    fn handle<'a>(
+        &self,
+        _: &CommandDispatcher,
+        request: &client_request_inner::Payload,
+        _: Option<HandleStream<'a>>,
+    ) -> Pin<Box<dyn Future<Output = client_response_inner::Payload> + Send + 'a>> {
+        let network_controller = self.network_controller.clone();
+        let config = match request {
+            client_request_inner::Payload::NetworkRequest(network_request) => {
+                // Errors are converted up front, as boxed errors cannot be sent
+                network_request
+                    .config
+                    .as_ref()
+                    .map(|config| NetworkConfig::try_from(config).map_err(|e| e.to_string()))
+            }
+            _ => None,
+        };
+
+        Box::pin(async move {
+            let lease = match config {
+                Some(Ok(config)) => {
+                    match NetworkController::set_config(&network_controller, config).await {
+                        Ok(lease) => Some((&lease).into()),
+                        Err(e) => {
+                            return client_response_inner::Payload::ErrorResponse(error_response(
+                                "Failed to configure network",
+                                &e,
+                            ));
+                        }
+                    }
+                }
+                Some(Err(e)) => {
+                    return client_response_inner::Payload::ErrorResponse(
+                        NeoBootError::InvalidRequest(format!(
+                            "Invalid network configuration: {}",
+                            e
+                        ))
+                        .into(),
+                    );
+                }
+                None => network_controller
+                    .lock()
+                    .await
+                    .lease()
+                    .map(NetworkLease::from),
+            };
+
+            client_response_inner::Payload::NetworkResponse(NetworkClientResponse { lease })
+        })
+    }
+
+    fn response_as_string(&self, response: &client_response_inner::Payload) -> String {
+        match response {
+            client_response_inner::Payload::NetworkResponse(NetworkClientResponse {
+                lease: Some(lease),
+            }) => format_lease(lease),
+            client_response_inner::Payload::NetworkResponse(_) => {
+                "Network is not configured".to_string()
+            }
+            client_response_inner::Payload::ErrorResponse(error_response) => {
+                error_response.error.clone()
+            }
+            _ => "".to_string(),
+        }
+    }
+
+    fn on_shutdown(&self) {}
+}
@@ -1,18 +1,22 @@
-use crate::executor::Executor;
+use crate::{asyncio::net, controllers::network::NetworkController, executor::Executor};
 
-use super::{CommandDispatcher, CommandHandler, CommandRole};
+use super::{
+    netstat::format_statistics, network::format_lease, CommandDispatcher, CommandHandler,
+    CommandRole,
+};
 use bytes::Bytes;
-use futures::Stream;
+use futures::{lock::Mutex, Stream};
 use log::info;
 use proto_rs::schema::{
     client_request::client_request_inner,
     client_response::client_response_inner::{self},
-    StatusClientRequest, StatusClientResponse,
+    NetworkLease, NetworkStatistics, StatusClientRequest, StatusClientResponse,
 };
-use std::{collections::HashMap, error::Error, future::Future, pin::Pin};
+use std::{collections::HashMap, error::Error, future::Future, pin::Pin, sync::Arc};
 
 pub struct StatusCommandHandler<'b> {
     pub executor: Executor<'b>,
+    pub network_controller: Arc<Mutex<NetworkController>>,
 }
 
 impl CommandHandler for StatusCommandHandler<'_> {
@@ -25,7 +29,7 @@ impl CommandHandler for StatusCommandHandler<'_> {
     }
 
     fn cmd_roles(&self) -> Vec<CommandRole> {
-        vec![CommandRole::Console]
+        vec![CommandRole::Console, CommandRole::System]
     }
 
     fn parse_args(
@@ -40,23 +44,36 @@ impl CommandHandler for StatusCommandHandler<'_> {
     fn handle<'a>(
         &self,
         _: &CommandDispatcher,
-        request: &client_request_inner::Payload,
+        _: &client_request_inner::Payload,
         _: Option<Pin<Box<dyn Stream<Item = Result<Bytes, hyper::Error>> + Send + 'a>>>,
     ) -> Pin<Box<dyn Future<Output = client_response_inner::Payload> + Send + 'a>> {
         info!("Active tasks: {:?}", self.executor.active_tasks());
-        let _ = match request {
-            client_request_inner::Payload::StatusRequest(_) => "Status".to_string(),
-            _ => "".to_string(),
-        };
-        Box::pin(
-            async move { client_response_inner::Payload::StatusResponse(StatusClientResponse {}) },
-        )
+        let network_controller = self.network_controller.clone();
+        let statistics = NetworkStatistics::from(&net::stats());
+        Box::pin(async move {
+            let network = network_controller
+                .lock()
+                .await
+                .lease()
+                .map(NetworkLease::from);
+            client_response_inner::Payload::StatusResponse(StatusClientResponse {
+                network,
+                statistics: Some(statistics),
+            })
+        })
     }
 
     fn response_as_string(&self, response: &client_response_inner::Payload) -> String {
         match response {
-            client_response_inner::Payload::PrintResponse(print_response) => {
-                print_response.message.clone()
+            client_response_inner::Payload::StatusResponse(status_response) => {
+                let mut output = match &status_response.network {
+                    Some(lease) => format!("Network:\n{}", format_lease(lease)),
+                    None => "Network: not configured".to_string(),
+                };
+                if let Some(statistics) = &status_response.statistics {
+                    output.push_str(&format!("\nStatistics:\n{}", format_statistics(statistics)));
+                }
+                output
             }
             _ => "".to_string(),
         }
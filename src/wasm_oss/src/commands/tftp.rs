@@ -0,0 +1,225 @@
+use super::{chain::ChainloadBuffer, CommandDispatcher, CommandHandler, CommandRole, HandleStream};
+use crate::{
+    asyncio::{
+        dns::GLOBAL_DNS_RESOLVER,
+        tftp::{TftpOptions, TftpTransfer},
+    },
+    controllers::boot::{BootController, PayloadType},
+    errors::neoboot_error::{error_response, NeoBootError},
+    errors::resolve_error::ResolveError,
+    errors::tftp_error::TftpError,
+    errors::verify_error::VerifyError,
+    utils::{
+        events::{self, BootStage, UploadProgress},
+        sys_get_env,
+        verify::PayloadVerifier,
+    },
+};
+use futures::lock::Mutex;
+use log::info;
+use proto_rs::schema::{
+    client_request::client_request_inner,
+    client_response::client_response_inner::{self},
+    TftpClientRequest, TftpClientResponse,
+};
+use std::{
+    collections::HashMap, error::Error, future::Future, net::IpAddr, pin::Pin, str::FromStr,
+    sync::Arc,
+};
+
+/// Target used to chain-load the file instead of storing it as a boot payload
+const CHAIN_TARGET: &str = "chain";
+
+pub struct TftpCommandHandler {
+    pub boot_controller: Arc<Mutex<BootController>>,
+}
+
+enum Destination {
+    Payload(PayloadType),
+    Chainload,
+}
+
+/// Fetches the requested file into its destination, returning its size and digest
+async fn fetch(
+    boot_controller: &Mutex<BootController>,
+    request: &TftpClientRequest,
+) -> Result<(Destination, TftpClientResponse), NeoBootError> {
+    let destination = if request.target == CHAIN_TARGET {
+        Destination::Chainload
+    } else {
+        Destination::Payload(PayloadType::from_str(&request.target)?)
+    };
+
+    let server = if request.server.is_empty() {
+        sys_get_env("serverip")?
+    } else {
+        request.server.clone()
+    };
+    let server = GLOBAL_DNS_RESOLVER
+        .lookup(&server)
+        .await?
+        .into_iter()
+        .find_map(|addr| match addr {
+            IpAddr::V4(addr) => Some(addr),
+            IpAddr::V6(_) => None,
+        })
+        .ok_or_else(|| ResolveError::NoAddress(server.clone()))?;
+
+    let mut transfer =
+        TftpTransfer::open(server, &request.filename, TftpOptions::default()).await?;
+
+    // Payloads are placed in preallocated memory, so the size must be known up front
+    let expected_size = (request.payload_size > 0).then_some(request.payload_size as u64);
+    let size = match (expected_size, transfer.transfer_size()) {
+        (Some(expected), Some(actual)) if expected != actual => {
+            return Err(VerifyError::SizeMismatch { expected, actual }.into());
+        }
+        (expected, announced) => expected.or(announced).ok_or_else(|| {
+            TftpError::Protocol("File size unknown, the server does not support tsize".into())
+        })?,
+    };
+    let mut verifier = PayloadVerifier::new(Some(size), &request.payload_sha256);
+
+    info!(
+        "Fetching {} ({} bytes) from {} with {} byte blocks",
+        request.filename,
+        size,
+        server,
+        transfer.block_size()
+    );
+    let mut progress = UploadProgress::new("tftp", Some(size));
+
+    let sha256 = match &destination {
+        Destination::Chainload => {
+            let mut buffer = ChainloadBuffer::new(size)?;
+            while let Some(block) = transfer.next_block().await? {
+                progress.update(block.len());
+                verifier.update(&block)?;
+                buffer.write(&block)?;
+            }
+            progress.finish();
+            let sha256 = verifier.finish()?;
+            buffer.commit();
+            sha256
+        }
+        Destination::Payload(payload_type) => {
+            let mut boot_controller = boot_controller.lock().await;
+            boot_controller.remove_payload(payload_type);
+
+            let result: Result<String, NeoBootError> = async {
+                while let Some(block) = transfer.next_block().await? {
+                    progress.update(block.len());
+                    verifier.update(&block)?;
+                    boot_controller
+                        .put_payload_bytes(payload_type.clone(), size, block)
+                        .await?;
+                }
+                progress.finish();
+                Ok(verifier.finish()?)
+            }
+            .await;
+
+            // Never leave a partial payload behind to be booted
+            if result.is_err() {
+                boot_controller.remove_payload(payload_type);
+            }
+            result?
+        }
+    };
+
+    Ok((destination, TftpClientResponse { size, sha256 }))
+}
+
+impl CommandHandler for TftpCommandHandler {
+    fn cmd_pattern(&self) -> &'static str {
+        "tftp <server> <filename> <target>"
+    }
+
+    fn cmd_description(&self) -> &'static str {
+        "Fetch a file over TFTP into a boot payload, or chain-load it"
+    }
+
+    fn cmd_roles(&self) -> Vec<CommandRole> {
+        vec![CommandRole::Console, CommandRole::System]
+    }
+
+    fn parse_args(
+        &self,
+        args: &HashMap<String, String>,
+    ) -> Result<client_request_inner::Payload, Box<dyn Error>> {
+        let arg = |name: &str| {
+            args.get(name)
+                .cloned()
+                .ok_or_else(|| format!("Missing argument: {}", name))
+        };
+
+        Ok(client_request_inner::Payload::TftpRequest(
+            TftpClientRequest {
+                server: arg("server")?,
+                filename: arg("filename")?,
+                target: arg("target")?,
+                payload_size: 0,
+                payload_sha256: String::new(),
+            },
+        ))
+    }
+
+    fn handle<'a>(
+        &self,
+        dispatcher: &CommandDispatcher,
+        request: &client_request_inner::Payload,
+        _: Option<HandleStream<'a>>,
+    ) -> Pin<Box<dyn Future<Output = client_response_inner::Payload> + Send + 'a>> {
+        let boot_controller = self.boot_controller.clone();
+        let message = match request {
+            client_request_inner::Payload::TftpRequest(tftp_request) => Some(tftp_request.clone()),
+            _ => None,
+        };
+
+        let shutdown_flag = dispatcher.shutdown_flag.clone();
+        Box::pin(async move {
+            let message = match message {
+                Some(message) => message,
+                None => {
+                    return client_response_inner::Payload::ErrorResponse(
+                        NeoBootError::InvalidRequest("No message provided".to_string()).into(),
+                    );
+                }
+            };
+
+            match fetch(&boot_controller, &message).await {
+                Ok((destination, response)) => {
+                    info!("Fetched {} (sha256 {})", message.filename, response.sha256);
+                    events::boot_stage(BootStage::Verified, "tftp", None);
+                    if let Destination::Chainload = destination {
+                        events::boot_stage(BootStage::Chainloading, "tftp", None);
+                        *shutdown_flag.lock().unwrap() = true;
+                    }
+                    client_response_inner::Payload::TftpResponse(response)
+                }
+                Err(e) => {
+                    events::boot_stage(BootStage::Rejected, "tftp", Some(e.to_string()));
+                    client_response_inner::Payload::ErrorResponse(error_response(
+                        &format!("Failed to fetch {}", message.filename),
+                        &e,
+                    ))
+                }
+            }
+        })
+    }
+
+    fn response_as_string(&self, response: &client_response_inner::Payload) -> String {
+        match response {
+            client_response_inner::Payload::TftpResponse(tftp_response) => format!(
+                "Received {} bytes\nsha256: {}",
+                tftp_response.size, tftp_response.sha256
+            ),
+            client_response_inner::Payload::ErrorResponse(error_response) => {
+                error_response.error.clone()
+            }
+            _ => "".to_string(),
+        }
+    }
+
+    fn on_shutdown(&self) {}
+}
@@ -1,8 +1,12 @@
+use crate::asyncio::get_keypress;
+use crate::asyncio::http::download::MemorySink;
+use crate::asyncio::http::response::{HostCopy, Response};
 use crate::commands::CommandDispatcher;
+use crate::errors::boot_error::BootError;
+use crate::errors::host_error::HostError;
 use crate::executor::Executor;
 use crate::ffi;
-use crate::utils::{parse_int, sys_get_env};
-use crate::{asyncio::get_keypress, errors::lwip_error::LwipError};
+use crate::utils::{parse_int, sys_get_env, verify::PayloadVerifier};
 use bytes::Bytes;
 use futures::lock::Mutex;
 use futures::Stream;
@@ -25,7 +29,7 @@ pub enum PayloadType {
 }
 
 impl PayloadType {
-    fn as_str(&self) -> &'static str {
+    pub fn as_str(&self) -> &'static str {
         match self {
             PayloadType::Kernel => "kernel_addr_r",
             PayloadType::Devicetree => "fdt_addr_r",
@@ -35,14 +39,14 @@ impl PayloadType {
 }
 
 impl FromStr for PayloadType {
-    type Err = LwipError;
+    type Err = BootError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         Ok(match s {
             "kernel_addr_r" => PayloadType::Kernel,
             "fdt_addr_r" => PayloadType::Devicetree,
             "ramdisk_addr_r" => PayloadType::Ramdisk,
-            _ => return Err(LwipError::IllegalArgument),
+            _ => return Err(BootError::UnknownPayloadType(s.to_string())),
         })
     }
 }
@@ -73,6 +77,11 @@ impl BootController {
         self.payloads.retain(|p| p.payload_type != *payload_type);
     }
 
+    /// Drops all payloads, e.g. after a transfer failed verification
+    pub fn clear_payloads(&mut self) {
+        self.payloads.clear();
+    }
+
     fn get_payload_mut(&mut self, payload_type: &PayloadType) -> Option<&mut Payload> {
         self.payloads
             .iter_mut()
@@ -97,13 +106,18 @@ impl BootController {
         payload_type: PayloadType,
         payload_size: u64,
         bytes: Bytes,
-    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    ) -> Result<(), BootError> {
         let payload = self.get_payload(&payload_type);
 
         // If the payload is not found, add it
         if payload.is_none() {
-            let payload_address = sys_get_env(payload_type.as_str())?;
-            let payload_address = parse_int(&payload_address)?;
+            let key = payload_type.as_str();
+            let payload_address = sys_get_env(key)?;
+            let payload_address =
+                parse_int(&payload_address).map_err(|_| BootError::InvalidAddress {
+                    key,
+                    value: payload_address,
+                })?;
             self.payloads.push(Payload {
                 payload_type: payload_type.clone(),
                 address: payload_address,
@@ -125,7 +139,11 @@ impl BootController {
         };
 
         if result < 0 {
-            return Err(Box::new(LwipError::Buffer));
+            return Err(HostError::Memcpy {
+                address: payload.address + payload.offset,
+                len: bytes.len(),
+            }
+            .into());
         }
 
         // Increment the offset
@@ -134,7 +152,32 @@ impl BootController {
         Ok(())
     }
 
-    pub fn boot(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+    /// Copies a payload from an HTTP response straight to its load address,
+    /// replacing any payload of the same type. At most `max_len` bytes are
+    /// accepted, and an empty `expected_sha256` skips the digest check.
+    pub async fn load_payload(
+        &mut self,
+        payload_type: PayloadType,
+        response: &mut Response<'_>,
+        max_len: u64,
+        expected_sha256: &str,
+    ) -> Result<HostCopy, Box<dyn std::error::Error>> {
+        self.remove_payload(&payload_type);
+        let mut sink = MemorySink::for_payload(&payload_type, max_len)?;
+        let copy = response
+            .copy_to_sink(&mut sink, PayloadVerifier::new(None, expected_sha256))
+            .await?;
+
+        self.payloads.push(Payload {
+            payload_type,
+            address: sink.address(),
+            offset: copy.size,
+            length: copy.size,
+        });
+        Ok(copy)
+    }
+
+    pub fn boot(&mut self) -> Result<(), BootError> {
         info!("Booting...");
         if self.payloads.is_empty() {
             info!("No payloads set");
@@ -148,11 +191,11 @@ impl BootController {
             None => {
                 let fdt_address = sys_get_env("fdt_addr");
                 if fdt_address.is_err() {
-                    return Err("No devicetree payload set".into());
+                    return Err(BootError::MissingPayload("devicetree"));
                 }
                 let fdt_address = parse_int(&fdt_address.unwrap());
                 if fdt_address.is_err() {
-                    return Err("No devicetree payload set".into());
+                    return Err(BootError::MissingPayload("devicetree"));
                 }
                 self.set_payload_address(PayloadType::Devicetree, fdt_address.unwrap());
                 self.get_payload(&PayloadType::Devicetree).unwrap()
@@ -161,12 +204,12 @@ impl BootController {
 
         let kernel_address = self.get_payload(&PayloadType::Kernel);
         if kernel_address.is_none() {
-            return Err("No kernel payload set".into());
+            return Err(BootError::MissingPayload("kernel"));
         }
 
         let ramdisk_address = self.get_payload(&PayloadType::Ramdisk);
         if ramdisk_address.is_none() {
-            return Err("No ramdisk payload set".into());
+            return Err(BootError::MissingPayload("ramdisk"));
         }
 
         info!("Booting...");
@@ -1 +1,2 @@
 pub mod boot;
+pub mod network;
@@ -0,0 +1,294 @@
+use crate::asyncio::dns::GLOBAL_DNS_RESOLVER;
+use crate::asyncio::sleep_ms;
+use crate::configuration::{AddressMode, NetworkConfig};
+use crate::errors::lwip_error::LwipError;
+use crate::ffi;
+use crate::utils::{ip_addr_to_u32, u32_to_ip_addr};
+use futures::lock::{Mutex, MutexGuard};
+use log::{info, warn};
+use std::net::Ipv4Addr;
+use std::sync::Arc;
+
+/// How long to wait for a DHCP server before giving up
+const DHCP_TIMEOUT_MS: u64 = 15_000;
+const DHCP_POLL_INTERVAL_MS: u64 = 50;
+
+/// Maximum number of DNS servers offered by DHCP that are used
+const MAX_DHCP_DNS_SERVERS: u32 = 2;
+
+/// The address configuration currently active on the interface
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lease {
+    pub dhcp: bool,
+    pub address: Ipv4Addr,
+    pub netmask: Ipv4Addr,
+    pub gateway: Ipv4Addr,
+    pub dns: Vec<Ipv4Addr>,
+    /// Lease time in seconds, 0 for a static configuration
+    pub lease_time: u32,
+    /// Timestamp at which the lease was bound or last renewed
+    pub acquired_at: u64,
+}
+
+impl Lease {
+    /// Milliseconds until the lease should be renewed (T1, half the lease time)
+    pub fn renew_in_ms(&self) -> Option<u64> {
+        if !self.dhcp || self.lease_time == 0 {
+            return None;
+        }
+
+        let renew_at = self.acquired_at + self.lease_time as u64 * 1000 / 2;
+        let now = unsafe { ffi::env_now() };
+        Some(renew_at.saturating_sub(now))
+    }
+}
+
+impl From<&Lease> for proto_rs::schema::NetworkLease {
+    fn from(lease: &Lease) -> Self {
+        Self {
+            dhcp: lease.dhcp,
+            address: lease.address.to_string(),
+            netmask: lease.netmask.to_string(),
+            gateway: lease.gateway.to_string(),
+            dns: lease.dns.iter().map(Ipv4Addr::to_string).collect(),
+            lease_time: lease.lease_time,
+        }
+    }
+}
+
+pub struct NetworkController {
+    config: NetworkConfig,
+    lease: Option<Lease>,
+    /// Set while the interface is being configured, the controller is not
+    /// locked while waiting for a DHCP server
+    configuring: bool,
+    /// Result of the last configuration, handed to the callers that waited for it
+    outcome: Option<Result<Lease, LwipError>>,
+}
+
+/// Clears the in-progress flag if a configuration is dropped halfway
+struct ConfiguringGuard<'c>(&'c Mutex<NetworkController>);
+
+impl Drop for ConfiguringGuard<'_> {
+    fn drop(&mut self) {
+        // The lock is held when the configuration completes, which clears the flag itself
+        if let Some(mut controller) = self.0.try_lock() {
+            controller.configuring = false;
+        }
+    }
+}
+
+impl NetworkController {
+    pub fn new(config: NetworkConfig) -> Arc<Mutex<Self>> {
+        Arc::new(Mutex::new(Self {
+            config,
+            lease: None,
+            configuring: false,
+            outcome: None,
+        }))
+    }
+
+    pub fn config(&self) -> &NetworkConfig {
+        &self.config
+    }
+
+    pub fn lease(&self) -> Option<&Lease> {
+        self.lease.as_ref()
+    }
+
+    /// Whether the interface is being configured or its lease renewed
+    pub fn is_configuring(&self) -> bool {
+        self.configuring
+    }
+
+    /// Replaces the configuration and reconfigures the interface, after any
+    /// configuration in progress completed
+    pub async fn set_config(
+        controller: &Mutex<Self>,
+        config: NetworkConfig,
+    ) -> Result<Lease, LwipError> {
+        let (mut this, _) = Self::idle(controller).await;
+        this.config = config;
+        Self::configure(controller, this, false).await
+    }
+
+    /// Configures the interface according to the current configuration.
+    /// Callers arriving while a configuration is in progress get its result.
+    ///
+    /// The controller is only locked to start and to read the result, so the
+    /// lease can be queried while waiting for a DHCP server.
+    pub async fn apply(controller: &Mutex<Self>) -> Result<Lease, LwipError> {
+        match Self::idle(controller).await {
+            (_, Some(outcome)) => outcome,
+            (this, None) => Self::configure(controller, this, false).await,
+        }
+    }
+
+    /// Renews the DHCP lease, falling back to a full DHCP exchange if that fails
+    pub async fn renew(controller: &Mutex<Self>) -> Result<Lease, LwipError> {
+        match Self::idle(controller).await {
+            (_, Some(outcome)) => outcome,
+            (this, None) => Self::configure(controller, this, true).await,
+        }
+    }
+
+    /// Waits until no configuration is in progress and locks the controller,
+    /// returning the outcome of the configuration waited for, if any
+    async fn idle(
+        controller: &Mutex<Self>,
+    ) -> (MutexGuard<'_, Self>, Option<Result<Lease, LwipError>>) {
+        let mut waited = false;
+        loop {
+            let this = controller.lock().await;
+            if !this.configuring {
+                let outcome = if waited { this.outcome.clone() } else { None };
+                return (this, outcome);
+            }
+            drop(this);
+            waited = true;
+            sleep_ms(DHCP_POLL_INTERVAL_MS).await;
+        }
+    }
+
+    async fn configure(
+        controller: &Mutex<Self>,
+        mut this: MutexGuard<'_, Self>,
+        renew: bool,
+    ) -> Result<Lease, LwipError> {
+        // A static configuration has no lease to renew, it is applied again
+        let mut renew = renew && this.config.mode == AddressMode::Dhcp;
+        let _guard = ConfiguringGuard(controller);
+        loop {
+            let started = if renew {
+                this.start_renew().map(|()| true)
+            } else {
+                this.start()
+            };
+            this.configuring = true;
+            drop(this);
+
+            let result = match started {
+                Ok(true) => Self::wait_for_dhcp().await,
+                Ok(false) => Ok(()),
+                Err(e) => Err(e),
+            };
+
+            this = controller.lock().await;
+            this.configuring = false;
+            match result {
+                Err(e) if renew => {
+                    warn!("DHCP renew failed: {}", e);
+                    renew = false;
+                }
+                result => {
+                    let outcome = result.map(|()| this.finish());
+                    this.outcome = Some(outcome.clone());
+                    return outcome;
+                }
+            }
+        }
+    }
+
+    /// Starts configuring the interface, returning whether a DHCP lease must be waited for
+    fn start(&mut self) -> Result<bool, LwipError> {
+        self.lease = None;
+
+        match self.config.mode.clone() {
+            AddressMode::Dhcp => {
+                info!("Requesting DHCP lease");
+                let result = unsafe { ffi::env_net_dhcp_start() };
+                if result != LwipError::Ok.to_code() {
+                    return Err(LwipError::from_code(result));
+                }
+                Ok(true)
+            }
+            AddressMode::Static {
+                address,
+                netmask,
+                gateway,
+            } => {
+                info!("Setting static address {}/{}", address, netmask);
+                let result = unsafe {
+                    ffi::env_net_set_static(
+                        ip_addr_to_u32(address),
+                        ip_addr_to_u32(netmask),
+                        ip_addr_to_u32(gateway.unwrap_or(Ipv4Addr::UNSPECIFIED)),
+                    )
+                };
+                if result != LwipError::Ok.to_code() {
+                    return Err(LwipError::from_code(result));
+                }
+                Ok(false)
+            }
+        }
+    }
+
+    fn start_renew(&self) -> Result<(), LwipError> {
+        info!("Renewing DHCP lease");
+        let result = unsafe { ffi::env_net_dhcp_renew() };
+        if result != LwipError::Ok.to_code() {
+            return Err(LwipError::from_code(result));
+        }
+
+        Ok(())
+    }
+
+    /// Reads the configuration the interface ended up with
+    fn finish(&mut self) -> Lease {
+        let lease = self.read_lease();
+        info!("Network configured: {:?}", lease);
+        if !lease.dns.is_empty() {
+            GLOBAL_DNS_RESOLVER.set_servers(lease.dns.clone());
+        }
+
+        self.lease.insert(lease).clone()
+    }
+
+    async fn wait_for_dhcp() -> Result<(), LwipError> {
+        let deadline = unsafe { ffi::env_now() } + DHCP_TIMEOUT_MS;
+        loop {
+            unsafe { ffi::env_net_rx() };
+            match LwipError::from_code(unsafe { ffi::env_net_dhcp_poll() }) {
+                LwipError::Ok => return Ok(()),
+                LwipError::InProgress | LwipError::WouldBlock => (),
+                err => return Err(err),
+            }
+
+            if unsafe { ffi::env_now() } >= deadline {
+                return Err(LwipError::Timeout);
+            }
+            sleep_ms(DHCP_POLL_INTERVAL_MS).await;
+        }
+    }
+
+    fn read_lease(&self) -> Lease {
+        let dhcp = self.config.mode == AddressMode::Dhcp;
+
+        // Configured DNS servers take precedence over the ones offered by DHCP
+        let dns = if self.config.dns.is_empty() && dhcp {
+            (0..MAX_DHCP_DNS_SERVERS)
+                .map(|index| unsafe { ffi::env_net_dns_get_server(index) })
+                .filter(|addr| *addr != 0)
+                .map(u32_to_ip_addr)
+                .collect()
+        } else {
+            self.config.dns.clone()
+        };
+
+        unsafe {
+            Lease {
+                dhcp,
+                address: u32_to_ip_addr(ffi::env_net_get_addr()),
+                netmask: u32_to_ip_addr(ffi::env_net_get_netmask()),
+                gateway: u32_to_ip_addr(ffi::env_net_get_gateway()),
+                dns,
+                lease_time: if dhcp {
+                    ffi::env_net_dhcp_lease_time()
+                } else {
+                    0
+                },
+                acquired_at: ffi::env_now(),
+            }
+        }
+    }
+}
@@ -0,0 +1,46 @@
+use super::host_error::HostError;
+
+#[derive(Debug)]
+pub enum BootError {
+    /// The name does not match any payload type
+    UnknownPayloadType(String),
+    /// A payload needed to boot was not provided
+    MissingPayload(&'static str),
+    /// The load address in the environment is not a number
+    InvalidAddress { key: &'static str, value: String },
+    /// The payload does not fit in the memory reserved for it
+    PayloadTooLarge { capacity: u64 },
+    /// The payload could not be placed in memory
+    Host(HostError),
+}
+
+impl std::fmt::Display for BootError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnknownPayloadType(name) => write!(f, "Unknown payload type: {}", name),
+            Self::MissingPayload(name) => write!(f, "No {} payload set", name),
+            Self::InvalidAddress { key, value } => {
+                write!(f, "Invalid load address in {}: {}", key, value)
+            }
+            Self::PayloadTooLarge { capacity } => {
+                write!(f, "Payload exceeds the {} bytes reserved for it", capacity)
+            }
+            Self::Host(err) => write!(f, "Host error: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for BootError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Host(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<HostError> for BootError {
+    fn from(err: HostError) -> Self {
+        Self::Host(err)
+    }
+}
@@ -0,0 +1,54 @@
+/// Failures to decompress a response body
+#[derive(Debug)]
+pub enum DecodeError {
+    /// The server used a content encoding that was not offered
+    UnsupportedEncoding(String),
+    /// The body ended in the middle of a compressed stream
+    Truncated(&'static str),
+    /// The compressed stream does not follow its format
+    Malformed(&'static str),
+    /// The gzip or deflate stream is corrupt
+    Deflate(std::io::Error),
+    /// The zstd stream is corrupt
+    Zstd(ruzstd::decoding::errors::FrameDecoderError),
+    /// An lz4 block is corrupt
+    Lz4(lz4_flex::block::DecompressError),
+}
+
+impl std::fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnsupportedEncoding(encoding) => {
+                write!(f, "Unsupported content encoding: {}", encoding)
+            }
+            Self::Truncated(format) => write!(f, "Truncated {} stream", format),
+            Self::Malformed(msg) => write!(f, "{}", msg),
+            Self::Deflate(err) => write!(f, "Invalid deflate stream: {}", err),
+            Self::Zstd(err) => write!(f, "Invalid zstd stream: {}", err),
+            Self::Lz4(err) => write!(f, "Invalid lz4 block: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Deflate(err) => Some(err),
+            Self::Zstd(err) => Some(err),
+            Self::Lz4(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<ruzstd::decoding::errors::FrameDecoderError> for DecodeError {
+    fn from(err: ruzstd::decoding::errors::FrameDecoderError) -> Self {
+        Self::Zstd(err)
+    }
+}
+
+impl From<lz4_flex::block::DecompressError> for DecodeError {
+    fn from(err: lz4_flex::block::DecompressError) -> Self {
+        Self::Lz4(err)
+    }
+}
@@ -0,0 +1,27 @@
+/// Failures of calls into the host environment
+#[derive(Debug)]
+pub enum HostError {
+    /// The environment variable is not set
+    MissingEnv(String),
+    /// The environment variable is not valid UTF-8
+    InvalidEnv(String),
+    /// The host could not allocate the requested number of bytes
+    OutOfMemory(u64),
+    /// Copying into host memory failed
+    Memcpy { address: u64, len: usize },
+}
+
+impl std::fmt::Display for HostError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingEnv(key) => write!(f, "Environment variable {} is not set", key),
+            Self::InvalidEnv(key) => write!(f, "Environment variable {} is not UTF-8", key),
+            Self::OutOfMemory(size) => write!(f, "Failed to allocate {} bytes", size),
+            Self::Memcpy { address, len } => {
+                write!(f, "Failed to copy {} bytes to {:#x}", len, address)
+            }
+        }
+    }
+}
+
+impl std::error::Error for HostError {}
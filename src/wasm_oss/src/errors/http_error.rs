@@ -0,0 +1,250 @@
+use super::decode_error::DecodeError;
+use super::lwip_error::LwipError;
+use super::proxy_error::ProxyError;
+use super::resolve_error::ResolveError;
+use super::websocket_error::WebSocketError;
+use std::error::Error;
+
+/// Failures of the HTTP client
+#[derive(Debug)]
+pub enum HttpError {
+    /// The URL could not be parsed
+    InvalidUrl(url::ParseError),
+    /// The URL could not be turned into a request target
+    InvalidUri(http::uri::InvalidUri),
+    /// The URL has no host
+    MissingHost,
+    /// The host cannot be checked against a server certificate
+    InvalidServerName(rustls_pki_types::InvalidDnsNameError),
+    /// The server name could not be resolved
+    Dns(ResolveError),
+    /// No TCP connection could be established to the server or proxy
+    Connect(LwipError),
+    /// The proxy did not open a tunnel to the server
+    Proxy(ProxyError),
+    /// The TLS handshake with the server failed
+    Tls(rustls::Error),
+    /// The server certificate cannot be checked before the wall clock is synced
+    ClockNotSynced,
+    /// The connection failed during the TLS handshake
+    Io(std::io::Error),
+    /// A phase of the request did not complete in time
+    Timeout(&'static str),
+    /// The request could not be built from its headers
+    Request(http::Error),
+    /// A streamed request body can only be sent once
+    BodyAlreadySent,
+    /// The connection failed while sending the request or receiving the response
+    Connection(hyper::Error),
+    /// The redirect limit was reached
+    TooManyRedirects(usize),
+    /// A redirect points to a scheme other than HTTP or HTTPS
+    UnsupportedRedirect(String),
+    /// The server answered with a status that does not allow to continue
+    Status(u16),
+    /// The response body could not be decompressed
+    Decode(DecodeError),
+    /// The decompressed response body exceeds the limit
+    DecompressedTooLarge(u64),
+    /// The response stream did not start with the status and headers
+    MalformedResponse(&'static str),
+    /// The response body is not valid JSON
+    Json(serde_json::Error),
+    /// The TLS client certificate and key do not go together
+    Identity(&'static str),
+    /// The TLS client certificate or key is not valid PEM
+    Pem(&'static str, rustls_pki_types::pem::Error),
+    /// The TLS client certificate or key was rejected
+    ClientAuth(rustls::Error),
+    /// No credentials could be obtained for the request
+    Auth(Box<dyn Error + Send + Sync>),
+    /// The WebSocket handshake failed
+    WebSocket(WebSocketError),
+    /// The resource changed between two parts of a download
+    ResourceChanged {
+        what: &'static str,
+        before: String,
+        after: String,
+    },
+    /// The server resumed a download at another offset than requested
+    RangeMismatch { expected: u64, actual: u64 },
+    /// The body ended before reaching the announced size
+    IncompleteBody { received: u64, expected: u64 },
+    /// A download kept failing, with the last failure
+    Interrupted {
+        url: String,
+        attempts: u32,
+        last: Box<HttpError>,
+    },
+}
+
+impl std::fmt::Display for HttpError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidUrl(err) => write!(f, "Invalid URL: {}", err),
+            Self::InvalidUri(err) => write!(f, "Invalid URL: {}", err),
+            Self::MissingHost => write!(f, "Missing host in URL"),
+            Self::InvalidServerName(err) => write!(f, "Invalid server name: {}", err),
+            Self::Dns(err) => write!(f, "DNS resolution failed: {}", err),
+            Self::Connect(err) => write!(f, "Connection failed: {}", err),
+            Self::Proxy(err) => write!(f, "Proxy tunnel failed: {}", err),
+            Self::Tls(err) => write!(f, "TLS handshake failed: {}", err),
+            Self::ClockNotSynced => write!(
+                f,
+                "Cannot verify the server certificate until the clock is synced"
+            ),
+            Self::Io(err) => write!(f, "TLS handshake failed: {}", err),
+            Self::Timeout(phase) => write!(f, "{} timed out", phase),
+            Self::Request(err) => write!(f, "Invalid request: {}", err),
+            Self::BodyAlreadySent => write!(f, "Streaming request body was already sent"),
+            Self::Connection(err) => write!(f, "Request failed: {}", err),
+            Self::TooManyRedirects(count) => write!(f, "Stopped after {} redirects", count),
+            Self::UnsupportedRedirect(url) => write!(f, "Unsupported redirect to {}", url),
+            Self::Status(status_code) => write!(f, "Server returned {}", status_code),
+            Self::Decode(err) => write!(f, "Failed to decompress response: {}", err),
+            Self::DecompressedTooLarge(limit) => {
+                write!(f, "Decompressed response exceeds {} bytes", limit)
+            }
+            Self::MalformedResponse(msg) => write!(f, "Malformed response: {}", msg),
+            Self::Json(err) => write!(f, "Invalid JSON response: {}", err),
+            Self::Identity(msg) => write!(f, "Invalid client identity: {}", msg),
+            Self::Pem(what, err) => write!(f, "Invalid client identity: invalid {}: {}", what, err),
+            Self::ClientAuth(err) => write!(f, "Invalid client identity: {}", err),
+            Self::Auth(err) => write!(f, "No credentials: {}", err),
+            Self::WebSocket(err) => write!(f, "{}", err),
+            Self::ResourceChanged {
+                what,
+                before,
+                after,
+            } => write!(
+                f,
+                "Resource changed during download: {} {} became {}",
+                what, before, after
+            ),
+            Self::RangeMismatch { expected, actual } => {
+                write!(f, "Server resumed at {} instead of {}", actual, expected)
+            }
+            Self::IncompleteBody { received, expected } => write!(
+                f,
+                "Connection closed after {} of {} bytes",
+                received, expected
+            ),
+            Self::Interrupted {
+                url,
+                attempts,
+                last,
+            } => write!(
+                f,
+                "Download of {} failed after {} attempts: {}",
+                url, attempts, last
+            ),
+        }
+    }
+}
+
+impl Error for HttpError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::InvalidUrl(err) => Some(err),
+            Self::InvalidUri(err) => Some(err),
+            Self::InvalidServerName(err) => Some(err),
+            Self::Dns(err) => Some(err),
+            Self::Connect(err) => Some(err),
+            Self::Proxy(err) => Some(err),
+            Self::Tls(err) => Some(err),
+            Self::Io(err) => Some(err),
+            Self::Request(err) => Some(err),
+            Self::Connection(err) => Some(err),
+            Self::Decode(err) => Some(err),
+            Self::Json(err) => Some(err),
+            Self::Pem(_, err) => Some(err),
+            Self::ClientAuth(err) => Some(err),
+            Self::Auth(err) => Some(err.as_ref()),
+            Self::WebSocket(err) => Some(err),
+            Self::Interrupted { last, .. } => Some(last.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl From<url::ParseError> for HttpError {
+    fn from(err: url::ParseError) -> Self {
+        Self::InvalidUrl(err)
+    }
+}
+
+impl From<http::uri::InvalidUri> for HttpError {
+    fn from(err: http::uri::InvalidUri) -> Self {
+        Self::InvalidUri(err)
+    }
+}
+
+impl From<rustls_pki_types::InvalidDnsNameError> for HttpError {
+    fn from(err: rustls_pki_types::InvalidDnsNameError) -> Self {
+        Self::InvalidServerName(err)
+    }
+}
+
+impl From<ResolveError> for HttpError {
+    fn from(err: ResolveError) -> Self {
+        Self::Dns(err)
+    }
+}
+
+impl From<ProxyError> for HttpError {
+    fn from(err: ProxyError) -> Self {
+        Self::Proxy(err)
+    }
+}
+
+/// TLS handshakes report protocol failures as I/O errors wrapping the
+/// `rustls::Error`, which is unwrapped so it can be told apart from a lost connection
+impl From<std::io::Error> for HttpError {
+    fn from(err: std::io::Error) -> Self {
+        if !err
+            .get_ref()
+            .is_some_and(|inner| inner.is::<rustls::Error>())
+        {
+            return Self::Io(err);
+        }
+        let kind = err.kind();
+        match err
+            .into_inner()
+            .map(|inner| inner.downcast::<rustls::Error>())
+        {
+            Some(Ok(err)) => Self::Tls(*err),
+            Some(Err(inner)) => Self::Io(std::io::Error::new(kind, inner)),
+            None => Self::Io(kind.into()),
+        }
+    }
+}
+
+impl From<http::Error> for HttpError {
+    fn from(err: http::Error) -> Self {
+        Self::Request(err)
+    }
+}
+
+impl From<hyper::Error> for HttpError {
+    fn from(err: hyper::Error) -> Self {
+        Self::Connection(err)
+    }
+}
+
+impl From<DecodeError> for HttpError {
+    fn from(err: DecodeError) -> Self {
+        Self::Decode(err)
+    }
+}
+
+impl From<serde_json::Error> for HttpError {
+    fn from(err: serde_json::Error) -> Self {
+        Self::Json(err)
+    }
+}
+
+impl From<WebSocketError> for HttpError {
+    fn from(err: WebSocketError) -> Self {
+        Self::WebSocket(err)
+    }
+}
@@ -1,2 +1,15 @@
+pub mod boot_error;
+pub mod decode_error;
+pub mod host_error;
+pub mod http_error;
 pub mod lwip_error;
 pub mod msgpack_error;
+pub mod neoboot_error;
+pub mod protocol_error;
+pub mod proxy_error;
+pub mod resolve_error;
+pub mod route_error;
+pub mod sntp_error;
+pub mod tftp_error;
+pub mod verify_error;
+pub mod websocket_error;
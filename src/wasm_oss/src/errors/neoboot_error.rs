@@ -0,0 +1,278 @@
+use super::boot_error::BootError;
+use super::host_error::HostError;
+use super::http_error::HttpError;
+use super::lwip_error::LwipError;
+use super::msgpack_error::MessagePackError;
+use super::protocol_error::ProtocolError;
+use super::resolve_error::ResolveError;
+use super::sntp_error::SntpError;
+use super::tftp_error::TftpError;
+use super::verify_error::VerifyError;
+use super::websocket_error::WebSocketError;
+use proto_rs::schema::{error_client_response::ErrorCode, ErrorClientResponse};
+use std::error::Error;
+
+/// Crate-wide error, grouping the errors of each subsystem
+#[derive(Debug)]
+pub enum NeoBootError {
+    /// The client request is malformed or refers to something that does not exist
+    InvalidRequest(String),
+    /// A socket or the network interface failed
+    Network(LwipError),
+    /// A host name could not be resolved
+    Resolve(ResolveError),
+    /// The HTTP client failed, including its TLS handshakes
+    Http(HttpError),
+    /// A TFTP transfer failed
+    Tftp(TftpError),
+    /// Received data does not follow its encoding
+    Protocol(Box<dyn Error + Send + Sync>),
+    /// A payload does not match its announced size or digest
+    Verification(VerifyError),
+    /// A boot payload could not be loaded or booted
+    Boot(BootError),
+    /// A call into the host environment failed
+    Host(HostError),
+}
+
+impl NeoBootError {
+    /// Code reported to clients, so they can react without parsing the message
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Self::InvalidRequest(_) => ErrorCode::InvalidRequest,
+            Self::Network(err) => err.into(),
+            Self::Resolve(err) => err.into(),
+            Self::Http(err) => err.into(),
+            Self::Tftp(err) => err.into(),
+            Self::Protocol(_) => ErrorCode::Protocol,
+            Self::Verification(err) => err.into(),
+            Self::Boot(err) => err.into(),
+            Self::Host(err) => err.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for NeoBootError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidRequest(msg) => write!(f, "Invalid request: {}", msg),
+            Self::Network(err) => write!(f, "Network error: {}", err),
+            Self::Resolve(err) => write!(f, "Failed to resolve: {}", err),
+            Self::Http(err) => write!(f, "HTTP error: {}", err),
+            Self::Tftp(err) => write!(f, "TFTP error: {}", err),
+            Self::Protocol(err) => write!(f, "Protocol error: {}", err),
+            Self::Verification(err) => write!(f, "Verification failed: {}", err),
+            Self::Boot(err) => write!(f, "Boot error: {}", err),
+            Self::Host(err) => write!(f, "Host error: {}", err),
+        }
+    }
+}
+
+impl Error for NeoBootError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::InvalidRequest(_) => None,
+            Self::Resolve(err) => Some(err),
+            Self::Network(err) => Some(err),
+            Self::Http(err) => Some(err),
+            Self::Tftp(err) => Some(err),
+            Self::Protocol(err) => Some(err.as_ref()),
+            Self::Verification(err) => Some(err),
+            Self::Boot(err) => Some(err),
+            Self::Host(err) => Some(err),
+        }
+    }
+}
+
+impl From<LwipError> for NeoBootError {
+    fn from(err: LwipError) -> Self {
+        Self::Network(err)
+    }
+}
+
+impl From<ResolveError> for NeoBootError {
+    fn from(err: ResolveError) -> Self {
+        Self::Resolve(err)
+    }
+}
+
+impl From<HttpError> for NeoBootError {
+    fn from(err: HttpError) -> Self {
+        Self::Http(err)
+    }
+}
+
+impl From<TftpError> for NeoBootError {
+    fn from(err: TftpError) -> Self {
+        Self::Tftp(err)
+    }
+}
+
+impl From<MessagePackError> for NeoBootError {
+    fn from(err: MessagePackError) -> Self {
+        Self::Protocol(Box::new(err))
+    }
+}
+
+impl From<ProtocolError> for NeoBootError {
+    fn from(err: ProtocolError) -> Self {
+        Self::Protocol(Box::new(err))
+    }
+}
+
+impl From<prost::DecodeError> for NeoBootError {
+    fn from(err: prost::DecodeError) -> Self {
+        Self::Protocol(Box::new(err))
+    }
+}
+
+impl From<VerifyError> for NeoBootError {
+    fn from(err: VerifyError) -> Self {
+        Self::Verification(err)
+    }
+}
+
+impl From<BootError> for NeoBootError {
+    fn from(err: BootError) -> Self {
+        Self::Boot(err)
+    }
+}
+
+impl From<HostError> for NeoBootError {
+    fn from(err: HostError) -> Self {
+        Self::Host(err)
+    }
+}
+
+impl From<NeoBootError> for ErrorClientResponse {
+    fn from(err: NeoBootError) -> Self {
+        Self {
+            error: err.to_string(),
+            code: err.code() as i32,
+        }
+    }
+}
+
+// Codes of the errors of each subsystem, shared by `NeoBootError::code` and
+// `error_code`, so that an error is reported the same way whether it was
+// wrapped in a `NeoBootError` or not
+
+impl From<&LwipError> for ErrorCode {
+    fn from(_: &LwipError) -> Self {
+        ErrorCode::Network
+    }
+}
+
+impl From<&ResolveError> for ErrorCode {
+    fn from(err: &ResolveError) -> Self {
+        match err {
+            ResolveError::InvalidName(_) => ErrorCode::InvalidRequest,
+            ResolveError::Entropy(_) => ErrorCode::Host,
+            _ => ErrorCode::Network,
+        }
+    }
+}
+
+impl From<&SntpError> for ErrorCode {
+    fn from(err: &SntpError) -> Self {
+        match err {
+            SntpError::Protocol(_) => ErrorCode::Protocol,
+            _ => ErrorCode::Network,
+        }
+    }
+}
+
+impl From<&HttpError> for ErrorCode {
+    fn from(err: &HttpError) -> Self {
+        match err {
+            HttpError::Dns(err) => err.into(),
+            HttpError::Connect(_) | HttpError::Io(_) | HttpError::Proxy(_) => ErrorCode::Network,
+            HttpError::Tls(_)
+            | HttpError::ClockNotSynced
+            | HttpError::Identity(_)
+            | HttpError::Pem(..)
+            | HttpError::ClientAuth(_) => ErrorCode::Tls,
+            HttpError::WebSocket(err) => err.into(),
+            HttpError::Interrupted { last, .. } => last.as_ref().into(),
+            _ => ErrorCode::Http,
+        }
+    }
+}
+
+impl From<&TftpError> for ErrorCode {
+    fn from(_: &TftpError) -> Self {
+        ErrorCode::Tftp
+    }
+}
+
+impl From<&WebSocketError> for ErrorCode {
+    fn from(err: &WebSocketError) -> Self {
+        match err {
+            WebSocketError::Io(_) | WebSocketError::Closed => ErrorCode::Network,
+            WebSocketError::Handshake(_) => ErrorCode::Http,
+            _ => ErrorCode::Protocol,
+        }
+    }
+}
+
+impl From<&VerifyError> for ErrorCode {
+    fn from(_: &VerifyError) -> Self {
+        ErrorCode::Verification
+    }
+}
+
+impl From<&BootError> for ErrorCode {
+    fn from(err: &BootError) -> Self {
+        match err {
+            BootError::Host(err) => err.into(),
+            _ => ErrorCode::Boot,
+        }
+    }
+}
+
+impl From<&HostError> for ErrorCode {
+    fn from(_: &HostError) -> Self {
+        ErrorCode::Host
+    }
+}
+
+/// Finds the code of the first error in the source chain of `err` that has one
+pub fn error_code(err: &(dyn Error + 'static)) -> ErrorCode {
+    fn code<T: Error + 'static>(err: &(dyn Error + 'static)) -> Option<ErrorCode>
+    where
+        for<'e> &'e T: Into<ErrorCode>,
+    {
+        err.downcast_ref::<T>().map(Into::into)
+    }
+
+    std::iter::successors(Some(err), |&err| err.source())
+        .find_map(|err| {
+            if let Some(err) = err.downcast_ref::<NeoBootError>() {
+                return Some(err.code());
+            }
+            if err.is::<MessagePackError>()
+                || err.is::<prost::DecodeError>()
+                || err.is::<ProtocolError>()
+            {
+                return Some(ErrorCode::Protocol);
+            }
+            code::<LwipError>(err)
+                .or_else(|| code::<ResolveError>(err))
+                .or_else(|| code::<SntpError>(err))
+                .or_else(|| code::<HttpError>(err))
+                .or_else(|| code::<TftpError>(err))
+                .or_else(|| code::<WebSocketError>(err))
+                .or_else(|| code::<VerifyError>(err))
+                .or_else(|| code::<BootError>(err))
+                .or_else(|| code::<HostError>(err))
+        })
+        .unwrap_or(ErrorCode::Unspecified)
+}
+
+/// Reports `err` to the client, after a description of what failed
+pub fn error_response(context: &str, err: &(dyn Error + 'static)) -> ErrorClientResponse {
+    ErrorClientResponse {
+        error: format!("{}: {}", context, err),
+        code: error_code(err) as i32,
+    }
+}
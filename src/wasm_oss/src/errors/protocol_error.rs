@@ -0,0 +1,21 @@
+#[derive(Debug)]
+pub enum ProtocolError {
+    /// The message ended in the middle of a field
+    Truncated,
+    /// A field holds a value the format does not allow
+    Malformed(&'static str),
+    /// A value is too long for its field
+    TooLong(&'static str),
+}
+
+impl std::fmt::Display for ProtocolError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Truncated => write!(f, "Message is truncated"),
+            Self::Malformed(what) => write!(f, "Malformed message: {}", what),
+            Self::TooLong(what) => write!(f, "{} is too long", what),
+        }
+    }
+}
+
+impl std::error::Error for ProtocolError {}
@@ -0,0 +1,73 @@
+/// Failures to use an HTTP proxy
+#[derive(Debug)]
+pub enum ProxyError {
+    /// The proxy URL could not be parsed
+    InvalidUrl(url::ParseError),
+    /// Only `http://` proxies are supported
+    UnsupportedScheme(String),
+    /// The proxy URL has no host
+    MissingHost,
+    /// The credentials in the proxy URL are not valid UTF-8 once decoded
+    InvalidCredentials(std::str::Utf8Error),
+    /// Writing the `CONNECT` request or reading the response failed
+    Io(std::io::Error),
+    /// The proxy closed the connection before responding
+    Closed,
+    /// The response headers exceed the size limit
+    ResponseTooLarge,
+    /// The proxy sent data past its response before the tunnel was used
+    UnexpectedData,
+    /// The response has no status line, with the line that was received
+    MalformedResponse(String),
+    /// The proxy requires credentials that were not given or were rejected
+    AuthenticationRequired,
+    /// The proxy refused to open the tunnel, with its status line
+    Refused(String),
+}
+
+impl std::fmt::Display for ProxyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidUrl(err) => write!(f, "Invalid proxy URL: {}", err),
+            Self::UnsupportedScheme(scheme) => write!(f, "Unsupported proxy scheme: {}", scheme),
+            Self::MissingHost => write!(f, "Missing host in proxy URL"),
+            Self::InvalidCredentials(err) => write!(f, "Invalid proxy credentials: {}", err),
+            Self::Io(err) => write!(f, "Proxy connection failed: {}", err),
+            Self::Closed => write!(f, "Proxy closed the connection"),
+            Self::ResponseTooLarge => write!(f, "Proxy response too large"),
+            Self::UnexpectedData => write!(f, "Unexpected data after the proxy response"),
+            Self::MalformedResponse(line) => write!(f, "Invalid proxy response: {}", line),
+            Self::AuthenticationRequired => write!(f, "Proxy authentication required"),
+            Self::Refused(line) => write!(f, "Proxy refused the tunnel: {}", line),
+        }
+    }
+}
+
+impl std::error::Error for ProxyError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::InvalidUrl(err) => Some(err),
+            Self::InvalidCredentials(err) => Some(err),
+            Self::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<url::ParseError> for ProxyError {
+    fn from(err: url::ParseError) -> Self {
+        Self::InvalidUrl(err)
+    }
+}
+
+impl From<std::str::Utf8Error> for ProxyError {
+    fn from(err: std::str::Utf8Error) -> Self {
+        Self::InvalidCredentials(err)
+    }
+}
+
+impl From<std::io::Error> for ProxyError {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
+    }
+}
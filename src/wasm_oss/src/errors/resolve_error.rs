@@ -0,0 +1,53 @@
+use super::lwip_error::LwipError;
+
+#[derive(Debug)]
+pub enum ResolveError {
+    /// The name cannot be put in a query, e.g. because a label is too long
+    InvalidName(String),
+    /// The server answered that the name does not exist
+    NotFound(String),
+    /// The name exists, but has no IPv4 address, for example when only CNAME
+    /// or AAAA records were returned
+    NoAddress(String),
+    /// The server could not answer the query, with the response code it sent
+    ServerFailure(u8),
+    /// No server answered in time
+    Timeout,
+    /// No DNS servers are configured
+    NoServers,
+    /// The query could not be sent or the reply could not be received
+    Network(LwipError),
+    /// No random query id could be drawn from the host
+    Entropy(getrandom::Error),
+}
+
+impl std::fmt::Display for ResolveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidName(name) => write!(f, "{} is not a valid host name", name),
+            Self::NotFound(name) => write!(f, "{} does not exist", name),
+            Self::NoAddress(name) => write!(f, "{} has no IPv4 address", name),
+            Self::ServerFailure(rcode) => write!(f, "Server failed with response code {}", rcode),
+            Self::Timeout => write!(f, "No server responded"),
+            Self::NoServers => write!(f, "No DNS servers configured"),
+            Self::Network(err) => write!(f, "Network error: {}", err),
+            Self::Entropy(err) => write!(f, "No random source: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for ResolveError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Network(err) => Some(err),
+            Self::Entropy(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<LwipError> for ResolveError {
+    fn from(err: LwipError) -> Self {
+        Self::Network(err)
+    }
+}
@@ -0,0 +1,79 @@
+use http::{Method, StatusCode};
+
+/// Failures of routing or handling a request on the HTTP server, each
+/// answered with a matching status code
+#[derive(Debug)]
+pub enum RouteError {
+    /// No route matches the path
+    NotFound,
+    /// Routes match the path, but only for these methods
+    MethodNotAllowed(Vec<Method>),
+    /// The credentials are missing or wrong
+    Unauthorized,
+    MissingHeader(&'static str),
+    InvalidHeader {
+        name: &'static str,
+        reason: String,
+    },
+    /// A path or query parameter is missing
+    MissingParam(&'static str),
+    /// A path or query parameter could not be parsed
+    InvalidParam {
+        name: &'static str,
+        reason: String,
+    },
+    /// The body could not be read or decoded
+    InvalidBody(String),
+    /// The request is malformed in another way
+    BadRequest(String),
+    /// The body exceeds the limit of the route
+    PayloadTooLarge(u64),
+    /// The request was valid, but could not be processed
+    Internal(String),
+    /// The server is at a limit, the request may succeed later
+    Unavailable(String),
+}
+
+impl RouteError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            Self::NotFound => StatusCode::NOT_FOUND,
+            Self::MethodNotAllowed(_) => StatusCode::METHOD_NOT_ALLOWED,
+            Self::Unauthorized => StatusCode::UNAUTHORIZED,
+            Self::MissingHeader(_)
+            | Self::InvalidHeader { .. }
+            | Self::MissingParam(_)
+            | Self::InvalidParam { .. }
+            | Self::InvalidBody(_)
+            | Self::BadRequest(_) => StatusCode::BAD_REQUEST,
+            Self::PayloadTooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            Self::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            Self::Unavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+        }
+    }
+}
+
+impl std::fmt::Display for RouteError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotFound => write!(f, "Not found"),
+            Self::MethodNotAllowed(_) => write!(f, "Method not allowed"),
+            Self::Unauthorized => write!(f, "Unauthorized"),
+            Self::MissingHeader(name) => write!(f, "Missing {} header", name),
+            Self::InvalidHeader { name, reason } => {
+                write!(f, "Invalid {} header: {}", name, reason)
+            }
+            Self::MissingParam(name) => write!(f, "Missing parameter {}", name),
+            Self::InvalidParam { name, reason } => {
+                write!(f, "Invalid parameter {}: {}", name, reason)
+            }
+            Self::InvalidBody(msg) => write!(f, "Invalid body: {}", msg),
+            Self::BadRequest(msg) => write!(f, "{}", msg),
+            Self::PayloadTooLarge(limit) => write!(f, "Body exceeds {} bytes", limit),
+            Self::Internal(msg) => write!(f, "{}", msg),
+            Self::Unavailable(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl std::error::Error for RouteError {}
@@ -0,0 +1,41 @@
+use super::lwip_error::LwipError;
+use super::protocol_error::ProtocolError;
+
+#[derive(Debug)]
+pub enum SntpError {
+    /// The underlying socket failed
+    Network(LwipError),
+    /// The server did not answer in time
+    Timeout,
+    /// The server is not synchronized itself, or told the client to back off
+    Unsynchronized,
+    /// The server sent a reply that does not follow the protocol
+    Protocol(ProtocolError),
+}
+
+impl std::fmt::Display for SntpError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Network(err) => write!(f, "Network error: {}", err),
+            Self::Timeout => write!(f, "Server did not respond"),
+            Self::Unsynchronized => write!(f, "Server is not synchronized"),
+            Self::Protocol(err) => write!(f, "Protocol error: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for SntpError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Network(err) => Some(err),
+            Self::Protocol(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<LwipError> for SntpError {
+    fn from(err: LwipError) -> Self {
+        Self::Network(err)
+    }
+}
@@ -0,0 +1,39 @@
+use super::lwip_error::LwipError;
+
+#[derive(Debug)]
+pub enum TftpError {
+    /// The underlying socket failed
+    Network(LwipError),
+    /// The server did not answer in time
+    Timeout,
+    /// The server aborted the transfer with an ERROR packet
+    Remote { code: u16, message: String },
+    /// The server sent a packet that does not follow the protocol
+    Protocol(String),
+}
+
+impl std::fmt::Display for TftpError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Network(err) => write!(f, "Network error: {}", err),
+            Self::Timeout => write!(f, "Server did not respond"),
+            Self::Remote { code, message } => write!(f, "Server error {}: {}", code, message),
+            Self::Protocol(msg) => write!(f, "Protocol error: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for TftpError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Network(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<LwipError> for TftpError {
+    fn from(err: LwipError) -> Self {
+        Self::Network(err)
+    }
+}
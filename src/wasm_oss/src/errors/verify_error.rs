@@ -0,0 +1,31 @@
+#[derive(Debug)]
+pub enum VerifyError {
+    /// More data was received than announced
+    TooLarge { expected: u64 },
+    /// The payload ended before reaching the announced size
+    SizeMismatch { expected: u64, actual: u64 },
+    /// The SHA-256 digest of the payload does not match
+    HashMismatch { expected: String, actual: String },
+}
+
+impl std::fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::TooLarge { expected } => {
+                write!(f, "Payload is larger than the expected {} bytes", expected)
+            }
+            Self::SizeMismatch { expected, actual } => write!(
+                f,
+                "Payload size mismatch: expected {} bytes, got {}",
+                expected, actual
+            ),
+            Self::HashMismatch { expected, actual } => write!(
+                f,
+                "Payload hash mismatch: expected {}, got {}",
+                expected, actual
+            ),
+        }
+    }
+}
+
+impl std::error::Error for VerifyError {}
@@ -0,0 +1,60 @@
+#[derive(Debug)]
+pub enum WebSocketError {
+    /// The opening handshake failed
+    Handshake(String),
+    /// The peer violated the protocol
+    Protocol(&'static str),
+    /// A text message or close reason is not valid UTF-8
+    InvalidUtf8,
+    /// A message exceeds the size limit
+    MessageTooLarge(usize),
+    /// A compressed message could not be inflated
+    Compression(String),
+    /// Reading from or writing to the connection failed
+    Io(std::io::Error),
+    /// The connection is closed
+    Closed,
+}
+
+impl WebSocketError {
+    /// Status code of the close frame that fails the connection (RFC 6455, section 7.4.1)
+    pub fn close_code(&self) -> Option<u16> {
+        match self {
+            Self::Protocol(_) | Self::Compression(_) => Some(1002),
+            Self::InvalidUtf8 => Some(1007),
+            Self::MessageTooLarge(_) => Some(1009),
+            Self::Handshake(_) | Self::Io(_) | Self::Closed => None,
+        }
+    }
+}
+
+impl std::fmt::Display for WebSocketError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Handshake(msg) => write!(f, "WebSocket handshake failed: {}", msg),
+            Self::Protocol(msg) => write!(f, "WebSocket protocol error: {}", msg),
+            Self::InvalidUtf8 => write!(f, "WebSocket text is not valid UTF-8"),
+            Self::MessageTooLarge(limit) => {
+                write!(f, "WebSocket message exceeds {} bytes", limit)
+            }
+            Self::Compression(msg) => write!(f, "Failed to inflate WebSocket message: {}", msg),
+            Self::Io(err) => write!(f, "WebSocket connection failed: {}", err),
+            Self::Closed => write!(f, "WebSocket is closed"),
+        }
+    }
+}
+
+impl std::error::Error for WebSocketError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for WebSocketError {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
+    }
+}
@@ -1,6 +1,7 @@
 use futures::lock::Mutex;
 use futures::task::ArcWake;
 use std::cell::RefCell;
+use std::collections::HashMap;
 use std::future::Future;
 use std::mem;
 use std::pin::Pin;
@@ -20,7 +21,9 @@ struct ExecutorInner<'a> {
     scheduled: mpsc::Receiver<Arc<Task<'a>>>,
     sender: mpsc::Sender<Arc<Task<'a>>>,
     exit_flag: bool,
-    exit_waker: Option<Waker>,
+    // Wakers of the tasks waiting for the exit, keyed by waiter
+    exit_wakers: HashMap<usize, Waker>,
+    next_waiter_id: usize,
 }
 
 #[derive(Clone)]
@@ -42,7 +45,8 @@ impl<'a> Executor<'a> {
                 scheduled,
                 sender,
                 exit_flag: false,
-                exit_waker: None,
+                exit_wakers: HashMap::new(),
+                next_waiter_id: 0,
             })),
         }
     }
@@ -78,8 +82,8 @@ impl<'a> Executor<'a> {
     pub fn exit(&self) {
         let mut inner = self.inner.borrow_mut();
         inner.exit_flag = true;
-        if let Some(waker) = inner.exit_waker.take() {
-            waker.wake_by_ref();
+        for (_, waker) in inner.exit_wakers.drain() {
+            waker.wake();
         }
     }
 
@@ -94,31 +98,60 @@ impl<'a> Executor<'a> {
     pub async fn wait_for_exit(&self) {
         struct WaitForExit<'a> {
             executor: Executor<'a>,
+            id: usize,
         }
 
         impl Future for WaitForExit<'_> {
             type Output = ();
 
             fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
-                let executor = self.executor.clone();
-                let mut inner = executor.inner.borrow_mut();
+                let mut inner = self.executor.inner.borrow_mut();
 
                 if inner.exit_flag {
                     return Poll::Ready(());
                 }
 
-                inner.exit_waker = Some(cx.waker().clone());
+                inner.exit_wakers.insert(self.id, cx.waker().clone());
                 Poll::Pending
             }
         }
 
+        impl Drop for WaitForExit<'_> {
+            fn drop(&mut self) {
+                self.executor
+                    .inner
+                    .borrow_mut()
+                    .exit_wakers
+                    .remove(&self.id);
+            }
+        }
+
+        let id = {
+            let mut inner = self.inner.borrow_mut();
+            inner.next_waiter_id = inner.next_waiter_id.wrapping_add(1);
+            inner.next_waiter_id
+        };
+
         WaitForExit {
             executor: self.clone(),
+            id,
         }
         .await;
     }
 }
 
+/// Lets hyper spawn the tasks of HTTP/2 connections, one per stream
+impl<'a, F> hyper::rt::Executor<F> for Executor<'a>
+where
+    F: Future + 'a,
+{
+    fn execute(&self, future: F) {
+        self.spawn(async move {
+            future.await;
+        });
+    }
+}
+
 struct TaskFuture<'a> {
     future: Pin<Box<dyn Future<Output = ()> + 'a>>,
     poll: Poll<()>,
@@ -16,11 +16,20 @@ unsafe extern "C" {
     pub fn env_net_setup() -> i32;
     pub fn env_net_teardown() -> i32;
     pub fn env_net_rx() -> i32;
-    // pub fn env_max_sockets() -> i32;
-    // pub fn env_used_sockets() -> i32;
+    pub fn env_net_dhcp_start() -> i32; // Start DHCP discovery on the interface
+    pub fn env_net_dhcp_poll() -> i32; // Returns 0 once a lease is bound, or an error code
+    pub fn env_net_dhcp_renew() -> i32; // Renew the current lease
+    pub fn env_net_dhcp_lease_time() -> u32; // Lease time in seconds, 0 without a lease
+    pub fn env_net_set_static(addr: u32, netmask: u32, gateway: u32) -> i32;
+    pub fn env_net_get_addr() -> u32;
+    pub fn env_net_get_netmask() -> u32;
+    pub fn env_net_get_gateway() -> u32;
+    pub fn env_max_sockets() -> i32; // Size of the host's socket pool
+    pub fn env_used_sockets() -> i32; // Sockets of the pool currently in use
 
     // DNS
     pub fn env_net_dns_set_server(server_addr: u32);
+    pub fn env_net_dns_get_server(index: u32) -> u32; // DNS server offered by DHCP, 0 if unset
     pub fn env_net_dns_lookup(hostname: *const u8, len: u32) -> i32;
     pub fn env_net_dns_lookup_poll() -> i32;
     pub fn env_net_dns_lookup_result() -> u32;
@@ -36,6 +45,34 @@ unsafe extern "C" {
     pub fn env_net_socket_accept(socket: i32) -> i32;
     pub fn env_net_socket_accept_poll(socket: i32) -> i32;
     pub fn env_net_socket_read(socket: i32, buf: *const u8, len: u32) -> i32;
-    pub fn env_net_socket_write(socket: i32, buf: *const u8, len: u32) -> i32;
+    pub fn env_net_socket_write(socket: i32, buf: *const u8, len: u32) -> i32; // Returns the number of bytes queued, 0 while the send buffer is full
+    pub fn env_net_socket_sndbuf(socket: i32) -> i32; // Free space in the TCP send buffer
     pub fn env_net_socket_write_poll(socket: i32) -> i32;
+    pub fn env_net_socket_sendto(
+        socket: i32,
+        buf: *const u8,
+        len: u32,
+        addr: u32,
+        port: u32,
+    ) -> i32; // Send a single datagram to the given address
+    pub fn env_net_socket_recvfrom(
+        socket: i32,
+        buf: *const u8,
+        len: u32,
+        addr: *mut u32,
+        port: *mut u32,
+    ) -> i32; // Receive a single datagram and its source address
+    pub fn env_net_socket_peer_addr(socket: i32, addr: *mut u32, port: *mut u32) -> i32;
+    pub fn env_net_socket_local_addr(socket: i32, addr: *mut u32, port: *mut u32) -> i32;
+    pub fn env_net_socket_set_nodelay(socket: i32, enabled: u32) -> i32; // Disable Nagle's algorithm
+    pub fn env_net_socket_set_keepalive(
+        socket: i32,
+        enabled: u32,
+        idle_ms: u32,
+        interval_ms: u32,
+        count: u32,
+    ) -> i32;
+    pub fn env_net_socket_shutdown(socket: i32, how: u32) -> i32; // 0 = read, 1 = write, 2 = both
+    pub fn env_net_socket_join_multicast(socket: i32, group: u32) -> i32;
+    pub fn env_net_socket_leave_multicast(socket: i32, group: u32) -> i32;
 }
@@ -2,16 +2,21 @@ use commands::{
     boot::BootCommandHandler,
     chain::{self, ChainCommandHandler},
     help::{self, HelpCommandHandler},
+    netstat::NetstatCommandHandler,
+    network::NetworkCommandHandler,
     nonce::{self, NonceCommandHandler},
     print::{self, PrintCommandHandler},
     quit::{self, QuitCommandHandler},
+    status::StatusCommandHandler,
+    tftp::TftpCommandHandler,
     CommandDispatcher,
 };
 use executor::Executor;
 use log::error;
 use proto_rs::schema::{
-    BootClientRequest, ChainClientRequest, HelpClientRequest, NonceClientRequest,
-    PrintClientRequest, QuitClientRequest,
+    BootClientRequest, ChainClientRequest, HelpClientRequest, NetstatClientRequest,
+    NetworkClientRequest, NonceClientRequest, PrintClientRequest, QuitClientRequest,
+    StatusClientRequest, TftpClientRequest,
 };
 use services::ServiceRegistry;
 use std::{cell::RefCell, rc::Rc};
@@ -19,6 +24,7 @@ use utils::sys_print;
 
 mod asyncio;
 mod commands;
+mod configuration;
 mod controllers;
 mod errors;
 mod executor;
@@ -41,7 +47,14 @@ pub extern "C" fn main() {
         }
     }
 
-    // Setup network
+    // Load configuration
+    let config = configuration::Configuration::load();
+
+    // Queue logs for forwarding before anything else is set up
+    let syslog_service =
+        services::syslog::SyslogService::new(config.syslog.clone(), &config.device);
+
+    // Setup network, the interface is configured by the network service
     let setup_result = unsafe { ffi::env_net_setup() };
     if setup_result != 0 {
         log::error!("Failed to setup network: {}", setup_result);
@@ -53,6 +66,7 @@ pub extern "C" fn main() {
 
     // Setup controllers
     let mut boot_controller = controllers::boot::BootController::new();
+    let network_controller = controllers::network::NetworkController::new(config.network);
     {
         // Setup command dispatcher
         let mut dispatcher = CommandDispatcher::new();
@@ -64,12 +78,35 @@ pub extern "C" fn main() {
         dispatcher.register_handler::<BootClientRequest>(BootCommandHandler {
             boot_controller: boot_controller.clone(),
         });
+        dispatcher.register_handler::<NetworkClientRequest>(NetworkCommandHandler {
+            network_controller: network_controller.clone(),
+        });
+        dispatcher.register_handler::<NetstatClientRequest>(NetstatCommandHandler {});
+        dispatcher.register_handler::<TftpClientRequest>(TftpCommandHandler {
+            boot_controller: boot_controller.clone(),
+        });
+        dispatcher.register_handler::<StatusClientRequest>(StatusCommandHandler {
+            executor: executor.clone(),
+            network_controller: network_controller.clone(),
+        });
         let dispatcher = Rc::new(RefCell::new(dispatcher));
 
         // Setup service registry
         let mut service_registry = ServiceRegistry::new();
+        service_registry.register(services::network::NetworkService::new(
+            network_controller.clone(),
+        ));
         service_registry.register(services::console::ConsoleService::new(dispatcher.clone()));
-        service_registry.register(services::server::ServerService::new(dispatcher.clone()));
+        service_registry.register(services::server::ServerService::new(
+            dispatcher.clone(),
+            config.server.clone(),
+        ));
+        service_registry.register(services::mdns::MdnsService::new(
+            &config.device,
+            config.server.port,
+        ));
+        service_registry.register(services::time::TimeService::new(config.time.clone()));
+        service_registry.register(syslog_service);
         service_registry.spawn_all(&executor);
 
         // Run executor
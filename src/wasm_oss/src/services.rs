@@ -4,7 +4,11 @@ use std::future::Future;
 use std::pin::Pin;
 
 pub mod console;
+pub mod mdns;
+pub mod network;
 pub mod server;
+pub mod syslog;
+pub mod time;
 
 pub trait Service<'a> {
     fn name(&self) -> &'static str;
@@ -0,0 +1,367 @@
+use crate::asyncio::dns::message::{
+    Message, Question, Record, RecordData, CLASS_IN, CLASS_MDNS_FLAG, FLAG_AUTHORITATIVE,
+    FLAG_RESPONSE, TYPE_A, TYPE_ANY, TYPE_PTR, TYPE_SRV, TYPE_TXT,
+};
+use crate::asyncio::net::UdpSocket;
+use crate::asyncio::sleep_ms;
+use crate::configuration::DeviceConfig;
+use crate::executor::Executor;
+use crate::ffi;
+use crate::services::server::rpc::RPC_PATH;
+use crate::utils::u32_to_ip_addr;
+use futures::{
+    future::{select, Either},
+    FutureExt,
+};
+use log::{error, info, warn};
+use std::{error::Error, future::Future, net::Ipv4Addr, pin::Pin};
+
+const MDNS_GROUP: Ipv4Addr = Ipv4Addr::new(224, 0, 0, 251);
+const MDNS_PORT: u16 = 5353;
+
+const SERVICE_TYPE: &str = "_neoboot._tcp.local";
+/// Meta-query used to enumerate service types (RFC 6763, section 9)
+const SERVICE_ENUMERATION: &str = "_services._dns-sd._udp.local";
+
+/// TTLs recommended by RFC 6762, section 10
+const HOST_RECORD_TTL: u32 = 120;
+const OTHER_RECORD_TTL: u32 = 4500;
+/// Maximum TTL in responses to legacy unicast queries (RFC 6762, section 6.7)
+const LEGACY_UNICAST_TTL: u32 = 10;
+
+const ANNOUNCE_COUNT: usize = 2;
+const ANNOUNCE_INTERVAL_MS: u64 = 1000;
+/// How often the interface address is checked for changes
+const POLL_INTERVAL_MS: u64 = 250;
+
+const MAX_PACKET_SIZE: usize = 1500;
+
+/// mDNS responder that answers queries for `<device-id>.local` and advertises
+/// the RPC server as a `_neoboot._tcp` DNS-SD service.
+///
+/// Probing is skipped, device ids are expected to be unique on the link.
+pub struct MdnsService {
+    port: u16,
+    hostname: String,
+    instance: String,
+    txt: Vec<String>,
+}
+
+impl MdnsService {
+    pub fn new(device: &DeviceConfig, port: u16) -> Self {
+        Self {
+            port,
+            hostname: format!("{}.local", device.id),
+            instance: format!("{}.{}", device.id, SERVICE_TYPE),
+            txt: vec![
+                "txtvers=1".to_string(),
+                format!("id={}", device.id),
+                format!("firmware={}", device.firmware_hash),
+                format!("arch={}", device.arch),
+                format!("security={}", device.security_mode),
+                format!("path={}", RPC_PATH),
+            ],
+        }
+    }
+
+    fn record(name: &str, rtype: u16, ttl: u32, unique: bool, data: RecordData) -> Record {
+        Record {
+            name: name.to_string(),
+            rtype,
+            // Unique records flush stale entries from the caches of other hosts
+            rclass: if unique {
+                CLASS_IN | CLASS_MDNS_FLAG
+            } else {
+                CLASS_IN
+            },
+            ttl,
+            data,
+        }
+    }
+
+    fn address_record(&self, addr: Ipv4Addr) -> Record {
+        Self::record(
+            &self.hostname,
+            TYPE_A,
+            HOST_RECORD_TTL,
+            true,
+            RecordData::A(addr),
+        )
+    }
+
+    fn service_record(&self) -> Record {
+        Self::record(
+            SERVICE_TYPE,
+            TYPE_PTR,
+            OTHER_RECORD_TTL,
+            false,
+            RecordData::Ptr(self.instance.clone()),
+        )
+    }
+
+    fn srv_record(&self) -> Record {
+        Self::record(
+            &self.instance,
+            TYPE_SRV,
+            HOST_RECORD_TTL,
+            true,
+            RecordData::Srv {
+                priority: 0,
+                weight: 0,
+                port: self.port,
+                target: self.hostname.clone(),
+            },
+        )
+    }
+
+    fn txt_record(&self) -> Record {
+        Self::record(
+            &self.instance,
+            TYPE_TXT,
+            OTHER_RECORD_TTL,
+            true,
+            RecordData::Txt(self.txt.clone()),
+        )
+    }
+
+    fn enumeration_record(&self) -> Record {
+        Self::record(
+            SERVICE_ENUMERATION,
+            TYPE_PTR,
+            OTHER_RECORD_TTL,
+            false,
+            RecordData::Ptr(SERVICE_TYPE.to_string()),
+        )
+    }
+
+    /// Builds an unsolicited response announcing all records, or withdrawing
+    /// them when `goodbye` is set
+    fn announcement(&self, addr: Ipv4Addr, goodbye: bool) -> Message {
+        let mut answers = vec![
+            self.address_record(addr),
+            self.service_record(),
+            self.srv_record(),
+            self.txt_record(),
+        ];
+        if goodbye {
+            for record in answers.iter_mut() {
+                record.ttl = 0;
+            }
+        }
+
+        Message {
+            flags: FLAG_RESPONSE | FLAG_AUTHORITATIVE,
+            answers,
+            ..Default::default()
+        }
+    }
+
+    /// Builds the response to a query, if any of the questions concern us
+    fn respond(&self, query: &Message, addr: Ipv4Addr, legacy: bool) -> Option<Message> {
+        let mut answers = Vec::new();
+        let mut additionals = Vec::new();
+
+        for question in &query.questions {
+            let name = question.name.to_ascii_lowercase();
+            let matches = |rtype: u16| question.qtype == rtype || question.qtype == TYPE_ANY;
+
+            if name == self.hostname && matches(TYPE_A) {
+                answers.push(self.address_record(addr));
+            } else if name == SERVICE_TYPE && matches(TYPE_PTR) {
+                answers.push(self.service_record());
+                additionals.extend([
+                    self.srv_record(),
+                    self.txt_record(),
+                    self.address_record(addr),
+                ]);
+            } else if name == self.instance {
+                if matches(TYPE_SRV) {
+                    answers.push(self.srv_record());
+                    additionals.push(self.address_record(addr));
+                }
+                if matches(TYPE_TXT) {
+                    answers.push(self.txt_record());
+                }
+            } else if name == SERVICE_ENUMERATION && matches(TYPE_PTR) {
+                answers.push(self.enumeration_record());
+            }
+        }
+
+        // Known-answer suppression (RFC 6762, section 7.1)
+        answers.retain(|record| {
+            !query.answers.iter().any(|known| {
+                known.name.eq_ignore_ascii_case(&record.name)
+                    && known.rtype == record.rtype
+                    && known.data == record.data
+                    && known.ttl >= record.ttl / 2
+            })
+        });
+        if answers.is_empty() {
+            return None;
+        }
+        additionals.retain(|record| !answers.contains(record));
+        additionals.dedup();
+
+        if legacy {
+            // Legacy resolvers expect a conventional unicast DNS response
+            for record in answers.iter_mut().chain(additionals.iter_mut()) {
+                record.rclass &= !CLASS_MDNS_FLAG;
+                record.ttl = record.ttl.min(LEGACY_UNICAST_TTL);
+            }
+        }
+
+        Some(Message {
+            id: if legacy { query.id } else { 0 },
+            flags: FLAG_RESPONSE | FLAG_AUTHORITATIVE,
+            questions: if legacy {
+                query
+                    .questions
+                    .iter()
+                    .map(|question| Question {
+                        qclass: question.qclass & !CLASS_MDNS_FLAG,
+                        ..question.clone()
+                    })
+                    .collect()
+            } else {
+                vec![]
+            },
+            answers,
+            authorities: vec![],
+            additionals,
+        })
+    }
+
+    fn handle_packet(
+        &self,
+        socket: &UdpSocket,
+        packet: &[u8],
+        source: Ipv4Addr,
+        source_port: u16,
+        addr: Ipv4Addr,
+    ) -> Result<(), Box<dyn Error>> {
+        let query = match Message::decode(packet) {
+            Ok(query) if !query.is_response() && query.opcode() == 0 => query,
+            // Ignore responses from other hosts and malformed packets
+            _ => return Ok(()),
+        };
+
+        // Queries not sent from the mDNS port come from simple unicast resolvers
+        let legacy = source_port != MDNS_PORT;
+        let response = match self.respond(&query, addr, legacy) {
+            Some(response) => response.encode()?,
+            None => return Ok(()),
+        };
+
+        let unicast = query
+            .questions
+            .iter()
+            .all(|question| question.qclass & CLASS_MDNS_FLAG != 0);
+        if legacy || unicast {
+            socket.send_to(&response, source, source_port)?;
+        } else {
+            socket.send_to(&response, MDNS_GROUP, MDNS_PORT)?;
+        }
+
+        Ok(())
+    }
+
+    fn announce(&self, socket: &UdpSocket, addr: Ipv4Addr, goodbye: bool) {
+        let packet = match self.announcement(addr, goodbye).encode() {
+            Ok(packet) => packet,
+            Err(e) => {
+                warn!("Failed to encode mDNS announcement: {}", e);
+                return;
+            }
+        };
+        if let Err(e) = socket.send_to(&packet, MDNS_GROUP, MDNS_PORT) {
+            warn!("Failed to send mDNS announcement: {}", e);
+        }
+    }
+
+    fn current_addr() -> Option<Ipv4Addr> {
+        let addr = u32_to_ip_addr(unsafe { ffi::env_net_get_addr() });
+        (!addr.is_unspecified()).then_some(addr)
+    }
+}
+
+impl<'a> super::Service<'a> for MdnsService {
+    fn name(&self) -> &'static str {
+        "mdns"
+    }
+
+    fn run(self: Box<Self>, executor: Executor<'a>) -> Pin<Box<dyn Future<Output = ()> + 'a>> {
+        Box::pin(async move {
+            let socket = match UdpSocket::bind(Ipv4Addr::UNSPECIFIED, MDNS_PORT) {
+                Ok(socket) => socket,
+                Err(e) => {
+                    error!("Failed to bind mDNS socket: {}", e);
+                    return;
+                }
+            };
+            if let Err(e) = socket.join_multicast(MDNS_GROUP) {
+                error!("Failed to join mDNS multicast group: {}", e);
+                return;
+            }
+            info!("Advertising {} as {}", self.hostname, self.instance);
+
+            let mut buf = vec![0u8; MAX_PACKET_SIZE];
+            let mut addr = None;
+            let mut announcements_left = 0;
+            let mut next_announcement = 0;
+
+            loop {
+                // Announce again whenever the address changes
+                let current = Self::current_addr();
+                if current != addr {
+                    addr = current;
+                    announcements_left = ANNOUNCE_COUNT;
+                    next_announcement = 0;
+                }
+
+                let now = unsafe { ffi::env_now() };
+                if let Some(addr) = addr {
+                    if announcements_left > 0 && now >= next_announcement {
+                        self.announce(&socket, addr, false);
+                        announcements_left -= 1;
+                        next_announcement = now + ANNOUNCE_INTERVAL_MS;
+                    }
+                }
+
+                let recv_fut = socket.recv_from(&mut buf).boxed();
+                let wait_fut = select(
+                    sleep_ms(POLL_INTERVAL_MS).boxed(),
+                    executor.wait_for_exit().boxed(),
+                );
+
+                let received = match select(recv_fut, wait_fut).await {
+                    Either::Left((received, _)) => Some(received),
+                    Either::Right((Either::Left(_), _)) => None,
+                    Either::Right((Either::Right(_), _)) => {
+                        // Withdraw the records so clients do not keep stale entries
+                        if let Some(addr) = addr {
+                            self.announce(&socket, addr, true);
+                        }
+                        let _ = socket.leave_multicast(MDNS_GROUP);
+                        return;
+                    }
+                };
+
+                match (received, addr) {
+                    (Some(Ok((len, source, source_port))), Some(addr)) => {
+                        if let Err(e) =
+                            self.handle_packet(&socket, &buf[..len], source, source_port, addr)
+                        {
+                            warn!("Failed to answer mDNS query: {}", e);
+                        }
+                    }
+                    (Some(Err(e)), _) => {
+                        error!("Failed to receive mDNS packet: {}", e);
+                        sleep_ms(POLL_INTERVAL_MS).await;
+                    }
+                    // Nothing to answer with until the interface has an address
+                    _ => (),
+                }
+            }
+        })
+    }
+}
@@ -0,0 +1,81 @@
+use crate::asyncio::sleep_ms;
+use crate::controllers::network::{Lease, NetworkController};
+use crate::executor::Executor;
+use futures::{
+    future::{select, Either},
+    lock::Mutex,
+    FutureExt,
+};
+use log::error;
+use std::{future::Future, pin::Pin, sync::Arc};
+
+/// Upper bound between checks, so that leases obtained after a reconfiguration
+/// over RPC are renewed as well
+const CHECK_INTERVAL_MS: u64 = 1000;
+
+/// Delay before retrying after the interface could not be configured
+const RETRY_INTERVAL_MS: u64 = 10_000;
+
+/// Configures the interface on startup and keeps the DHCP lease alive while
+/// the bootloader waits for commands
+pub struct NetworkService {
+    network_controller: Arc<Mutex<NetworkController>>,
+}
+
+impl NetworkService {
+    pub fn new(network_controller: Arc<Mutex<NetworkController>>) -> Self {
+        Self { network_controller }
+    }
+
+    /// Configures the interface or renews the lease when due, returning how
+    /// long to wait before checking again
+    async fn maintain(&self) -> u64 {
+        let renew_in_ms = {
+            let controller = self.network_controller.lock().await;
+            // A reconfiguration over RPC is in progress and reports its own errors
+            if controller.is_configuring() {
+                return CHECK_INTERVAL_MS;
+            }
+            controller.lease().map(Lease::renew_in_ms)
+        };
+        let result = match renew_in_ms {
+            // Not configured yet, or the last attempt failed
+            None => NetworkController::apply(&self.network_controller)
+                .await
+                .map(|_| ()),
+            Some(Some(0)) => NetworkController::renew(&self.network_controller)
+                .await
+                .map(|_| ()),
+            Some(Some(delay)) => return delay.min(CHECK_INTERVAL_MS),
+            // Static configuration, nothing to renew
+            Some(None) => return CHECK_INTERVAL_MS,
+        };
+
+        match result {
+            Ok(()) => 0,
+            Err(e) => {
+                error!("Failed to configure network: {}", e);
+                RETRY_INTERVAL_MS
+            }
+        }
+    }
+}
+
+impl<'a> super::Service<'a> for NetworkService {
+    fn name(&self) -> &'static str {
+        "network"
+    }
+
+    fn run(self: Box<Self>, executor: Executor<'a>) -> Pin<Box<dyn Future<Output = ()> + 'a>> {
+        Box::pin(async move {
+            loop {
+                let delay = self.maintain().await;
+                if let Either::Right(_) =
+                    select(sleep_ms(delay).boxed(), executor.wait_for_exit().boxed()).await
+                {
+                    return;
+                }
+            }
+        })
+    }
+}
@@ -1,170 +1,286 @@
-use crate::asyncio::net::TcpListener;
-use crate::asyncio::{http::stream::AnyHttpStream, net::TcpStream};
+use crate::asyncio::http::router::middleware::{AccessLog, BearerAuth, RequestIds};
+use crate::asyncio::http::router::{full, RouteResult, Router};
+use crate::asyncio::http::stream::{AnyHttpStream, Rewind};
+use crate::asyncio::net::{self, TcpKeepalive, TcpListener, TcpStream};
+use crate::asyncio::sleep::HyperTimer;
+use crate::asyncio::sleep_ms;
 use crate::commands::CommandDispatcher;
+use crate::configuration::ServerConfig;
 use crate::errors::lwip_error::LwipError;
 use crate::executor::Executor;
-use base64::prelude::*;
-use bytes::Bytes;
 use futures::future::{select, Either};
-use futures::FutureExt;
-use futures_lite::StreamExt;
-use http::{Method, Request, Response, StatusCode};
-use http_body_util::{BodyExt, Full};
-use hyper::header::HeaderValue;
-use hyper::{body::Incoming, server::conn::http1::Builder, service::service_fn};
-use log::error;
-use prost::Message;
-use proto_rs::schema::ClientRequest;
-use std::cell::RefCell;
+use futures::{AsyncReadExt, FutureExt};
+use http::Request;
+use hyper::server::conn::{http1, http2};
+use hyper::{body::Incoming, service::service_fn};
+use log::{error, info, warn};
+use std::cell::{Cell, RefCell};
+use std::convert::Infallible;
 use std::future::Future;
 use std::net::Ipv4Addr;
-use std::pin::Pin;
+use std::pin::{pin, Pin};
 use std::rc::Rc;
+use std::time::Duration;
+
+#[cfg(feature = "upload_bench")]
+pub mod bench;
+pub mod events;
+pub mod rpc;
+#[cfg(feature = "websocket_echo")]
+pub mod ws_echo;
+
+/// Time given to in-flight responses to complete once the executor exits
+const SHUTDOWN_GRACE_MS: u64 = 2000;
+
+/// Interval at which a free connection slot is checked for
+const SLOT_POLL_INTERVAL_MS: u64 = 10;
+
+/// Connection preface of HTTP/2 clients (RFC 9113, section 3.4)
+const HTTP2_PREFACE: &[u8; 24] = b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n";
+
+/// Requests multiplexed on an HTTP/2 connection at the same time
+const HTTP2_MAX_STREAMS: u32 = 8;
+
+/// Flow control window of each HTTP/2 stream, which bounds the data buffered for it
+const HTTP2_STREAM_WINDOW_SIZE: u32 = 256 * 1024;
+
+/// Counts a connection as active until its task ends, or until it carries
+/// an event stream, which is limited by `max_event_streams` instead
+struct ConnectionGuard {
+    active: Rc<Cell<usize>>,
+    counted: Cell<bool>,
+}
+
+impl ConnectionGuard {
+    fn new(active: Rc<Cell<usize>>) -> Self {
+        active.set(active.get() + 1);
+        Self {
+            active,
+            counted: Cell::new(true),
+        }
+    }
+
+    /// Frees the slot of the connection for others
+    fn release(&self) {
+        if self.counted.replace(false) {
+            self.active.set(self.active.get() - 1);
+        }
+    }
+}
+
+impl Drop for ConnectionGuard {
+    fn drop(&mut self) {
+        self.release();
+    }
+}
 
 /// HTTP server service that handles incoming connections and routes requests
 pub struct ServerService<'a> {
     listener: Option<TcpListener>,
     dispatcher: Rc<RefCell<CommandDispatcher<'a>>>,
+    config: ServerConfig,
+    active_connections: Rc<Cell<usize>>,
 }
 
 impl<'a> ServerService<'a> {
     /// Creates a new ServerService instance
-    pub fn new(dispatcher: Rc<RefCell<CommandDispatcher<'a>>>) -> Self {
+    pub fn new(dispatcher: Rc<RefCell<CommandDispatcher<'a>>>, config: ServerConfig) -> Self {
         Self {
             listener: None,
             dispatcher,
+            config,
+            active_connections: Rc::new(Cell::new(0)),
         }
     }
 
+    /// Reads as much of the stream as needed to tell whether it starts with
+    /// the HTTP/2 connection preface, returning the bytes read
+    async fn sniff_http2(stream: &mut TcpStream) -> std::io::Result<(bool, Vec<u8>)> {
+        let mut read = Vec::with_capacity(HTTP2_PREFACE.len());
+        let mut buf = [0u8; HTTP2_PREFACE.len()];
+        while read.len() < HTTP2_PREFACE.len() {
+            let n = stream
+                .read(&mut buf[..HTTP2_PREFACE.len() - read.len()])
+                .await?;
+            if n == 0 {
+                break;
+            }
+            read.extend_from_slice(&buf[..n]);
+            if !HTTP2_PREFACE.starts_with(&read) {
+                return Ok((false, read));
+            }
+        }
+        Ok((read == HTTP2_PREFACE, read))
+    }
+
     /// Handles an incoming HTTP connection
     async fn handle_connection(
         dispatcher: Rc<RefCell<CommandDispatcher<'a>>>,
+        router: Rc<Router<'a>>,
         executor: Executor<'a>,
-        tcp_stream: AnyHttpStream<TcpStream>,
+        mut tcp_stream: TcpStream,
+        header_timeout_ms: u64,
+        guard: Rc<ConnectionGuard>,
     ) -> Result<(), Box<dyn std::error::Error>> {
+        let exit_executor = executor.clone();
+
+        // Clients with prior knowledge of HTTP/2 support open with its preface.
+        // Clients that send nothing are dropped, so they do not hold a slot.
+        let sniff_fut = Self::sniff_http2(&mut tcp_stream).boxed_local();
+        let wait_fut = select(
+            sleep_ms(header_timeout_ms).boxed(),
+            exit_executor.wait_for_exit().boxed(),
+        );
+        let (http2, prefix) = match select(sniff_fut, wait_fut).await {
+            Either::Left((result, _)) => result?,
+            Either::Right((Either::Left(_), _)) => {
+                info!("Closing connection that sent no request");
+                return Ok(());
+            }
+            Either::Right((Either::Right(_), _)) => return Ok(()),
+        };
+        let stream = AnyHttpStream::Http(Rewind::new(prefix, tcp_stream));
+
+        let service_executor = executor.clone();
         let service = service_fn(move |req: Request<Incoming>| {
+            let response = router.handle(req);
             let dispatcher = dispatcher.clone();
-            let executor = executor.clone();
+            let executor = service_executor.clone();
+            let guard = guard.clone();
             async move {
-                let response = Self::handle_request(dispatcher.clone(), req).await;
+                let response = response.await;
+                // Event streams stay open as long as the client listens, so they
+                // must not keep the slot from other requests
+                if events::is_event_stream(&response) {
+                    guard.release();
+                }
                 dispatcher
                     .borrow()
                     .finalize_shutdown_if_requested(&executor);
-                response
+                Ok::<_, Infallible>(response)
             }
         });
 
-        let mut http = Builder::new();
-        http.keep_alive(false);
-        http.max_buf_size(8192);
-        if let Err(err) = http.serve_connection(tcp_stream, service).await {
+        let result = if http2 {
+            let mut http = http2::Builder::new(executor.clone());
+            http.max_concurrent_streams(HTTP2_MAX_STREAMS);
+            http.initial_stream_window_size(HTTP2_STREAM_WINDOW_SIZE);
+            let connection = pin!(http.serve_connection(stream, service));
+            Self::drive_connection(connection, |c| c.graceful_shutdown(), &exit_executor).await
+        } else {
+            let mut http = http1::Builder::new();
+            http.keep_alive(true);
+            http.max_buf_size(8192);
+            // Also bounds the wait for the next request of a kept-alive connection
+            http.timer(HyperTimer);
+            http.header_read_timeout(Duration::from_millis(header_timeout_ms));
+            // Upgraded connections, such as WebSockets, are handed over to their own task
+            let connection = pin!(http.serve_connection(stream, service).with_upgrades());
+            Self::drive_connection(connection, |c| c.graceful_shutdown(), &exit_executor).await
+        };
+        if let Err(err) = result {
             error!("Failed to serve connection: {err:#}");
         }
 
         Ok(())
     }
 
-    /// Processes an HTTP request and returns an appropriate response
-    async fn handle_request(
-        dispatcher: Rc<RefCell<CommandDispatcher<'a>>>,
-        mut req: Request<Incoming>,
-    ) -> Result<Response<Full<Bytes>>, hyper::Error> {
-        let mut response = Response::new(Full::default());
-
-        match (req.method(), req.uri().path()) {
-            // Root route - help information
-            (&Method::GET, "/") => {
-                // TODO: Add a more detailed help message, including the version of the server, client configuration, root public key, etc.
-                *response.body_mut() = Full::from("Welcome to NeoBoot Local HTTP Server\n\nAvailable endpoints:\n- GET /: This help message\n- POST /api/v1/rpc: RPC service endpoint for client requests\n\nServer is running on port 8080\n");
+    /// Serves a connection until it is closed or the executor exits
+    async fn drive_connection<C>(
+        mut connection: Pin<&mut C>,
+        graceful_shutdown: impl FnOnce(Pin<&mut C>),
+        exit_executor: &Executor<'a>,
+    ) -> Result<(), hyper::Error>
+    where
+        C: Future<Output = Result<(), hyper::Error>>,
+    {
+        match select(connection.as_mut(), exit_executor.wait_for_exit().boxed()).await {
+            Either::Left((result, _)) => result,
+            Either::Right((_, _)) => {
+                // Let the response in flight complete, e.g. the one to a boot request
+                graceful_shutdown(connection.as_mut());
+                match select(connection, sleep_ms(SHUTDOWN_GRACE_MS).boxed()).await {
+                    Either::Left((result, _)) => result,
+                    Either::Right((_, _)) => Ok(()),
+                }
             }
+        }
+    }
 
-            // RPC service route
-            (&Method::POST, "/api/v1/rpc") => {
-                let client_request = match req.headers().get("X-Client-Request") {
-                    Some(header) => match header.to_str() {
-                        Ok(str) => str,
-                        Err(_) => {
-                            *response.status_mut() = StatusCode::BAD_REQUEST;
-                            *response.body_mut() = Full::from("Invalid header encoding");
-                            return Ok(response);
-                        }
-                    },
-                    None => {
-                        *response.status_mut() = StatusCode::BAD_REQUEST;
-                        *response.body_mut() = Full::from("Missing X-Client-Request header");
-                        return Ok(response);
-                    }
-                };
+    /// Routes of the server, each endpoint is a module of this one
+    fn router(&self, executor: &Executor<'a>) -> Router<'a> {
+        let dispatcher = self.dispatcher.clone();
+        let dispatcher_v2 = self.dispatcher.clone();
+        let events_executor = executor.clone();
+        let event_streams = events::EventStreams::new(self.config.max_event_streams);
+        let router = Router::new()
+            .post(rpc::RPC_PATH, move |req| {
+                rpc::handle(dispatcher.clone(), req)
+            })
+            .post(rpc::RPC_V2_PATH, move |req| {
+                rpc::handle_v2(dispatcher_v2.clone(), req)
+            })
+            .get(events::EVENTS_PATH, move |req| {
+                events::handle(req, events_executor.clone(), event_streams.clone())
+            });
 
-                let client_request =
-                    match base64::engine::general_purpose::STANDARD.decode(client_request) {
-                        Ok(decoded) => decoded,
-                        Err(err) => {
-                            *response.status_mut() = StatusCode::BAD_REQUEST;
-                            *response.body_mut() =
-                                Full::from(format!("Invalid base64 encoding: {}", err));
-                            return Ok(response);
-                        }
-                    };
-
-                let client_request: ClientRequest =
-                    match ClientRequest::decode(client_request.as_slice()) {
-                        Ok(client_request) => client_request,
-                        Err(err) => {
-                            *response.status_mut() = StatusCode::BAD_REQUEST;
-                            *response.body_mut() =
-                                Full::from(format!("Invalid ClientRequest encoding: {}", err));
-                            return Ok(response);
-                        }
-                    };
-
-                let stream = Some(req.body_mut().into_data_stream().boxed());
-                let client_response =
-                    match dispatcher.borrow().dispatch(&client_request, stream).await {
-                        Ok(result) => result,
-                        Err(err) => {
-                            *response.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
-                            *response.body_mut() =
-                                Full::from(format!("Error dispatching request: {}", err));
-                            return Ok(response);
-                        }
-                    };
-
-                // Encode the client response as a base64 string, as place it in the header
-                let encoded_response = base64::engine::general_purpose::STANDARD
-                    .encode(client_response.encode_to_vec());
-                let header_map = response.headers_mut();
-                header_map.insert(
-                    "X-Client-Response",
-                    HeaderValue::from_str(&encoded_response).unwrap(),
-                );
-
-                *response.status_mut() = StatusCode::OK;
-            }
+        #[cfg(feature = "upload_bench")]
+        let router = router.post(bench::BENCH_PATH, bench::handle);
 
-            // Catch-all 404 for unhandled routes
-            _ => {
-                *response.status_mut() = StatusCode::NOT_FOUND;
-            }
+        #[cfg(feature = "websocket_echo")]
+        let router = {
+            let ws_executor = executor.clone();
+            router.get(ws_echo::WS_ECHO_PATH, move |req| {
+                ws_echo::handle(req, ws_executor.clone())
+            })
+        };
+
+        // Everything but the help message requires the token, if one is configured
+        let router = match &self.config.auth_token {
+            Some(token) => router.route_layer(BearerAuth::new(token)),
+            None => router,
         };
 
-        Ok(response)
+        let port = self.config.port;
+        let max_event_streams = self.config.max_event_streams;
+        router
+            .get("/", move |req| help(req, port, max_event_streams))
+            .layer(AccessLog)
+            .layer(RequestIds::new())
     }
 }
 
+async fn help(_: Request<Incoming>, port: u16, max_event_streams: usize) -> RouteResult {
+    // TODO: Add a more detailed help message, including the version of the server, client configuration, root public key, etc.
+    Ok(full(format!("Welcome to NeoBoot Local HTTP Server\n\nAvailable endpoints:\n- GET /: This help message\n- POST /api/v1/rpc: RPC service endpoint, request in the X-Client-Request header\n- POST /api/v2/rpc: RPC service endpoint, length-delimited request and response in the body\n- GET /api/v1/events: Server-Sent Events stream of logs and boot progress, at most {} at a time\n\nServer is running on port {}\n", max_event_streams, port)))
+}
+
 impl<'a> super::Service<'a> for ServerService<'a> {
     fn name(&self) -> &'static str {
         "server"
     }
 
     fn run(mut self: Box<Self>, executor: Executor<'a>) -> Pin<Box<dyn Future<Output = ()> + 'a>> {
-        const DEFAULT_PORT: u16 = 8080;
         let addr = Ipv4Addr::UNSPECIFIED;
 
-        self.listener = Some(TcpListener::bind(addr.to_string().as_str(), DEFAULT_PORT).unwrap());
+        self.listener = Some(
+            TcpListener::bind_with_backlog(addr, self.config.port, self.config.backlog).unwrap(),
+        );
+        let max_connections = self.config.max_connections.max(1);
+        let header_timeout_ms = self.config.header_timeout_ms;
+        let router = Rc::new(self.router(&executor));
 
         Box::pin(async move {
             loop {
+                // Leave further connections queued in the listener backlog while all slots are taken
+                while self.active_connections.get() >= max_connections {
+                    let sleep_fut = sleep_ms(SLOT_POLL_INTERVAL_MS).boxed();
+                    let exit_fut = executor.wait_for_exit().boxed();
+                    if let Either::Right(_) = select(sleep_fut, exit_fut).await {
+                        return;
+                    }
+                }
+
                 let accept_fut = self.listener.as_ref().unwrap().accept().boxed();
                 let exit_fut = executor.wait_for_exit().boxed();
 
@@ -175,23 +291,48 @@ impl<'a> super::Service<'a> for ServerService<'a> {
 
                 match accept {
                     Ok(stream) => {
-                        let tcp_stream = AnyHttpStream::Http(stream);
-
-                        if let Err(err) = Self::handle_connection(
-                            self.dispatcher.clone(),
-                            executor.clone(),
-                            tcp_stream,
-                        )
-                        .await
-                        {
-                            error!("Failed to handle connection: {err:?}");
+                        match stream.peer_addr() {
+                            Ok(peer) => info!("Accepted connection from {}", peer),
+                            Err(err) => warn!("Failed to get peer address: {err}"),
+                        }
+
+                        // Detect clients that vanish in the middle of long uploads
+                        if let Err(err) = stream.set_keepalive(Some(TcpKeepalive::default())) {
+                            warn!("Failed to enable keepalive: {err}");
+                        }
+                        if let Err(err) = stream.set_nodelay(true) {
+                            warn!("Failed to disable Nagle's algorithm: {err}");
                         }
+
+                        // Serve each connection on its own task, so a slow client does not block others
+                        let guard = Rc::new(ConnectionGuard::new(self.active_connections.clone()));
+                        let dispatcher = self.dispatcher.clone();
+                        let router = router.clone();
+                        let connection_executor = executor.clone();
+                        executor.spawn(async move {
+                            if let Err(err) = Self::handle_connection(
+                                dispatcher,
+                                router,
+                                connection_executor,
+                                stream,
+                                header_timeout_ms,
+                                guard,
+                            )
+                            .await
+                            {
+                                error!("Failed to handle connection: {err:?}");
+                            }
+                        });
                     }
                     Err(err) => {
                         if err == LwipError::ConnectionAborted {
                             return;
                         }
-                        error!("Failed to accept connection: {err:?}");
+                        let stats = net::stats();
+                        error!(
+                            "Failed to accept connection: {err:?} ({} open sockets, {:?} of {:?} host sockets in use)",
+                            stats.open_sockets, stats.used_sockets, stats.max_sockets
+                        );
                     }
                 }
             }
@@ -0,0 +1,26 @@
+use crate::asyncio::http::router::{full, RouteResult};
+use crate::errors::route_error::RouteError;
+use crate::ffi;
+use http::Request;
+use http_body_util::BodyExt;
+use hyper::body::Incoming;
+
+/// Path of the upload benchmark endpoint
+pub const BENCH_PATH: &str = "/api/v1/bench/upload";
+
+/// Discards the body and reports the elapsed time
+pub async fn handle(mut req: Request<Incoming>) -> RouteResult {
+    let start = unsafe { ffi::env_now() };
+    let mut received = 0;
+    while let Some(frame) = req.body_mut().frame().await {
+        let frame = frame.map_err(|e| RouteError::InvalidBody(e.to_string()))?;
+        if let Ok(data) = frame.into_data() {
+            received += data.len();
+        }
+    }
+    let elapsed = unsafe { ffi::env_now() } - start;
+    Ok(full(format!(
+        "Received {} bytes in {} ms\n",
+        received, elapsed
+    )))
+}
@@ -0,0 +1,156 @@
+use crate::asyncio::http::router::extract::RequestExt;
+use crate::asyncio::http::router::{ResponseBody, RouteResult};
+use crate::asyncio::sleep_ms;
+use crate::errors::route_error::RouteError;
+use crate::executor::Executor;
+use crate::utils::events;
+use bytes::Bytes;
+use futures::channel::mpsc;
+use futures::future::{select, Either};
+use futures::{FutureExt, SinkExt};
+use futures_lite::StreamExt;
+use http::{Request, Response};
+use http_body_util::{BodyExt, StreamBody};
+use hyper::body::{Frame, Incoming};
+use hyper::header::HeaderValue;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// Path of the Server-Sent Events endpoint
+pub const EVENTS_PATH: &str = "/api/v1/events";
+
+/// Interval of the comments sent on idle event streams, so dead clients are noticed
+const EVENTS_KEEPALIVE_MS: u64 = 15_000;
+
+/// Chunks of an event stream buffered before the producer waits for the client
+const EVENTS_CHANNEL_SIZE: usize = 16;
+
+/// Event streams open at the same time, limited separately from the
+/// connections of the server, as each stays open as long as its client listens.
+/// The count is shared with the response bodies, which must be `Send`.
+#[derive(Clone)]
+pub struct EventStreams {
+    open: Arc<AtomicUsize>,
+    max: usize,
+}
+
+impl EventStreams {
+    pub fn new(max: usize) -> Self {
+        Self {
+            open: Arc::new(AtomicUsize::new(0)),
+            max,
+        }
+    }
+
+    /// Takes a slot for a new stream, `None` if all are taken
+    fn acquire(&self) -> Option<StreamSlot> {
+        self.open
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |open| {
+                (open < self.max).then_some(open + 1)
+            })
+            .ok()?;
+        Some(StreamSlot(self.open.clone()))
+    }
+}
+
+/// Frees the slot of an event stream when dropped
+struct StreamSlot(Arc<AtomicUsize>);
+
+impl Drop for StreamSlot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Whether `response` is an event stream, which may stay open indefinitely
+pub fn is_event_stream(response: &Response<ResponseBody>) -> bool {
+    response
+        .headers()
+        .get(hyper::header::CONTENT_TYPE)
+        .is_some_and(|value| value == "text/event-stream")
+}
+
+/// Streams events as Server-Sent Events, starting after the id in the
+/// `Last-Event-ID` header, or with all events still kept without it.
+/// Answers 503 once `streams` are all taken.
+pub async fn handle<'a>(
+    req: Request<Incoming>,
+    executor: Executor<'a>,
+    streams: EventStreams,
+) -> RouteResult {
+    let last_event_id: Option<u64> = req.header("Last-Event-ID")?;
+    let slot = streams.acquire().ok_or_else(|| {
+        RouteError::Unavailable(format!("{} event streams already open", streams.max))
+    })?;
+
+    // The body must not borrow the executor, so the events are produced by a task
+    let (sender, receiver) = mpsc::channel(EVENTS_CHANNEL_SIZE);
+    executor.spawn(produce_events(last_event_id, sender, executor.clone()));
+
+    // The body holds the slot, hyper drops it as soon as the client is gone
+    let body = StreamBody::new(receiver.map(move |chunk| {
+        let _slot = &slot;
+        Ok(Frame::data(chunk))
+    }));
+    let mut response = Response::new(body.boxed_unsync());
+    let headers = response.headers_mut();
+    headers.insert(
+        hyper::header::CONTENT_TYPE,
+        HeaderValue::from_static("text/event-stream"),
+    );
+    headers.insert(
+        hyper::header::CACHE_CONTROL,
+        HeaderValue::from_static("no-cache"),
+    );
+    Ok(response)
+}
+
+/// Sends the events after `last_id` to `sender` until the client goes away.
+/// When the executor exits, the events published until then are still sent.
+async fn produce_events(
+    mut last_id: Option<u64>,
+    mut sender: mpsc::Sender<Bytes>,
+    executor: Executor<'_>,
+) {
+    // An id from before a restart is newer than any event, so all events are sent
+    last_id = last_id.filter(|id| *id <= events::last_id());
+    let mut exiting = false;
+    loop {
+        let (pending, missed) = events::since(last_id);
+        if missed > 0 {
+            let chunk = format!("event: dropped\ndata: {{\"count\":{}}}\n\n", missed);
+            if sender.send(Bytes::from(chunk)).await.is_err() {
+                return;
+            }
+        }
+        for event in pending {
+            last_id = Some(event.id);
+            let chunk = format!(
+                "id: {}\nevent: {}\ndata: {}\n\n",
+                event.id,
+                event.name(),
+                event.data()
+            );
+            if sender.send(Bytes::from(chunk)).await.is_err() {
+                return;
+            }
+        }
+        if exiting {
+            return;
+        }
+
+        let wait_fut = select(
+            events::wait_after(last_id.unwrap_or(0)).boxed(),
+            sleep_ms(EVENTS_KEEPALIVE_MS).boxed(),
+        );
+        match select(wait_fut, executor.wait_for_exit().boxed()).await {
+            Either::Left((Either::Left(_), _)) => (),
+            Either::Left((Either::Right(_), _)) => {
+                if sender.send(Bytes::from(": keep-alive\n\n")).await.is_err() {
+                    return;
+                }
+            }
+            Either::Right(_) => exiting = true,
+        }
+    }
+}
@@ -0,0 +1,145 @@
+use crate::asyncio::http::router::extract::RequestExt;
+use crate::asyncio::http::router::{full, RouteResult};
+use crate::commands::{CommandDispatcher, HandleStream};
+use crate::errors::route_error::RouteError;
+use base64::prelude::*;
+use bytes::{Bytes, BytesMut};
+use futures_lite::{stream, StreamExt};
+use http::Request;
+use http_body_util::BodyExt;
+use hyper::body::Incoming;
+use hyper::header::HeaderValue;
+use prost::Message;
+use proto_rs::schema::{ClientRequest, ClientResponse};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// Path of the RPC endpoint taking the request in a header
+pub const RPC_PATH: &str = "/api/v1/rpc";
+
+/// Path of the RPC endpoint taking the request framed in the body
+pub const RPC_V2_PATH: &str = "/api/v2/rpc";
+
+/// Largest `ClientRequest` accepted in front of the payload, enough for a
+/// signed request with its certificate chain
+const MAX_CLIENT_REQUEST_SIZE: usize = 64 * 1024;
+
+/// Longest varint a protobuf length prefix can be encoded in
+const MAX_VARINT_SIZE: usize = 10;
+
+/// Content type of the framed messages of the v2 endpoint
+const FRAMED_CONTENT_TYPE: &str = "application/x-protobuf";
+
+/// Header carrying the base64-encoded `ClientRequest`, as the body carries its payload
+const CLIENT_REQUEST_HEADER: &str = "X-Client-Request";
+
+/// Header carrying the base64-encoded `ClientResponse`
+const CLIENT_RESPONSE_HEADER: &str = "X-Client-Response";
+
+/// Dispatches the request in the `X-Client-Request` header, streaming the
+/// body to the command, and returns the response in `X-Client-Response`
+pub async fn handle(
+    dispatcher: Rc<RefCell<CommandDispatcher<'_>>>,
+    mut req: Request<Incoming>,
+) -> RouteResult {
+    let client_request: ClientRequest = req.protobuf_header(CLIENT_REQUEST_HEADER)?;
+
+    let stream = Some(req.body_mut().into_data_stream().boxed());
+    let client_response = dispatch(&dispatcher, &client_request, stream).await?;
+
+    // Encode the client response as a base64 string, as place it in the header
+    let encoded_response = BASE64_STANDARD.encode(client_response.encode_to_vec());
+    let encoded_response = HeaderValue::from_str(&encoded_response)
+        .map_err(|err| RouteError::Internal(format!("Error encoding response: {}", err)))?;
+    let mut response = full("");
+    response
+        .headers_mut()
+        .insert(CLIENT_RESPONSE_HEADER, encoded_response);
+    Ok(response)
+}
+
+/// Dispatches the length-delimited `ClientRequest` at the start of the body,
+/// streaming the rest of the body to the command, and returns the
+/// length-delimited `ClientResponse` as the response body
+pub async fn handle_v2(
+    dispatcher: Rc<RefCell<CommandDispatcher<'_>>>,
+    req: Request<Incoming>,
+) -> RouteResult {
+    let mut body = req.into_body().into_data_stream();
+    let mut buffer = BytesMut::new();
+    let (prefix_len, request_len) = loop {
+        if let Some((prefix_len, request_len)) = request_frame(&buffer)? {
+            if buffer.len() >= prefix_len + request_len {
+                break (prefix_len, request_len);
+            }
+        }
+        match body.next().await {
+            Some(Ok(chunk)) => buffer.extend_from_slice(&chunk),
+            Some(Err(err)) => {
+                return Err(RouteError::BadRequest(format!(
+                    "Error reading request: {}",
+                    err
+                )))
+            }
+            None => {
+                return Err(RouteError::InvalidBody(
+                    "Truncated client request".to_string(),
+                ))
+            }
+        }
+    };
+
+    // Whatever was read past the request already belongs to the payload
+    let payload_head = buffer.split_off(prefix_len + request_len).freeze();
+    let client_request = ClientRequest::decode(&buffer[prefix_len..])
+        .map_err(|err| RouteError::InvalidBody(format!("Invalid client request: {}", err)))?;
+
+    let head = (!payload_head.is_empty()).then_some(Ok::<Bytes, hyper::Error>(payload_head));
+    let stream = Some(stream::iter(head).chain(body).boxed());
+    let client_response = dispatch(&dispatcher, &client_request, stream).await?;
+
+    let mut response = full(client_response.encode_length_delimited_to_vec());
+    response.headers_mut().insert(
+        hyper::header::CONTENT_TYPE,
+        HeaderValue::from_static(FRAMED_CONTENT_TYPE),
+    );
+    Ok(response)
+}
+
+/// Dispatches `client_request` with the payload in `stream`
+async fn dispatch<'a: 's, 's>(
+    dispatcher: &RefCell<CommandDispatcher<'a>>,
+    client_request: &ClientRequest,
+    stream: Option<HandleStream<'s>>,
+) -> Result<ClientResponse, RouteError> {
+    dispatcher
+        .borrow()
+        .dispatch(client_request, stream)
+        .await
+        .map_err(|err| RouteError::Internal(format!("Error dispatching request: {}", err)))
+}
+
+/// Returns the length of the varint prefix and of the `ClientRequest` at the
+/// start of `buffer`, or `None` while the prefix is still incomplete
+fn request_frame(buffer: &[u8]) -> Result<Option<(usize, usize)>, RouteError> {
+    let Some(end) = buffer
+        .iter()
+        .take(MAX_VARINT_SIZE)
+        .position(|byte| byte & 0x80 == 0)
+    else {
+        if buffer.len() >= MAX_VARINT_SIZE {
+            return Err(RouteError::InvalidBody(
+                "Invalid client request length".to_string(),
+            ));
+        }
+        return Ok(None);
+    };
+
+    let request_len = prost::decode_length_delimiter(&buffer[..=end]).map_err(|err| {
+        RouteError::InvalidBody(format!("Invalid client request length: {}", err))
+    })?;
+    if request_len > MAX_CLIENT_REQUEST_SIZE {
+        return Err(RouteError::PayloadTooLarge(MAX_CLIENT_REQUEST_SIZE as u64));
+    }
+    Ok(Some((end + 1, request_len)))
+}
@@ -0,0 +1,68 @@
+use crate::asyncio::http::router::RouteResult;
+use crate::asyncio::http::stream::{AnyHttpStream, Rewind};
+use crate::asyncio::http::websocket::handshake::{self, PendingWebSocket};
+use crate::asyncio::http::websocket::{CloseFrame, Message, WebSocketConfig, CLOSE_GOING_AWAY};
+use crate::asyncio::net::TcpStream;
+use crate::errors::route_error::RouteError;
+use crate::executor::Executor;
+use futures::future::{select, Either};
+use futures::FutureExt;
+use http::Request;
+use http_body_util::BodyExt;
+use hyper::body::Incoming;
+use log::warn;
+
+/// Path of the WebSocket echo endpoint, for testing the WebSocket implementation
+pub const WS_ECHO_PATH: &str = "/api/v1/ws/echo";
+
+/// Accepts the upgrade and echoes the messages on a task of its own
+pub async fn handle<'a>(mut req: Request<Incoming>, executor: Executor<'a>) -> RouteResult {
+    let (response, pending) = handshake::upgrade(&mut req, WebSocketConfig::default())
+        .map_err(|err| RouteError::BadRequest(err.to_string()))?;
+    executor.spawn(echo(pending, executor.clone()));
+    Ok(response.map(BodyExt::boxed_unsync))
+}
+
+/// Sends every text and binary message back until the peer closes the connection
+async fn echo(pending: PendingWebSocket, executor: Executor<'_>) {
+    let mut socket = match pending.accept::<AnyHttpStream<Rewind<TcpStream>>>().await {
+        Ok(socket) => socket,
+        Err(err) => {
+            warn!("WebSocket upgrade failed: {}", err);
+            return;
+        }
+    };
+
+    loop {
+        let recv_fut = socket.recv().boxed_local();
+        let message = match select(recv_fut, executor.wait_for_exit().boxed()).await {
+            Either::Left((message, _)) => Some(message),
+            Either::Right((_, _)) => None,
+        };
+        let message = match message {
+            Some(Some(Ok(message))) => message,
+            Some(Some(Err(err))) => {
+                warn!("WebSocket connection failed: {}", err);
+                return;
+            }
+            Some(None) => return,
+            None => {
+                let frame = CloseFrame {
+                    code: CLOSE_GOING_AWAY,
+                    reason: String::new(),
+                };
+                let _ = socket.close(Some(frame)).await;
+                return;
+            }
+        };
+
+        let result = match message {
+            Message::Text(_) | Message::Binary(_) => socket.send(message).await,
+            _ => Ok(()),
+        };
+        if let Err(err) = result {
+            warn!("WebSocket connection failed: {}", err);
+            return;
+        }
+    }
+}
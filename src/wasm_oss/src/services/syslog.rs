@@ -0,0 +1,269 @@
+use crate::asyncio::dns::GLOBAL_DNS_RESOLVER;
+use crate::asyncio::net::{TcpStream, UdpSocket};
+use crate::asyncio::sleep_ms;
+use crate::configuration::{DeviceConfig, SyslogConfig, SyslogTransport};
+use crate::executor::Executor;
+use crate::ffi;
+use crate::services::time::wall_clock;
+use crate::utils::logging::{self, QueuedRecord};
+use futures::{
+    future::{select, Either},
+    AsyncWriteExt, FutureExt,
+};
+use log::{info, warn, Level};
+use std::{error::Error, future::Future, net::IpAddr, net::Ipv4Addr, pin::Pin};
+
+const APP_NAME: &str = "neoboot";
+/// User-level messages (RFC 5424, section 6.2.1)
+const FACILITY: u8 = 1;
+
+/// How often the queue is checked for new records
+const POLL_INTERVAL_MS: u64 = 100;
+/// Delay before reconnecting after the collector could not be reached
+const RETRY_INTERVAL_MS: u64 = 10_000;
+/// Time given to forward the remaining records on exit
+const FLUSH_TIMEOUT_MS: u64 = 500;
+
+/// Messages are truncated to fit a single datagram without fragmentation
+const MAX_MESSAGE_SIZE: usize = 1200;
+
+enum Connection {
+    Udp(UdpSocket),
+    Tcp(TcpStream),
+}
+
+/// Forwards log records to a remote syslog collector (RFC 5424).
+///
+/// Records are queued by the logger from the moment the service is created,
+/// so the ones logged before the network is up are sent once it is.
+pub struct SyslogService {
+    config: SyslogConfig,
+    hostname: String,
+    /// Number of the last message sent, reported as the meta sequenceId
+    sequence: u32,
+}
+
+impl SyslogService {
+    pub fn new(config: SyslogConfig, device: &DeviceConfig) -> Self {
+        if config.server.is_some() {
+            logging::enable_forwarding();
+        }
+
+        Self {
+            config,
+            hostname: device.id.clone(),
+            sequence: 0,
+        }
+    }
+
+    async fn connect(&self, server: &str) -> Result<Connection, Box<dyn Error>> {
+        let addr = GLOBAL_DNS_RESOLVER
+            .lookup(server)
+            .await?
+            .into_iter()
+            .find_map(|addr| match addr {
+                IpAddr::V4(addr) => Some(addr),
+                IpAddr::V6(_) => None,
+            })
+            .ok_or_else(|| format!("No IPv4 address for {}", server))?;
+
+        Ok(match self.config.transport {
+            SyslogTransport::Udp => {
+                let socket = UdpSocket::bind(Ipv4Addr::UNSPECIFIED, 0)?;
+                socket.connect(addr, self.config.port)?;
+                Connection::Udp(socket)
+            }
+            SyslogTransport::Tcp => {
+                Connection::Tcp(TcpStream::connect(addr, self.config.port).await?)
+            }
+        })
+    }
+
+    fn format(&mut self, record: &QueuedRecord) -> Vec<u8> {
+        let severity = match record.level {
+            Level::Error => 3,
+            Level::Warn => 4,
+            Level::Info => 6,
+            Level::Debug | Level::Trace => 7,
+        };
+
+        // The timestamp is only known once the wall clock is synced
+        let now = unsafe { ffi::env_now() };
+        let timestamp = wall_clock()
+            .unix_ms
+            .map(|unix_ms| format_timestamp(unix_ms.saturating_sub(now - record.timestamp_ms)))
+            .unwrap_or_else(|| "-".to_string());
+
+        // Sequence ids range from 1 to 2^31 - 1 (RFC 5424, section 7.3.1)
+        self.sequence = self.sequence % (i32::MAX as u32) + 1;
+
+        let mut message = format!(
+            "<{}>1 {} {} {} - - [meta sequenceId=\"{}\" sysUpTime=\"{}\"] {}: {}",
+            FACILITY * 8 + severity,
+            timestamp,
+            self.hostname,
+            APP_NAME,
+            self.sequence,
+            // sysUpTime is in hundredths of a second
+            record.timestamp_ms / 10,
+            record.module,
+            record.message
+        );
+        if message.len() > MAX_MESSAGE_SIZE {
+            let mut end = MAX_MESSAGE_SIZE;
+            while !message.is_char_boundary(end) {
+                end -= 1;
+            }
+            message.truncate(end);
+        }
+
+        message.into_bytes()
+    }
+
+    async fn send(connection: &mut Connection, message: &[u8]) -> Result<(), Box<dyn Error>> {
+        match connection {
+            Connection::Udp(socket) => {
+                socket.send(message).await?;
+            }
+            Connection::Tcp(stream) => {
+                // Octet counting, each message is prefixed by its length (RFC 6587, section 3.4.1)
+                let mut frame = format!("{} ", message.len()).into_bytes();
+                frame.extend_from_slice(message);
+                stream.write_all(&frame).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Sends all queued records, putting back the one that failed on errors
+    async fn flush(&mut self, connection: &mut Connection) -> Result<(), Box<dyn Error>> {
+        let dropped = logging::take_dropped();
+        if dropped > 0 {
+            let record = QueuedRecord {
+                level: Level::Warn,
+                timestamp_ms: unsafe { ffi::env_now() },
+                module: module_path!().to_string(),
+                message: format!("{} log records were dropped", dropped),
+            };
+            let message = self.format(&record);
+            if let Err(e) = Self::send(connection, &message).await {
+                logging::restore_dropped(dropped);
+                return Err(e);
+            }
+        }
+
+        while let Some(record) = logging::pop_forwarded() {
+            let message = self.format(&record);
+            if let Err(e) = Self::send(connection, &message).await {
+                logging::requeue_forwarded(record);
+                return Err(e);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<'a> super::Service<'a> for SyslogService {
+    fn name(&self) -> &'static str {
+        "syslog"
+    }
+
+    fn run(mut self: Box<Self>, executor: Executor<'a>) -> Pin<Box<dyn Future<Output = ()> + 'a>> {
+        Box::pin(async move {
+            let Some(server) = self.config.server.clone() else {
+                return;
+            };
+            info!(
+                "Forwarding logs to {}:{} over {:?}",
+                server, self.config.port, self.config.transport
+            );
+
+            let mut connection = None;
+            // Only the first failure of an outage is logged, so retries do not
+            // fill the queue with their own warnings
+            let mut failing = false;
+
+            loop {
+                let network_up = unsafe { ffi::env_net_get_addr() } != 0;
+                if connection.is_none() && network_up {
+                    let connect_fut = self.connect(&server).boxed_local();
+                    match select(connect_fut, executor.wait_for_exit().boxed()).await {
+                        Either::Left((Ok(new_connection), _)) => connection = Some(new_connection),
+                        Either::Left((Err(e), _)) => {
+                            if !failing {
+                                warn!("Failed to connect to syslog server {}: {}", server, e);
+                                failing = true;
+                            }
+                        }
+                        Either::Right((_, _)) => return,
+                    }
+                }
+
+                if let Some(active) = connection.as_mut() {
+                    let flush_fut = self.flush(active).boxed_local();
+                    let flushed = match select(flush_fut, executor.wait_for_exit().boxed()).await {
+                        Either::Left((flushed, _)) => flushed,
+                        Either::Right((_, _)) => break,
+                    };
+                    match flushed {
+                        Ok(()) => failing = false,
+                        Err(e) => {
+                            if !failing {
+                                warn!("Failed to forward logs to {}: {}", server, e);
+                                failing = true;
+                            }
+                            connection = None;
+                        }
+                    }
+                }
+
+                let delay = if connection.is_none() && network_up {
+                    RETRY_INTERVAL_MS
+                } else {
+                    POLL_INTERVAL_MS
+                };
+                if let Either::Right(_) =
+                    select(sleep_ms(delay).boxed(), executor.wait_for_exit().boxed()).await
+                {
+                    break;
+                }
+            }
+
+            // Forward what was logged while shutting down, without holding up the exit
+            if let Some(mut active) = connection {
+                let flush_fut = self.flush(&mut active).boxed_local();
+                let _ = select(flush_fut, sleep_ms(FLUSH_TIMEOUT_MS).boxed()).await;
+            }
+        })
+    }
+}
+
+/// Formats ms since the Unix epoch as an RFC 3339 UTC timestamp
+fn format_timestamp(unix_ms: u64) -> String {
+    let days = (unix_ms / 86_400_000) as i64;
+    let ms_of_day = unix_ms % 86_400_000;
+
+    // Civil date from days since the epoch, after Howard Hinnant's algorithm
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        ms_of_day / 3_600_000,
+        ms_of_day / 60_000 % 60,
+        ms_of_day / 1000 % 60,
+        ms_of_day % 1000
+    )
+}
@@ -0,0 +1,244 @@
+use crate::asyncio::dns::GLOBAL_DNS_RESOLVER;
+use crate::asyncio::sleep_ms;
+use crate::asyncio::sntp::{self, Sample};
+use crate::configuration::TimeConfig;
+use crate::executor::Executor;
+use crate::ffi;
+use futures::{
+    future::{select, Either},
+    FutureExt,
+};
+use log::{info, warn};
+use once_cell::sync::Lazy;
+use std::{
+    future::Future,
+    net::{IpAddr, Ipv4Addr},
+    pin::Pin,
+    sync::Mutex,
+};
+
+/// Delay before retrying after no server could be reached
+const RETRY_INTERVAL_MS: u64 = 10_000;
+
+/// Addresses queried per configured server, e.g. for pools resolving to several hosts
+const ADDRESSES_PER_SERVER: usize = 2;
+const MAX_SOURCES: usize = 6;
+
+/// Maximum difference between the offsets of two sources that agree
+const AGREEMENT_MS: i64 = 500;
+
+/// Bound on the drift of the local clock, ppm
+const MAX_DRIFT_PPM: f64 = 500.0;
+/// Minimum time between two syncs to estimate the drift from
+const MIN_DRIFT_INTERVAL_MS: u64 = 60_000;
+/// Assumed error of the drift estimate, used to grow the error bound over time
+const DRIFT_ERROR_PPM: u64 = 100;
+
+static CLOCK: Lazy<Mutex<Option<ClockState>>> = Lazy::new(|| Mutex::new(None));
+
+/// How much the wall clock can be trusted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Confidence {
+    /// No server has been reached yet
+    Unsynced,
+    /// The time comes from a single server, or servers that disagree
+    SingleSource,
+    /// At least two servers agreed on the time
+    MultiSource,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WallClock {
+    /// Milliseconds since the Unix epoch, `None` while unsynced
+    pub unix_ms: Option<u64>,
+    pub confidence: Confidence,
+    /// Estimated bound on the error of `unix_ms`
+    pub error_ms: u64,
+}
+
+struct ClockState {
+    /// Unix time minus monotonic time at the last sync
+    offset_ms: i64,
+    /// Monotonic time of the last sync
+    synced_at: u64,
+    drift_ppm: Option<f64>,
+    error_ms: u64,
+    confidence: Confidence,
+}
+
+/// Returns the current wall-clock time along with how much it can be trusted.
+///
+/// SNTP is not authenticated, so even a multi-source time should only be used
+/// where an attacker on the network shifting the clock is acceptable.
+pub fn wall_clock() -> WallClock {
+    let now = unsafe { ffi::env_now() };
+    let clock = CLOCK.lock().unwrap();
+    let Some(state) = clock.as_ref() else {
+        return WallClock {
+            unix_ms: None,
+            confidence: Confidence::Unsynced,
+            error_ms: u64::MAX,
+        };
+    };
+
+    let elapsed = now.saturating_sub(state.synced_at);
+    let correction = state
+        .drift_ppm
+        .map_or(0, |drift| (drift * elapsed as f64 / 1_000_000.0) as i64);
+    let unix_ms = now as i64 + state.offset_ms + correction;
+
+    WallClock {
+        unix_ms: u64::try_from(unix_ms).ok(),
+        confidence: state.confidence,
+        error_ms: state.error_ms + elapsed * DRIFT_ERROR_PPM / 1_000_000,
+    }
+}
+
+/// Combines samples from several sources into an offset, error bound and confidence
+fn combine(samples: &[Sample]) -> Option<(i64, u64, Confidence)> {
+    // Find the largest group of sources that agree with each other
+    let cluster = samples
+        .iter()
+        .map(|center| {
+            samples
+                .iter()
+                .filter(|sample| (sample.offset_ms - center.offset_ms).abs() <= AGREEMENT_MS)
+                .collect::<Vec<_>>()
+        })
+        .max_by_key(|cluster| cluster.len())?;
+
+    if cluster.len() < 2 {
+        if samples.len() > 1 {
+            warn!("Time sources disagree: {:?}", samples);
+        }
+        // Fall back to the source with the shortest round trip
+        let best = samples.iter().min_by_key(|sample| sample.delay_ms)?;
+        return Some((best.offset_ms, best.delay_ms / 2, Confidence::SingleSource));
+    }
+
+    let mut offsets: Vec<i64> = cluster.iter().map(|sample| sample.offset_ms).collect();
+    offsets.sort_unstable();
+    let median = offsets[offsets.len() / 2];
+    let spread = (offsets[offsets.len() - 1] - offsets[0]) as u64;
+    let max_delay = cluster.iter().map(|sample| sample.delay_ms).max()?;
+
+    Some((median, max_delay / 2 + spread, Confidence::MultiSource))
+}
+
+/// Keeps the wall clock synced against the configured SNTP servers
+pub struct TimeService {
+    config: TimeConfig,
+}
+
+impl TimeService {
+    pub fn new(config: TimeConfig) -> Self {
+        Self { config }
+    }
+
+    async fn sources(&self) -> Vec<Ipv4Addr> {
+        let mut sources = Vec::new();
+        for server in &self.config.servers {
+            match GLOBAL_DNS_RESOLVER.lookup(server).await {
+                Ok(addrs) => sources.extend(
+                    addrs
+                        .into_iter()
+                        .filter_map(|addr| match addr {
+                            IpAddr::V4(addr) => Some(addr),
+                            IpAddr::V6(_) => None,
+                        })
+                        .take(ADDRESSES_PER_SERVER),
+                ),
+                Err(e) => warn!("Failed to resolve time server {}: {}", server, e),
+            }
+        }
+
+        sources.sort_unstable();
+        sources.dedup();
+        sources.truncate(MAX_SOURCES);
+        sources
+    }
+
+    /// Queries all sources and updates the clock, returning whether it succeeded
+    async fn sync(&self) -> bool {
+        let mut samples = Vec::new();
+        for source in self.sources().await {
+            match sntp::query(source).await {
+                Ok(sample) => samples.push(sample),
+                Err(e) => warn!("SNTP query to {} failed: {}", source, e),
+            }
+        }
+
+        let Some((offset_ms, error_ms, confidence)) = combine(&samples) else {
+            return false;
+        };
+
+        let now = unsafe { ffi::env_now() };
+        let mut clock = CLOCK.lock().unwrap();
+        let drift_ppm = match clock.as_ref() {
+            Some(previous) if now - previous.synced_at >= MIN_DRIFT_INTERVAL_MS => {
+                let elapsed = (now - previous.synced_at) as f64;
+                let measured = (offset_ms - previous.offset_ms) as f64 / elapsed * 1_000_000.0;
+                // Smooth the estimate, as single measurements are noisy
+                let drift = match previous.drift_ppm {
+                    Some(drift) => drift * 0.75 + measured * 0.25,
+                    None => measured,
+                };
+                Some(drift.clamp(-MAX_DRIFT_PPM, MAX_DRIFT_PPM))
+            }
+            Some(previous) => previous.drift_ppm,
+            None => None,
+        };
+
+        info!(
+            "Clock synced from {} source(s): offset {} ms, error {} ms, drift {:?} ppm, {:?}",
+            samples.len(),
+            offset_ms,
+            error_ms,
+            drift_ppm,
+            confidence
+        );
+        *clock = Some(ClockState {
+            offset_ms,
+            synced_at: now,
+            drift_ppm,
+            error_ms,
+            confidence,
+        });
+
+        true
+    }
+}
+
+impl<'a> super::Service<'a> for TimeService {
+    fn name(&self) -> &'static str {
+        "time"
+    }
+
+    fn run(self: Box<Self>, executor: Executor<'a>) -> Pin<Box<dyn Future<Output = ()> + 'a>> {
+        Box::pin(async move {
+            if self.config.servers.is_empty() {
+                warn!("No time servers configured, the wall clock stays unsynced");
+                return;
+            }
+
+            loop {
+                let sync_fut = self.sync().boxed_local();
+                let synced = match select(sync_fut, executor.wait_for_exit().boxed()).await {
+                    Either::Left((synced, _)) => synced,
+                    Either::Right((_, _)) => return,
+                };
+
+                let delay = if synced {
+                    self.config.sync_interval_ms
+                } else {
+                    RETRY_INTERVAL_MS
+                };
+                if let Either::Right(_) =
+                    select(sleep_ms(delay).boxed(), executor.wait_for_exit().boxed()).await
+                {
+                    return;
+                }
+            }
+        })
+    }
+}
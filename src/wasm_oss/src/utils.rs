@@ -1,15 +1,24 @@
+use crate::errors::host_error::HostError;
 use crate::errors::lwip_error::LwipError;
 use crate::ffi;
 use std::net::Ipv4Addr;
 
+pub mod events;
 pub mod logging;
 pub mod msgpack;
 pub mod panic;
+pub mod verify;
+
+/// Longest environment value that is read, enough for a PEM certificate chain
+const MAX_ENV_VALUE_LEN: usize = 32 * 1024;
 
 // Utility functions
-pub fn ip_addr_to_u32(addr: &str) -> Result<u32, LwipError> {
-    let addr: Ipv4Addr = addr.parse().map_err(|_| LwipError::IllegalArgument)?;
-    Ok(u32::from_be_bytes(addr.octets()).to_be())
+pub fn ip_addr_to_u32(addr: Ipv4Addr) -> u32 {
+    u32::from_be_bytes(addr.octets()).to_be()
+}
+
+pub fn u32_to_ip_addr(addr: u32) -> Ipv4Addr {
+    Ipv4Addr::from(u32::from_be(addr))
 }
 
 pub fn sys_print(s: &str) {
@@ -18,27 +27,34 @@ pub fn sys_print(s: &str) {
     }
 }
 
-pub fn sys_get_env(key: &str) -> Result<String, LwipError> {
-    // Allocate a 512 byte buffer for the return value
+pub fn sys_get_env(key: &str) -> Result<String, HostError> {
+    // Allocate a 512 byte buffer for the return value, and grow it for long
+    // values such as PEM certificates
     let mut value = vec![0; 512];
 
-    let result = unsafe {
-        ffi::env_get_env(
-            key.as_ptr(),
-            key.len() as u32,
-            value.as_ptr(),
-            value.len() as u32,
-        )
+    let result = loop {
+        let result = unsafe {
+            ffi::env_get_env(
+                key.as_ptr(),
+                key.len() as u32,
+                value.as_ptr(),
+                value.len() as u32,
+            )
+        };
+        if result != LwipError::Buffer.to_code() || value.len() >= MAX_ENV_VALUE_LEN {
+            break result;
+        }
+        value.resize(value.len() * 4, 0);
     };
 
     // If the result is 0 or negative, return an error
     if result < 0 {
-        return Err(LwipError::IllegalArgument);
+        return Err(HostError::MissingEnv(key.to_string()));
     }
 
     // Convert the buffer to a string, use result as the length
-    let value = String::from_utf8(value[..result as usize].to_vec()).unwrap();
-    Ok(value)
+    String::from_utf8(value[..result as usize].to_vec())
+        .map_err(|_| HostError::InvalidEnv(key.to_string()))
 }
 
 pub fn parse_int(s: &str) -> std::result::Result<u64, std::num::ParseIntError> {
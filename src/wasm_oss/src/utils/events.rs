@@ -0,0 +1,249 @@
+use crate::ffi;
+use log::Level;
+use once_cell::sync::Lazy;
+use serde_json::{json, Value};
+use std::collections::VecDeque;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Mutex;
+use std::task::{Context, Poll, Waker};
+
+/// Number of events kept for clients resuming with `Last-Event-ID`, the oldest are dropped first
+const RING_CAPACITY: usize = 512;
+
+/// Bytes between two upload progress events of the same transfer
+const PROGRESS_STEP: u64 = 1024 * 1024;
+
+static EVENTS: Lazy<Mutex<EventRing>> = Lazy::new(|| {
+    Mutex::new(EventRing {
+        events: VecDeque::with_capacity(RING_CAPACITY),
+        next_id: 1,
+        wakers: Vec::new(),
+    })
+});
+
+struct EventRing {
+    events: VecDeque<Event>,
+    next_id: u64,
+    /// Subscribers waiting for the next event
+    wakers: Vec<Waker>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BootStage {
+    /// A payload is being received
+    Receiving,
+    /// The payload was received and passed verification
+    Verified,
+    /// The payload was rejected, the event carries the reason
+    Rejected,
+    /// The executor exits to boot the loaded payloads
+    Booting,
+    /// The executor exits to chain-load the received image
+    Chainloading,
+}
+
+impl BootStage {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BootStage::Receiving => "receiving",
+            BootStage::Verified => "verified",
+            BootStage::Rejected => "rejected",
+            BootStage::Booting => "booting",
+            BootStage::Chainloading => "chainloading",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum EventKind {
+    Log {
+        level: Level,
+        module: String,
+        message: String,
+    },
+    BootStage {
+        stage: BootStage,
+        /// Command that caused the transition, e.g. `boot` or `chain`
+        source: &'static str,
+        detail: Option<String>,
+    },
+    UploadProgress {
+        source: &'static str,
+        received: u64,
+        total: Option<u64>,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct Event {
+    /// Sequential, starting at 1, so clients can tell which events they missed
+    pub id: u64,
+    /// Monotonic time the event was published at, in ms
+    pub timestamp_ms: u64,
+    pub kind: EventKind,
+}
+
+impl Event {
+    pub fn name(&self) -> &'static str {
+        match self.kind {
+            EventKind::Log { .. } => "log",
+            EventKind::BootStage { .. } => "boot_stage",
+            EventKind::UploadProgress { .. } => "upload_progress",
+        }
+    }
+
+    /// The event as a JSON object
+    pub fn data(&self) -> Value {
+        match &self.kind {
+            EventKind::Log {
+                level,
+                module,
+                message,
+            } => json!({
+                "timestamp_ms": self.timestamp_ms,
+                "level": level.as_str(),
+                "module": module,
+                "message": message,
+            }),
+            EventKind::BootStage {
+                stage,
+                source,
+                detail,
+            } => json!({
+                "timestamp_ms": self.timestamp_ms,
+                "stage": stage.as_str(),
+                "source": source,
+                "detail": detail,
+            }),
+            EventKind::UploadProgress {
+                source,
+                received,
+                total,
+            } => json!({
+                "timestamp_ms": self.timestamp_ms,
+                "source": source,
+                "received": received,
+                "total": total,
+            }),
+        }
+    }
+}
+
+/// Appends an event to the ring and wakes the subscribers.
+///
+/// This is called by the logger, so it must not log itself.
+pub fn publish(kind: EventKind) {
+    let Ok(mut ring) = EVENTS.lock() else {
+        return;
+    };
+
+    if ring.events.len() >= RING_CAPACITY {
+        ring.events.pop_front();
+    }
+    let event = Event {
+        id: ring.next_id,
+        timestamp_ms: unsafe { ffi::env_now() },
+        kind,
+    };
+    ring.next_id += 1;
+    ring.events.push_back(event);
+
+    for waker in ring.wakers.drain(..) {
+        waker.wake();
+    }
+}
+
+pub fn boot_stage(stage: BootStage, source: &'static str, detail: Option<String>) {
+    publish(EventKind::BootStage {
+        stage,
+        source,
+        detail,
+    });
+}
+
+/// Returns the events after `last_id`, all kept events if it is `None`, and
+/// the number of events that were missed because they left the ring
+pub fn since(last_id: Option<u64>) -> (Vec<Event>, u64) {
+    let ring = EVENTS.lock().unwrap();
+    let first_id = ring.next_id - ring.events.len() as u64;
+    let Some(after) = last_id else {
+        return (ring.events.iter().cloned().collect(), 0);
+    };
+
+    let missed = first_id.saturating_sub(after + 1);
+    let skip = (after + 1).saturating_sub(first_id) as usize;
+    (ring.events.iter().skip(skip).cloned().collect(), missed)
+}
+
+/// Id of the latest event, 0 if none was published yet
+pub fn last_id() -> u64 {
+    EVENTS.lock().unwrap().next_id - 1
+}
+
+/// Waits until an event after `last_id` is published
+pub fn wait_after(last_id: u64) -> impl Future<Output = ()> {
+    WaitAfter { last_id }
+}
+
+struct WaitAfter {
+    last_id: u64,
+}
+
+impl Future for WaitAfter {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut ring = EVENTS.lock().unwrap();
+        if ring.next_id > self.last_id + 1 {
+            return Poll::Ready(());
+        }
+        if !ring.wakers.iter().any(|waker| waker.will_wake(cx.waker())) {
+            ring.wakers.push(cx.waker().clone());
+        }
+        Poll::Pending
+    }
+}
+
+/// Publishes the progress of a transfer in steps, rather than for every chunk
+pub struct UploadProgress {
+    source: &'static str,
+    total: Option<u64>,
+    received: u64,
+    reported: u64,
+}
+
+impl UploadProgress {
+    pub fn new(source: &'static str, total: Option<u64>) -> Self {
+        boot_stage(BootStage::Receiving, source, None);
+        Self {
+            source,
+            total,
+            received: 0,
+            reported: 0,
+        }
+    }
+
+    pub fn update(&mut self, len: usize) {
+        self.received += len as u64;
+        if self.received - self.reported >= PROGRESS_STEP {
+            self.report();
+        }
+    }
+
+    /// Reports the final size, if it was not reported yet
+    pub fn finish(&mut self) {
+        if self.received != self.reported {
+            self.report();
+        }
+    }
+
+    fn report(&mut self) {
+        self.reported = self.received;
+        publish(EventKind::UploadProgress {
+            source: self.source,
+            received: self.received,
+            total: self.total,
+        });
+    }
+}
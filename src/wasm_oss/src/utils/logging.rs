@@ -1,8 +1,34 @@
 use crate::ffi;
+use crate::utils::events::{self, EventKind};
 use log::{Level, Log, Metadata, Record, SetLoggerError};
+use once_cell::sync::Lazy;
+use std::collections::VecDeque;
+use std::sync::Mutex;
 
 static LOGGER: Logger = Logger {};
 
+/// Maximum number of records held for forwarding, the oldest are dropped first
+const QUEUE_CAPACITY: usize = 256;
+
+/// Records waiting to be forwarded, `None` while forwarding is disabled
+static FORWARD_QUEUE: Lazy<Mutex<Option<ForwardQueue>>> = Lazy::new(|| Mutex::new(None));
+
+#[derive(Default)]
+struct ForwardQueue {
+    records: VecDeque<QueuedRecord>,
+    dropped: usize,
+}
+
+/// A log record kept for forwarding to a remote collector
+#[derive(Debug, Clone)]
+pub struct QueuedRecord {
+    pub level: Level,
+    /// Monotonic time the record was logged at, in ms
+    pub timestamp_ms: u64,
+    pub module: String,
+    pub message: String,
+}
+
 struct Logger {}
 
 impl Log for Logger {
@@ -24,6 +50,30 @@ impl Log for Logger {
         let res_one_newline = format!("{}\n", res.trim());
         let s = res_one_newline.as_bytes();
         unsafe { ffi::env_print(s.as_ptr(), s.len() as u32) };
+
+        let module = record.module_path().unwrap_or(record.target()).to_string();
+        let message = record.args().to_string();
+
+        if let Ok(mut queue) = FORWARD_QUEUE.lock() {
+            if let Some(queue) = queue.as_mut() {
+                if queue.records.len() >= QUEUE_CAPACITY {
+                    queue.records.pop_front();
+                    queue.dropped += 1;
+                }
+                queue.records.push_back(QueuedRecord {
+                    level: record.level(),
+                    timestamp_ms: unsafe { ffi::env_now() },
+                    module: module.clone(),
+                    message: message.clone(),
+                });
+            }
+        }
+
+        events::publish(EventKind::Log {
+            level: record.level(),
+            module,
+            message,
+        });
     }
 
     fn flush(&self) {}
@@ -34,3 +84,43 @@ pub fn init_with_level(level: Level) -> core::result::Result<(), SetLoggerError>
     log::set_max_level(level.to_level_filter());
     Ok(())
 }
+
+/// Starts queueing records for forwarding, until then they are only printed
+pub fn enable_forwarding() {
+    let mut queue = FORWARD_QUEUE.lock().unwrap();
+    if queue.is_none() {
+        *queue = Some(ForwardQueue::default());
+    }
+}
+
+/// Takes the oldest record waiting to be forwarded
+pub fn pop_forwarded() -> Option<QueuedRecord> {
+    FORWARD_QUEUE.lock().unwrap().as_mut()?.records.pop_front()
+}
+
+/// Puts back a record that could not be forwarded, so it is retried first
+pub fn requeue_forwarded(record: QueuedRecord) {
+    if let Some(queue) = FORWARD_QUEUE.lock().unwrap().as_mut() {
+        if queue.records.len() >= QUEUE_CAPACITY {
+            queue.dropped += 1;
+        } else {
+            queue.records.push_front(record);
+        }
+    }
+}
+
+/// Returns the number of records dropped because the queue was full, and resets it
+pub fn take_dropped() -> usize {
+    FORWARD_QUEUE
+        .lock()
+        .unwrap()
+        .as_mut()
+        .map_or(0, |queue| std::mem::take(&mut queue.dropped))
+}
+
+/// Adds back a dropped count that could not be reported, so it is included in the next notice
+pub fn restore_dropped(count: usize) {
+    if let Some(queue) = FORWARD_QUEUE.lock().unwrap().as_mut() {
+        queue.dropped += count;
+    }
+}
@@ -0,0 +1,70 @@
+use crate::errors::verify_error::VerifyError;
+use sha2::{Digest, Sha256};
+
+/// Checks a payload against its announced size and SHA-256 digest while it
+/// is being received, so oversized payloads are rejected before they are
+/// copied past the end of their buffer.
+pub struct PayloadVerifier {
+    expected_size: Option<u64>,
+    expected_sha256: Option<String>,
+    size: u64,
+    hasher: Sha256,
+}
+
+impl PayloadVerifier {
+    /// Creates a verifier, where an empty `expected_sha256` skips the hash check
+    pub fn new(expected_size: Option<u64>, expected_sha256: &str) -> Self {
+        let expected_sha256 = expected_sha256.trim();
+        Self {
+            expected_size,
+            expected_sha256: (!expected_sha256.is_empty())
+                .then(|| expected_sha256.to_ascii_lowercase()),
+            size: 0,
+            hasher: Sha256::new(),
+        }
+    }
+
+    pub fn expected_size(&self) -> Option<u64> {
+        self.expected_size
+    }
+
+    /// Number of bytes seen so far
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// Accounts for the next chunk of the payload, failing if it would exceed
+    /// the expected size
+    pub fn update(&mut self, bytes: &[u8]) -> Result<(), VerifyError> {
+        let size = self.size + bytes.len() as u64;
+        if let Some(expected) = self.expected_size {
+            if size > expected {
+                return Err(VerifyError::TooLarge { expected });
+            }
+        }
+
+        self.size = size;
+        self.hasher.update(bytes);
+        Ok(())
+    }
+
+    /// Checks the complete payload, returning its hex-encoded SHA-256 digest
+    pub fn finish(self) -> Result<String, VerifyError> {
+        if let Some(expected) = self.expected_size {
+            if self.size != expected {
+                return Err(VerifyError::SizeMismatch {
+                    expected,
+                    actual: self.size,
+                });
+            }
+        }
+
+        let actual = format!("{:x}", self.hasher.finalize());
+        match self.expected_sha256 {
+            Some(expected) if expected != actual => {
+                Err(VerifyError::HashMismatch { expected, actual })
+            }
+            _ => Ok(actual),
+        }
+    }
+}